@@ -0,0 +1,275 @@
+use std::str::FromStr;
+
+use actix_web::{web, HttpRequest, HttpResponse};
+use common::{
+    establish_connection,
+    models::{
+        BannedPhrase, BannedPhraseAction, BannedPhraseKind, Channel, LevelOfRights,
+        NewBannedPhrase, User,
+    },
+    schema::{
+        banned_phrases::dsl as bp, channels::dsl as ch, rights::dsl as ri, user_tokens::dsl as ut,
+        users::dsl as us,
+    },
+};
+use diesel::{
+    delete, insert_into, ExpressionMethods, OptionalExtension, PgConnection, QueryDsl, RunQueryDsl,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::Response;
+
+/// The longest timeout Twitch allows, in seconds.
+const MAX_TIMEOUT_SEC: i32 = 14 * 24 * 60 * 60;
+
+pub async fn get_banned_phrases(id: web::Path<i32>, request: HttpRequest) -> HttpResponse {
+    let conn = &mut establish_connection();
+
+    let channel = match authorize_moderator(conn, &request, *id) {
+        Ok(v) => v,
+        Err(e) => return e,
+    };
+
+    match bp::banned_phrases
+        .filter(bp::channel_id.eq(&channel.id))
+        .order(bp::id.asc())
+        .get_results::<BannedPhrase>(conn)
+    {
+        Ok(v) => HttpResponse::Ok().json(Response {
+            status_code: 200,
+            message: None,
+            data: Some(v),
+        }),
+        Err(_) => HttpResponse::InternalServerError().json(Response {
+            status_code: 500,
+            message: Some("Failed to get the banned phrases.".to_string()),
+            data: None::<Vec<BannedPhrase>>,
+        }),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct BannedPhraseRequest {
+    pub pattern: String,
+    /// `text`, `wildcard` or `regex`. Defaults to `text`.
+    pub kind: Option<String>,
+    /// `delete`, `timeout` or `ban`. Defaults to `delete`.
+    pub action: Option<String>,
+    /// The base timeout duration, used only by the `timeout` action.
+    pub timeout_sec: Option<i32>,
+}
+
+pub async fn add_banned_phrase(
+    id: web::Path<i32>,
+    body: web::Json<BannedPhraseRequest>,
+    request: HttpRequest,
+) -> HttpResponse {
+    let conn = &mut establish_connection();
+
+    let channel = match authorize_moderator(conn, &request, *id) {
+        Ok(v) => v,
+        Err(e) => return e,
+    };
+
+    let pattern = body.pattern.trim().to_string();
+
+    if pattern.is_empty() {
+        return HttpResponse::BadRequest().json(Response {
+            status_code: 400,
+            message: Some("The pattern must not be empty.".to_string()),
+            data: None::<BannedPhrase>,
+        });
+    }
+
+    let kind = match body.kind.as_deref().map(BannedPhraseKind::from_str) {
+        Some(Ok(v)) => v,
+        Some(Err(_)) => {
+            return HttpResponse::BadRequest().json(Response {
+                status_code: 400,
+                message: Some("Unknown banned phrase kind.".to_string()),
+                data: None::<BannedPhrase>,
+            })
+        }
+        None => BannedPhraseKind::Text,
+    };
+
+    let action = match body.action.as_deref().map(BannedPhraseAction::from_str) {
+        Some(Ok(v)) => v,
+        Some(Err(_)) => {
+            return HttpResponse::BadRequest().json(Response {
+                status_code: 400,
+                message: Some("Unknown banned phrase action.".to_string()),
+                data: None::<BannedPhrase>,
+            })
+        }
+        None => BannedPhraseAction::Delete,
+    };
+
+    let timeout_sec = match (&action, body.timeout_sec) {
+        (BannedPhraseAction::Timeout, Some(v)) if v <= 0 || v > MAX_TIMEOUT_SEC => {
+            return HttpResponse::BadRequest().json(Response {
+                status_code: 400,
+                message: Some(format!(
+                    "The timeout must be between 1 and {} seconds.",
+                    MAX_TIMEOUT_SEC
+                )),
+                data: None::<BannedPhrase>,
+            })
+        }
+        (BannedPhraseAction::Timeout, v) => v,
+        _ => None,
+    };
+
+    if let Err(e) = kind.compile(&pattern) {
+        return HttpResponse::BadRequest().json(Response {
+            status_code: 400,
+            message: Some(format!("Invalid pattern: {}", e)),
+            data: None::<BannedPhrase>,
+        });
+    }
+
+    match insert_into(bp::banned_phrases)
+        .values([NewBannedPhrase {
+            channel_id: channel.id,
+            pattern,
+            kind,
+            action,
+            timeout_sec,
+        }])
+        .on_conflict_do_nothing()
+        .get_result::<BannedPhrase>(conn)
+        .optional()
+    {
+        Ok(Some(v)) => HttpResponse::Ok().json(Response {
+            status_code: 200,
+            message: None,
+            data: Some(v),
+        }),
+        Ok(None) => HttpResponse::Conflict().json(Response {
+            status_code: 409,
+            message: Some("The banned phrase already exists.".to_string()),
+            data: None::<BannedPhrase>,
+        }),
+        Err(_) => HttpResponse::InternalServerError().json(Response {
+            status_code: 500,
+            message: Some("Failed to create the banned phrase.".to_string()),
+            data: None::<BannedPhrase>,
+        }),
+    }
+}
+
+pub async fn delete_banned_phrase(
+    path: web::Path<(i32, i32)>,
+    request: HttpRequest,
+) -> HttpResponse {
+    let (channel_id, phrase_id) = path.into_inner();
+    let conn = &mut establish_connection();
+
+    let channel = match authorize_moderator(conn, &request, channel_id) {
+        Ok(v) => v,
+        Err(e) => return e,
+    };
+
+    match delete(
+        bp::banned_phrases
+            .filter(bp::id.eq(&phrase_id))
+            .filter(bp::channel_id.eq(&channel.id)),
+    )
+    .get_result::<BannedPhrase>(conn)
+    .optional()
+    {
+        Ok(Some(v)) => HttpResponse::Ok().json(Response {
+            status_code: 200,
+            message: None,
+            data: Some(v),
+        }),
+        Ok(None) => HttpResponse::NotFound().json(Response {
+            status_code: 404,
+            message: Some(format!("Banned phrase ID {} not found.", phrase_id)),
+            data: None::<BannedPhrase>,
+        }),
+        Err(_) => HttpResponse::InternalServerError().json(Response {
+            status_code: 500,
+            message: Some("Failed to delete the banned phrase.".to_string()),
+            data: None::<BannedPhrase>,
+        }),
+    }
+}
+
+/// Gets the channel if the authorized user is at least its moderator.
+/// The patterns themselves are offensive, so even listing them requires the rights.
+fn authorize_moderator(
+    conn: &mut PgConnection,
+    request: &HttpRequest,
+    channel_id: i32,
+) -> Result<Channel, HttpResponse> {
+    let token = match request
+        .headers()
+        .get("Authorization")
+        .and_then(|x| x.to_str().ok())
+        .and_then(|x| Uuid::parse_str(x).ok())
+    {
+        Some(v) => v,
+        None => {
+            return Err(HttpResponse::Unauthorized().json(Response {
+                status_code: 401,
+                message: Some("Please provide a valid authorization key.".to_string()),
+                data: None::<BannedPhrase>,
+            }))
+        }
+    };
+
+    let user: User = match ut::user_tokens
+        .filter(ut::token.eq(&token))
+        .select(ut::user_id)
+        .get_result::<i32>(conn)
+        .and_then(|x| us::users.find(x).get_result::<User>(conn))
+    {
+        Ok(v) => v,
+        Err(_) => {
+            return Err(HttpResponse::Unauthorized().json(Response {
+                status_code: 401,
+                message: Some(format!(
+                    "The provided authorization key (\"{}\") does not exist or has expired.",
+                    token.as_simple()
+                )),
+                data: None::<BannedPhrase>,
+            }))
+        }
+    };
+
+    let channel: Channel = match ch::channels.find(&channel_id).get_result::<Channel>(conn) {
+        Ok(v) => v,
+        Err(_) => {
+            return Err(HttpResponse::NotFound().json(Response {
+                status_code: 404,
+                message: Some(format!("Channel ID {} not found.", channel_id)),
+                data: None::<BannedPhrase>,
+            }))
+        }
+    };
+
+    let level = if user.is_admin {
+        LevelOfRights::Admin
+    } else if user.alias_id == channel.alias_id {
+        LevelOfRights::Broadcaster
+    } else {
+        ri::rights
+            .filter(ri::user_id.eq(&user.id))
+            .filter(ri::channel_id.eq(&channel.id))
+            .select(ri::level)
+            .get_result::<LevelOfRights>(conn)
+            .unwrap_or(LevelOfRights::User)
+    };
+
+    if level < LevelOfRights::Moderator {
+        return Err(HttpResponse::Forbidden().json(Response {
+            status_code: 403,
+            message: Some("You can't manage the banned phrases of this channel.".to_string()),
+            data: None::<BannedPhrase>,
+        }));
+    }
+
+    Ok(channel)
+}
@@ -1,9 +1,28 @@
-use actix_web::{web, HttpResponse};
-use common::{establish_connection, models::Event, schema::events::dsl as ev};
-use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
+use actix_web::{web, HttpRequest, HttpResponse};
+use common::{
+    establish_connection,
+    models::{
+        Channel, Event, EventRun, EventTrigger, EventType, LevelOfRights, NewEventTrigger, Right,
+        User,
+    },
+    schema::{
+        channels::dsl as ch, event_runs::dsl as er, event_triggers::dsl as et, events::dsl as ev,
+        rights::dsl as ri, user_tokens::dsl as ut, users::dsl as us,
+    },
+};
+use diesel::{
+    insert_into, BelongingToDsl, ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl,
+};
+use serde::Deserialize;
+use uuid::Uuid;
 
 use crate::Response;
 
+/// The most parameters a trigger can have, since they are spliced into chat messages.
+const MAX_TRIGGER_PARAMETERS: usize = 10;
+/// The longest total length of the trigger parameters, in characters.
+const MAX_TRIGGER_PARAMETERS_LENGTH: usize = 300;
+
 pub async fn get_channel_events(id: web::Path<i32>) -> HttpResponse {
     let conn = &mut establish_connection();
 
@@ -23,3 +42,192 @@ pub async fn get_channel_events(id: web::Path<i32>) -> HttpResponse {
         }),
     }
 }
+
+pub async fn get_channel_event_history(path: web::Path<(i32, i32)>) -> HttpResponse {
+    let (channel_id, event_id) = path.into_inner();
+    let conn = &mut establish_connection();
+
+    let event: Event = match ev::events
+        .find(&event_id)
+        .filter(ev::channel_id.eq(&channel_id))
+        .get_result::<Event>(conn)
+    {
+        Ok(v) => v,
+        Err(_) => {
+            return HttpResponse::NotFound().json(Response {
+                status_code: 404,
+                message: Some(format!("Event ID {} not found.", event_id)),
+                data: None::<Vec<EventRun>>,
+            })
+        }
+    };
+
+    match EventRun::belonging_to(&event)
+        .order(er::created_at.desc())
+        .get_results::<EventRun>(conn)
+    {
+        Ok(v) => HttpResponse::Ok().json(Response {
+            status_code: 200,
+            message: None,
+            data: Some(v),
+        }),
+        Err(_) => HttpResponse::InternalServerError().json(Response {
+            status_code: 500,
+            message: Some("Failed to get the event history.".to_string()),
+            data: None::<Vec<EventRun>>,
+        }),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct EventTriggerRequest {
+    #[serde(default)]
+    pub parameters: Vec<String>,
+}
+
+pub async fn trigger_channel_event(
+    path: web::Path<(i32, i32)>,
+    body: Option<web::Json<EventTriggerRequest>>,
+    request: HttpRequest,
+) -> HttpResponse {
+    let (channel_id, event_id) = path.into_inner();
+
+    let token = match request
+        .headers()
+        .get("Authorization")
+        .and_then(|x| x.to_str().ok())
+        .and_then(|x| Uuid::parse_str(x).ok())
+    {
+        Some(v) => v,
+        None => {
+            return HttpResponse::Unauthorized().json(Response {
+                status_code: 401,
+                message: Some("Please provide a valid authorization key.".to_string()),
+                data: None::<EventTrigger>,
+            })
+        }
+    };
+
+    let conn = &mut establish_connection();
+
+    let user: User = match ut::user_tokens
+        .filter(ut::token.eq(&token))
+        .select(ut::user_id)
+        .get_result::<i32>(conn)
+        .and_then(|x| us::users.find(x).get_result::<User>(conn))
+    {
+        Ok(v) => v,
+        Err(_) => {
+            return HttpResponse::Unauthorized().json(Response {
+                status_code: 401,
+                message: Some(format!(
+                    "The provided authorization key (\"{}\") does not exist or has expired.",
+                    token.as_simple()
+                )),
+                data: None::<EventTrigger>,
+            })
+        }
+    };
+
+    let channel: Channel = match ch::channels.find(&channel_id).get_result::<Channel>(conn) {
+        Ok(v) => v,
+        Err(_) => {
+            return HttpResponse::NotFound().json(Response {
+                status_code: 404,
+                message: Some(format!("Channel ID {} not found.", channel_id)),
+                data: None::<EventTrigger>,
+            })
+        }
+    };
+
+    let level = if user.is_admin {
+        LevelOfRights::Admin
+    } else if user.alias_id == channel.alias_id {
+        LevelOfRights::Broadcaster
+    } else {
+        match ri::rights
+            .filter(ri::user_id.eq(&user.id))
+            .filter(ri::channel_id.eq(&channel.id))
+            .get_result::<Right>(conn)
+            .optional()
+        {
+            Ok(Some(v)) => v.level,
+            _ => LevelOfRights::User,
+        }
+    };
+
+    if level < LevelOfRights::Moderator {
+        return HttpResponse::Forbidden().json(Response {
+            status_code: 403,
+            message: Some("You are not a moderator of this channel.".to_string()),
+            data: None::<EventTrigger>,
+        });
+    }
+
+    let event: Event = match ev::events
+        .find(&event_id)
+        .filter(ev::channel_id.eq(&channel.id))
+        .get_result::<Event>(conn)
+    {
+        Ok(v) => v,
+        Err(_) => {
+            return HttpResponse::NotFound().json(Response {
+                status_code: 404,
+                message: Some(format!("Event ID {} not found.", event_id)),
+                data: None::<EventTrigger>,
+            })
+        }
+    };
+
+    if event.event_type != EventType::Custom {
+        return HttpResponse::BadRequest().json(Response {
+            status_code: 400,
+            message: Some("Only custom events can be triggered.".to_string()),
+            data: None::<EventTrigger>,
+        });
+    }
+
+    if !event.is_enabled {
+        return HttpResponse::Conflict().json(Response {
+            status_code: 409,
+            message: Some("The event is disabled.".to_string()),
+            data: None::<EventTrigger>,
+        });
+    }
+
+    let parameters = body.map(|x| x.into_inner().parameters).unwrap_or_default();
+
+    if parameters.len() > MAX_TRIGGER_PARAMETERS
+        || parameters.iter().map(|x| x.chars().count()).sum::<usize>()
+            > MAX_TRIGGER_PARAMETERS_LENGTH
+    {
+        return HttpResponse::BadRequest().json(Response {
+            status_code: 400,
+            message: Some(format!(
+                "Up to {} parameters with a total length of {} characters are allowed.",
+                MAX_TRIGGER_PARAMETERS, MAX_TRIGGER_PARAMETERS_LENGTH
+            )),
+            data: None::<EventTrigger>,
+        });
+    }
+
+    match insert_into(et::event_triggers)
+        .values([NewEventTrigger {
+            event_id: event.id,
+            user_id: user.id,
+            parameters,
+        }])
+        .get_result::<EventTrigger>(conn)
+    {
+        Ok(v) => HttpResponse::Accepted().json(Response {
+            status_code: 202,
+            message: Some("The event will be fired soon!".to_string()),
+            data: Some(v),
+        }),
+        Err(_) => HttpResponse::InternalServerError().json(Response {
+            status_code: 500,
+            message: Some("Failed to create an event trigger.".to_string()),
+            data: None::<EventTrigger>,
+        }),
+    }
+}
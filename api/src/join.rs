@@ -4,8 +4,14 @@ use actix_web::{web, HttpRequest, HttpResponse};
 use chrono::NaiveDateTime;
 use common::{
     establish_connection,
-    models::{Channel, NewChannel, Session, User, UserToken as ClientToken},
-    schema::{channels::dsl as ch, sessions::dsl as se, user_tokens::dsl as ut, users::dsl as us},
+    models::{
+        Channel, ChannelUpdateAction, NewChannel, NewChannelUpdate, Session, User,
+        UserToken as ClientToken,
+    },
+    schema::{
+        channel_updates::dsl as chu, channels::dsl as ch, sessions::dsl as se,
+        user_tokens::dsl as ut, users::dsl as us,
+    },
 };
 use diesel::{
     insert_into, update, ExpressionMethods, PgArrayExpressionMethods, QueryDsl, RunQueryDsl,
@@ -197,11 +203,22 @@ pub async fn join_channel(body: web::Json<JoinRequest>, request: HttpRequest) ->
         Ok(mut v) => {
             if v.opt_outed_at.is_some() {
                 match update(ch::channels.find(&v.id))
-                    .set(ch::opt_outed_at.eq(None::<NaiveDateTime>))
+                    .set((
+                        ch::opt_outed_at.eq(None::<NaiveDateTime>),
+                        ch::purge_at.eq(None::<NaiveDateTime>),
+                    ))
                     .execute(conn)
-                {
+                    .and_then(|_| {
+                        insert_into(chu::channel_updates)
+                            .values([NewChannelUpdate {
+                                channel_id: v.id,
+                                action: ChannelUpdateAction::Join,
+                            }])
+                            .execute(conn)
+                    }) {
                     Ok(_) => {
                         v.opt_outed_at = None;
+                        v.purge_at = None;
 
                         return HttpResponse::Ok().json(Response {
                             status_code: 200,
@@ -234,7 +251,15 @@ pub async fn join_channel(body: web::Json<JoinRequest>, request: HttpRequest) ->
                     alias_name: login.clone(),
                 }])
                 .get_result::<Channel>(conn)
-            {
+                .and_then(|v| {
+                    insert_into(chu::channel_updates)
+                        .values([NewChannelUpdate {
+                            channel_id: v.id,
+                            action: ChannelUpdateAction::Join,
+                        }])
+                        .execute(conn)
+                        .map(|_| v)
+                }) {
                 Ok(v) => HttpResponse::Ok().json(Response {
                     status_code: 200,
                     message: Some(
@@ -1,4 +1,7 @@
-use crate::{auth::*, channels::*, commands::*, customcommands::*, events::*, join::*, users::*};
+use crate::{
+    auth::*, bannedphrases::*, channels::*, commands::*, customcommands::*, events::*, join::*,
+    part::*, rights::*, users::*,
+};
 use std::io::Result;
 
 use actix_web::{web, App, HttpServer};
@@ -6,11 +9,14 @@ use dotenvy::dotenv;
 use serde::{Deserialize, Serialize};
 
 mod auth;
+mod bannedphrases;
 mod channels;
 mod commands;
 mod customcommands;
 mod events;
 mod join;
+mod part;
+mod rights;
 mod users;
 
 #[derive(Deserialize, Serialize)]
@@ -41,13 +47,32 @@ async fn main() -> Result<()> {
                     web::scope("/channels")
                         .service(web::resource("").get(get_channels))
                         .service(web::resource("/alias_id/{name}").get(get_channels_by_alias_ids))
-                        .service(web::resource("/join").post(join_channel)),
+                        .service(web::resource("/join").post(join_channel))
+                        .service(web::resource("/part").post(part_channel)),
                 )
                 .service(
                     web::scope("/channel/{id}")
                         .service(web::resource("").get(get_channel_by_id))
                         .service(web::resource("/events").get(get_channel_events))
-                        .service(web::resource("/custom-commands").get(get_custom_commands)),
+                        .service(
+                            web::resource("/events/{event_id}/history")
+                                .get(get_channel_event_history),
+                        )
+                        .service(
+                            web::resource("/events/{event_id}/trigger").post(trigger_channel_event),
+                        )
+                        .service(web::resource("/custom-commands").get(get_custom_commands))
+                        .service(web::resource("/rights").get(get_channel_rights))
+                        .service(web::resource("/rights/{user_id}").put(set_channel_rights))
+                        .service(
+                            web::resource("/banned-phrases")
+                                .get(get_banned_phrases)
+                                .post(add_banned_phrase),
+                        )
+                        .service(
+                            web::resource("/banned-phrases/{phrase_id}")
+                                .delete(delete_banned_phrase),
+                        ),
                 )
                 .service(
                     web::scope("/user")
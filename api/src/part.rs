@@ -0,0 +1,124 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::{Duration, NaiveDateTime, Utc};
+use common::{
+    establish_connection,
+    models::{Channel, ChannelUpdateAction, NewChannelUpdate, User},
+    schema::{
+        channel_updates::dsl as chu, channels::dsl as ch, user_tokens::dsl as ut, users::dsl as us,
+    },
+    CHANNEL_PURGE_GRACE_PERIOD_SEC,
+};
+use diesel::{insert_into, update, ExpressionMethods, QueryDsl, RunQueryDsl};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::Response;
+
+#[derive(Deserialize)]
+pub struct PartRequest {
+    pub alias_id: i32,
+    #[serde(default)]
+    pub purge: bool,
+}
+
+pub async fn part_channel(body: web::Json<PartRequest>, request: HttpRequest) -> HttpResponse {
+    let token = match request
+        .headers()
+        .get("Authorization")
+        .and_then(|x| x.to_str().ok())
+        .and_then(|x| Uuid::parse_str(x).ok())
+    {
+        Some(v) => v,
+        None => {
+            return HttpResponse::Unauthorized().json(Response {
+                status_code: 401,
+                message: Some("Please provide a valid authorization key.".to_string()),
+                data: None::<Channel>,
+            })
+        }
+    };
+
+    let conn = &mut establish_connection();
+
+    let user: User = match ut::user_tokens
+        .filter(ut::token.eq(&token))
+        .select(ut::user_id)
+        .get_result::<i32>(conn)
+        .and_then(|x| us::users.find(x).get_result::<User>(conn))
+    {
+        Ok(v) => v,
+        Err(_) => {
+            return HttpResponse::Unauthorized().json(Response {
+                status_code: 401,
+                message: Some(format!(
+                    "The provided authorization key (\"{}\") does not exist or has expired.",
+                    token.as_simple()
+                )),
+                data: None::<Channel>,
+            })
+        }
+    };
+
+    if user.alias_id.ne(&body.alias_id) && !user.is_admin {
+        return HttpResponse::Forbidden().json(Response {
+            status_code: 403,
+            message: Some("Only the broadcaster can make the bot leave the channel.".to_string()),
+            data: None::<Channel>,
+        });
+    }
+
+    let channel: Channel = match ch::channels
+        .filter(ch::alias_id.eq(&body.alias_id))
+        .get_result::<Channel>(conn)
+    {
+        Ok(v) if v.opt_outed_at.is_none() => v,
+        Ok(v) => {
+            return HttpResponse::Conflict().json(Response {
+                status_code: 409,
+                message: Some("Already left this channel!".to_string()),
+                data: Some(v),
+            })
+        }
+        Err(_) => {
+            return HttpResponse::NotFound().json(Response {
+                status_code: 404,
+                message: Some(format!(
+                    "Channel with alias ID {} not found.",
+                    body.alias_id
+                )),
+                data: None::<Channel>,
+            })
+        }
+    };
+
+    let now = Utc::now().naive_utc();
+    let purge_at = if body.purge {
+        Some(now + Duration::seconds(CHANNEL_PURGE_GRACE_PERIOD_SEC))
+    } else {
+        None::<NaiveDateTime>
+    };
+
+    match update(ch::channels.find(&channel.id))
+        .set((ch::opt_outed_at.eq(Some(now)), ch::purge_at.eq(purge_at)))
+        .get_result::<Channel>(conn)
+        .and_then(|v| {
+            insert_into(chu::channel_updates)
+                .values([NewChannelUpdate {
+                    channel_id: v.id,
+                    action: ChannelUpdateAction::Part,
+                }])
+                .execute(conn)
+                .map(|_| v)
+        }) {
+        Ok(v) => HttpResponse::Ok().json(Response {
+            status_code: 200,
+            message: Some("The bot will be leaving this channel soon!".to_string()),
+            data: Some(v),
+        }),
+        Err(_) => HttpResponse::InternalServerError().json(Response {
+            status_code: 500,
+            message: Some("Failed to set the 'opt_outed_at' field.".to_string()),
+            data: Some(channel),
+        }),
+    }
+}
@@ -0,0 +1,210 @@
+use std::str::FromStr;
+
+use actix_web::{web, HttpRequest, HttpResponse};
+use common::{
+    establish_connection,
+    models::{Channel, LevelOfRights, NewRight, Right, User},
+    schema::{channels::dsl as ch, rights::dsl as ri, user_tokens::dsl as ut, users::dsl as us},
+};
+use diesel::{
+    insert_into, update, ExpressionMethods, OptionalExtension, PgConnection, QueryDsl, QueryResult,
+    RunQueryDsl,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::Response;
+
+pub async fn get_channel_rights(id: web::Path<i32>) -> HttpResponse {
+    let conn = &mut establish_connection();
+
+    match ri::rights
+        .filter(ri::channel_id.eq(&*id))
+        .get_results::<Right>(conn)
+    {
+        Ok(v) => HttpResponse::Ok().json(Response {
+            status_code: 200,
+            message: None,
+            data: Some(v),
+        }),
+        Err(_) => HttpResponse::NotFound().json(Response {
+            status_code: 404,
+            message: None,
+            data: None::<Vec<Right>>,
+        }),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RightsRequest {
+    /// The level to pin, or nothing to let it be recalculated from badges again.
+    pub level: Option<String>,
+}
+
+pub async fn set_channel_rights(
+    path: web::Path<(i32, i32)>,
+    body: web::Json<RightsRequest>,
+    request: HttpRequest,
+) -> HttpResponse {
+    let (channel_id, user_id) = path.into_inner();
+
+    let token = match request
+        .headers()
+        .get("Authorization")
+        .and_then(|x| x.to_str().ok())
+        .and_then(|x| Uuid::parse_str(x).ok())
+    {
+        Some(v) => v,
+        None => {
+            return HttpResponse::Unauthorized().json(Response {
+                status_code: 401,
+                message: Some("Please provide a valid authorization key.".to_string()),
+                data: None::<Right>,
+            })
+        }
+    };
+
+    let conn = &mut establish_connection();
+
+    let user: User = match ut::user_tokens
+        .filter(ut::token.eq(&token))
+        .select(ut::user_id)
+        .get_result::<i32>(conn)
+        .and_then(|x| us::users.find(x).get_result::<User>(conn))
+    {
+        Ok(v) => v,
+        Err(_) => {
+            return HttpResponse::Unauthorized().json(Response {
+                status_code: 401,
+                message: Some(format!(
+                    "The provided authorization key (\"{}\") does not exist or has expired.",
+                    token.as_simple()
+                )),
+                data: None::<Right>,
+            })
+        }
+    };
+
+    let channel: Channel = match ch::channels.find(&channel_id).get_result::<Channel>(conn) {
+        Ok(v) => v,
+        Err(_) => {
+            return HttpResponse::NotFound().json(Response {
+                status_code: 404,
+                message: Some(format!("Channel ID {} not found.", channel_id)),
+                data: None::<Right>,
+            })
+        }
+    };
+
+    let target: User = match us::users.find(&user_id).get_result::<User>(conn) {
+        Ok(v) => v,
+        Err(_) => {
+            return HttpResponse::NotFound().json(Response {
+                status_code: 404,
+                message: Some(format!("User ID {} not found.", user_id)),
+                data: None::<Right>,
+            })
+        }
+    };
+
+    let new_level = match body.level.as_deref().map(LevelOfRights::from_str) {
+        Some(Ok(v)) => Some(v),
+        Some(Err(_)) => {
+            return HttpResponse::BadRequest().json(Response {
+                status_code: 400,
+                message: Some("Unknown level of rights.".to_string()),
+                data: None::<Right>,
+            })
+        }
+        None => None,
+    };
+
+    let level = if user.is_admin {
+        LevelOfRights::Admin
+    } else if user.alias_id == channel.alias_id {
+        LevelOfRights::Broadcaster
+    } else {
+        match get_rights(conn, user.id, channel.id) {
+            Ok(Some(v)) => v.level,
+            _ => LevelOfRights::User,
+        }
+    };
+
+    let rights = match get_rights(conn, target.id, channel.id) {
+        Ok(v) => v,
+        Err(_) => {
+            return HttpResponse::InternalServerError().json(Response {
+                status_code: 500,
+                message: Some("Failed to get the user's rights.".to_string()),
+                data: None::<Right>,
+            })
+        }
+    };
+
+    let target_level = rights
+        .as_ref()
+        .map(|x| x.level.clone())
+        .unwrap_or(LevelOfRights::User);
+
+    // nobody can manage the broadcaster, admins and users of the same or higher level
+    if level < LevelOfRights::Moderator
+        || target.alias_id == channel.alias_id
+        || target.is_admin
+        || target_level >= level
+        || new_level
+            .as_ref()
+            .is_some_and(|x| *x >= level || *x >= LevelOfRights::Broadcaster)
+    {
+        return HttpResponse::Forbidden().json(Response {
+            status_code: 403,
+            message: Some("You can't manage the rights of this user.".to_string()),
+            data: None::<Right>,
+        });
+    }
+
+    let is_fixed = new_level.is_some();
+    let new_level = new_level.unwrap_or(LevelOfRights::User);
+
+    let result = match rights {
+        Some(v) => update(ri::rights.find(&v.id))
+            .set((ri::level.eq(&new_level), ri::is_fixed.eq(is_fixed)))
+            .get_result::<Right>(conn),
+        None => insert_into(ri::rights)
+            .values([NewRight {
+                user_id: target.id,
+                channel_id: channel.id,
+                level: new_level,
+            }])
+            .get_result::<Right>(conn)
+            .and_then(|v| {
+                update(ri::rights.find(&v.id))
+                    .set(ri::is_fixed.eq(is_fixed))
+                    .get_result::<Right>(conn)
+            }),
+    };
+
+    match result {
+        Ok(v) => HttpResponse::Ok().json(Response {
+            status_code: 200,
+            message: None,
+            data: Some(v),
+        }),
+        Err(_) => HttpResponse::InternalServerError().json(Response {
+            status_code: 500,
+            message: Some("Failed to update the user's rights.".to_string()),
+            data: None::<Right>,
+        }),
+    }
+}
+
+fn get_rights(
+    conn: &mut PgConnection,
+    user_id: i32,
+    channel_id: i32,
+) -> QueryResult<Option<Right>> {
+    ri::rights
+        .filter(ri::user_id.eq(&user_id))
+        .filter(ri::channel_id.eq(&channel_id))
+        .get_result::<Right>(conn)
+        .optional()
+}
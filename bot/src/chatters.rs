@@ -0,0 +1,146 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use common::{
+    models::Channel,
+    schema::{channel_preferences::dsl as chp, user_preferences::dsl as up, users::dsl as us},
+};
+use diesel::{ExpressionMethods, OptionalExtension, PgConnection, QueryDsl, RunQueryDsl};
+use twitch_api::helix::chat::GetChattersRequest;
+use twitch_irc::message::PrivmsgMessage;
+
+use crate::{
+    commands::response::ResponseError,
+    instance_bundle::InstanceBundle,
+    shared_variables::{ACTIVE_CHATTERS_DEFAULT_WINDOW_SEC, ACTIVE_CHATTERS_MAX_WINDOW_SEC},
+};
+
+/// Recently active chatters by channel alias ID: user alias ID -> (login, last message time).
+pub type ActiveChatters = HashMap<i32, HashMap<i32, (String, Instant)>>;
+
+/// Remembers the sender of the message as an active chatter of the channel.
+pub async fn record_active_chatter(instance_bundle: &InstanceBundle, message: &PrivmsgMessage) {
+    let (channel_id, user_id) = match (
+        message.channel_id.parse::<i32>(),
+        message.sender.id.parse::<i32>(),
+    ) {
+        (Ok(x), Ok(y)) => (x, y),
+        _ => return,
+    };
+
+    let mut active_chatters = instance_bundle.active_chatters.lock().await;
+    let chatters = active_chatters.entry(channel_id).or_default();
+    let now = Instant::now();
+
+    chatters.retain(|_, (_, timestamp)| {
+        now.duration_since(*timestamp) <= Duration::from_secs(ACTIVE_CHATTERS_MAX_WINDOW_SEC)
+    });
+
+    chatters.insert(user_id, (message.sender.login.clone(), now));
+}
+
+/// Returns logins of the chatters to be mentioned in a mass ping,
+/// skipping opted out users and the channel's exclusion list.
+///
+/// If the active window is set, only the chatters who have sent a message within it are taken.
+/// Otherwise, the full chatter list is requested from Twitch, falling back to
+/// the active chatters if the bot is not a moderator.
+pub async fn get_massping_chatters(
+    conn: &mut PgConnection,
+    instance_bundle: &InstanceBundle,
+    channel: &Channel,
+    active_window_sec: Option<u64>,
+) -> Result<Vec<String>, ResponseError> {
+    let bot_id = instance_bundle.twitch_api_token.user_id.clone().take();
+
+    let chatters: Vec<(i32, String)> = match active_window_sec {
+        Some(window) => get_active_chatters(instance_bundle, channel, window).await,
+        None => match instance_bundle
+            .twitch_api_client
+            .req_get(
+                GetChattersRequest::new(channel.alias_id.to_string(), bot_id.clone()),
+                &*instance_bundle.twitch_api_token,
+            )
+            .await
+        {
+            Ok(response) => response
+                .data
+                .iter()
+                .filter_map(|x| {
+                    x.user_id
+                        .as_str()
+                        .parse::<i32>()
+                        .ok()
+                        .map(|id| (id, x.user_login.to_string()))
+                })
+                .collect(),
+            Err(_) => {
+                get_active_chatters(instance_bundle, channel, ACTIVE_CHATTERS_DEFAULT_WINDOW_SEC)
+                    .await
+            }
+        },
+    };
+
+    if chatters.is_empty() && active_window_sec.is_none() {
+        return Err(ResponseError::InsufficientRights);
+    }
+
+    Ok(filter_massping_chatters(conn, channel, &bot_id, chatters))
+}
+
+async fn get_active_chatters(
+    instance_bundle: &InstanceBundle,
+    channel: &Channel,
+    window_sec: u64,
+) -> Vec<(i32, String)> {
+    let active_chatters = instance_bundle.active_chatters.lock().await;
+    let now = Instant::now();
+
+    match active_chatters.get(&channel.alias_id) {
+        Some(chatters) => chatters
+            .iter()
+            .filter(|(_, (_, timestamp))| {
+                now.duration_since(*timestamp) <= Duration::from_secs(window_sec)
+            })
+            .map(|(id, (login, _))| (*id, login.clone()))
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+fn filter_massping_chatters(
+    conn: &mut PgConnection,
+    channel: &Channel,
+    bot_id: &str,
+    chatters: Vec<(i32, String)>,
+) -> Vec<String> {
+    let exclusions = chp::channel_preferences
+        .filter(chp::channel_id.eq(&channel.id))
+        .select(chp::massping_exclusions)
+        .first::<Vec<String>>(conn)
+        .optional()
+        .expect("Failed to get massping exclusions")
+        .unwrap_or_default();
+
+    let alias_ids = chatters.iter().map(|x| x.0).collect::<Vec<i32>>();
+
+    let opted_out_ids = us::users
+        .inner_join(up::user_preferences)
+        .filter(up::is_massping_opted_out.eq(true))
+        .filter(us::alias_id.eq_any(&alias_ids))
+        .select(us::alias_id)
+        .load::<i32>(conn)
+        .expect("Failed to get users opted out of mass pings");
+
+    chatters
+        .into_iter()
+        .filter(|(id, login)| {
+            id.to_string() != bot_id
+                && !opted_out_ids.contains(id)
+                && !exclusions.contains(&login.to_lowercase())
+        })
+        .map(|(_, login)| login)
+        .collect()
+}
@@ -2,11 +2,14 @@ use crate::{
     instance_bundle::InstanceBundle,
     localization::LineId,
     modules::{
-        custom_command::CustomCommandsCommand, ecount::EmoteCountCommand,
+        admin::AdminCommand, custom_command::CustomCommandsCommand, ecount::EmoteCountCommand,
+        ediff::EmoteDiffCommand, ehistory::EmoteHistoryCommand, emote::EmoteCommand,
         esim::EmoteSimilarityCommand, etop::EmoteTopCommand, event::EventCommand,
-        help::HelpCommand, holiday::HolidayCommand, join::JoinCommand, massping::MasspingCommand,
-        mcsrv::MinecraftServerCommand, notify::NotifyCommand, ping::PingCommand,
-        settings::SettingsCommand, spam::SpamCommand, timer::TimerCommand, userid::UserIdCommand,
+        ewho::EmoteWhoCommand, filter::FilterCommand, help::HelpCommand, holiday::HolidayCommand,
+        join::JoinCommand, massping::MasspingCommand, mcsrv::MinecraftServerCommand,
+        notify::NotifyCommand, part::PartCommand, ping::PingCommand, rights::RightsCommand,
+        settings::SettingsCommand, seventv::SevenTVCommand, spam::SpamCommand, timer::TimerCommand,
+        userid::UserIdCommand,
     },
     shared_variables::{
         DEFAULT_COMMAND_DELAY_SEC, DEFAULT_COMMAND_LEVEL_OF_RIGHTS, DEFAULT_COMMAND_OPTIONS,
@@ -37,6 +40,10 @@ pub trait Command {
     fn get_subcommands(&self) -> Vec<String> {
         DEFAULT_COMMAND_SUBCOMMANDS
     }
+    /// Subcommands that can be used while the command is on cooldown.
+    fn get_delay_free_subcommands(&self) -> Vec<String> {
+        Vec::new()
+    }
     fn required_rights(&self) -> LevelOfRights {
         DEFAULT_COMMAND_LEVEL_OF_RIGHTS
     }
@@ -57,9 +64,12 @@ impl CommandLoader {
             commands: vec![
                 Box::new(PingCommand),
                 Box::new(SpamCommand),
+                Box::new(RightsCommand),
+                Box::new(FilterCommand),
                 Box::new(MasspingCommand),
                 Box::new(HolidayCommand),
                 Box::new(JoinCommand),
+                Box::new(PartCommand),
                 Box::new(TimerCommand),
                 Box::new(CustomCommandsCommand),
                 Box::new(EventCommand),
@@ -68,9 +78,15 @@ impl CommandLoader {
                 Box::new(EmoteCountCommand),
                 Box::new(EmoteTopCommand),
                 Box::new(EmoteSimilarityCommand),
+                Box::new(EmoteHistoryCommand),
+                Box::new(EmoteWhoCommand),
+                Box::new(EmoteDiffCommand),
+                Box::new(EmoteCommand),
+                Box::new(SevenTVCommand),
                 Box::new(UserIdCommand),
                 Box::new(MinecraftServerCommand),
                 Box::new(HelpCommand),
+                Box::new(AdminCommand),
             ],
         }
     }
@@ -1,4 +1,4 @@
-use std::env;
+use std::{collections::HashMap, env};
 
 use chrono::{NaiveDateTime, Utc};
 use diesel::{
@@ -26,6 +26,7 @@ use super::CommandLoader;
 pub struct Request {
     pub command_id: String,
     pub subcommand_id: Option<String>,
+    pub options: HashMap<String, String>,
     pub message: Option<String>,
 
     pub sender: User,
@@ -108,15 +109,20 @@ impl Request {
                 .expect("Failed to update user name");
         }
 
-        let mut badges_iter = message.badges.iter();
+        // globally suspended users are ignored in every chat room
+        if sender.is_suspended && !sender.is_admin {
+            return None;
+        }
+
+        let has_badge = |name: &str| message.badges.iter().any(|x| x.name.eq(name));
 
         let level_of_rights = if sender.alias_id == channel.alias_id {
             LevelOfRights::Broadcaster
-        } else if badges_iter.any(|x| x.name.eq("moderator")) {
+        } else if has_badge("moderator") {
             LevelOfRights::Moderator
-        } else if badges_iter.any(|x| x.name.eq("vip")) {
+        } else if has_badge("vip") {
             LevelOfRights::Vip
-        } else if badges_iter.any(|x| x.name.eq("subscriber")) {
+        } else if has_badge("subscriber") {
             LevelOfRights::Subscriber
         } else {
             LevelOfRights::User
@@ -124,6 +130,7 @@ impl Request {
 
         let mut rights = ri::rights
             .filter(ri::user_id.eq(&sender.id))
+            .filter(ri::channel_id.eq(&channel.id))
             .get_result::<Right>(conn)
             .unwrap_or_else(|_| {
                 insert_into(ri::rights)
@@ -136,7 +143,11 @@ impl Request {
                     .expect("Failed to insert a new rights")
             });
 
-        if rights.level != LevelOfRights::Suspended && rights.level != level_of_rights {
+        // fixed levels are set manually and aren't recalculated from badges
+        if !rights.is_fixed
+            && rights.level != LevelOfRights::Suspended
+            && rights.level != level_of_rights
+        {
             rights.level = level_of_rights.clone();
 
             update(ri::rights.find(&rights.id))
@@ -145,6 +156,11 @@ impl Request {
                 .expect("Failed to update rights");
         }
 
+        // admins bypass the chat room's restrictions, but their level is never stored
+        if sender.is_admin {
+            rights.level = LevelOfRights::Admin;
+        }
+
         let prefix = channel_preference.prefix.as_str();
 
         if !message.message_text.starts_with(prefix) {
@@ -182,6 +198,21 @@ impl Request {
             return None;
         }
 
+        message_split.remove(0);
+
+        let subcommand_id = if let Some(v) = message_split.first() {
+            let v = v.to_string();
+
+            if command.get_subcommands().contains(&v) {
+                message_split.remove(0);
+                Some(v)
+            } else {
+                None
+            }
+        } else {
+            None
+        };
+
         let last_action_timestamp = ac::actions
             .filter(ac::channel_id.eq(&channel.id))
             .filter(ac::user_id.eq(&sender.id))
@@ -190,7 +221,12 @@ impl Request {
             .order(ac::processed_at.desc())
             .first::<NaiveDateTime>(conn);
 
-        if let Ok(last_action_timestamp) = last_action_timestamp {
+        let is_delay_free = sender.is_admin
+            || subcommand_id
+                .as_ref()
+                .is_some_and(|x| command.get_delay_free_subcommands().contains(x));
+
+        if let (false, Ok(last_action_timestamp)) = (is_delay_free, last_action_timestamp) {
             let la_timestamp: i64 = last_action_timestamp.timestamp();
             let now_timestamp: i64 = Utc::now().naive_utc().timestamp();
 
@@ -199,24 +235,27 @@ impl Request {
             }
         }
 
-        message_split.remove(0);
+        let command_options = command.get_options();
+        let mut options: HashMap<String, String> = HashMap::new();
 
-        let subcommand_id = if let Some(v) = message_split.first() {
-            let v = v.to_string();
+        // options are passed as "--name=value" or "--name" anywhere in the message
+        message_split.retain(|x| {
+            if let Some(option) = x.strip_prefix("--") {
+                let (name, value) = option.split_once('=').unwrap_or((option, ""));
 
-            if command.get_subcommands().contains(&v) {
-                message_split.remove(0);
-                Some(v)
-            } else {
-                None
+                if command_options.iter().any(|y| y.eq(name)) {
+                    options.insert(name.to_string(), value.to_string());
+                    return false;
+                }
             }
-        } else {
-            None
-        };
+
+            true
+        });
 
         Some(Request {
             command_id,
             subcommand_id,
+            options,
             message: if message_split.is_empty() {
                 None
             } else {
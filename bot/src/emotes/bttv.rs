@@ -0,0 +1,297 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
+
+use futures::{SinkExt, StreamExt};
+use log::{error, info};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::net::TcpStream;
+use tokio_tungstenite::{connect_async, tungstenite::Message, MaybeTlsStream, WebSocketStream};
+use twitch_api::types::UserId;
+
+use crate::{
+    http::{HttpClient, RequestOptions},
+    instance_bundle::InstanceBundle,
+    shared_variables::BTTV_WEBSOCKET_URL,
+};
+
+use super::{
+    announce_emote_changes, details::EmoteDetails, EmoteChange, EmoteProvider, ProviderEmote,
+};
+
+const BTTV_URL: &str = "https://api.betterttv.net/3";
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BetterTTVUser {
+    pub channel_emotes: Vec<BetterTTVEmote>,
+    pub shared_emotes: Vec<BetterTTVEmote>,
+}
+
+#[derive(Deserialize)]
+struct BetterTTVEmote {
+    pub id: String,
+    pub code: String,
+    /// The uploader of the shared emote. Channel emotes are uploaded by the channel itself.
+    pub user: Option<BetterTTVEmoteUser>,
+}
+
+#[derive(Deserialize)]
+struct BetterTTVEmoteUser {
+    pub name: String,
+}
+
+pub struct BetterTTVAPIClient {
+    http_client: Arc<HttpClient>,
+}
+
+impl BetterTTVAPIClient {
+    pub fn new(http_client: Arc<HttpClient>) -> Self {
+        Self { http_client }
+    }
+
+    pub async fn get_channel_emotes(&self, twitch_id: String) -> Option<Vec<ProviderEmote>> {
+        self.get_channel_emote_details(twitch_id).await.map(|x| {
+            x.into_iter()
+                .map(|y| ProviderEmote {
+                    id: y.id,
+                    name: y.name,
+                })
+                .collect()
+        })
+    }
+
+    pub async fn get_channel_emote_details(&self, twitch_id: String) -> Option<Vec<EmoteDetails>> {
+        let url = format!("{BTTV_URL}/cached/users/twitch/{twitch_id}");
+
+        let data = self
+            .http_client
+            .get_json::<BetterTTVUser>(url.as_str(), &RequestOptions::default())
+            .await
+            .ok()?;
+
+        Some(
+            data.channel_emotes
+                .into_iter()
+                .chain(data.shared_emotes)
+                .map(|x| EmoteDetails {
+                    id: x.id,
+                    name: x.code,
+                    author: x.user.map(|y| y.name),
+                    added_at: None,
+                    actor_id: None,
+                })
+                .collect(),
+        )
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct BetterTTVPayload<T> {
+    pub name: String,
+    pub data: T,
+}
+
+#[derive(Serialize)]
+struct BetterTTVChannel {
+    pub name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BetterTTVEmoteEvent {
+    pub channel: String,
+    pub emote: Option<BetterTTVEmote>,
+    pub emote_id: Option<String>,
+}
+
+/// Listens to the emote updates of the joined chat rooms through the BetterTTV WebSocket.
+pub struct BetterTTVWebsocketClient {
+    instance_bundle: Arc<InstanceBundle>,
+    url: Url,
+
+    listening_channel_ids: HashSet<UserId>,
+    // BetterTTV sends only IDs of the deleted emotes, so their names are cached
+    emote_names: HashMap<UserId, HashMap<String, String>>,
+}
+
+impl BetterTTVWebsocketClient {
+    pub fn new(instance_bundle: Arc<InstanceBundle>) -> Self {
+        Self {
+            instance_bundle,
+            url: Url::parse(BTTV_WEBSOCKET_URL).unwrap(),
+            listening_channel_ids: HashSet::new(),
+            emote_names: HashMap::new(),
+        }
+    }
+
+    pub async fn run(&mut self) {
+        loop {
+            match connect_async(self.url.clone()).await {
+                Ok((socket, _)) => {
+                    info!("Connected to BetterTTV WebSocket");
+
+                    if let Err(e) = self.listen(socket).await {
+                        error!("BetterTTV WebSocket error: {}", e);
+                    }
+                }
+                Err(e) => error!("Failed to connect to BetterTTV WebSocket: {}", e),
+            }
+
+            // the channels are joined again after reconnecting
+            self.listening_channel_ids.clear();
+            tokio::time::sleep(Duration::from_secs(30)).await;
+        }
+    }
+
+    async fn listen(
+        &mut self,
+        mut socket: WebSocketStream<MaybeTlsStream<TcpStream>>,
+    ) -> Result<(), eyre::Error> {
+        let mut interval = tokio::time::interval(Duration::from_secs(5));
+
+        loop {
+            tokio::select!(
+                _ = interval.tick() => self.process_channels(&mut socket).await?,
+                msg = socket.next() => match msg {
+                    Some(Ok(Message::Text(s))) => self.process_message(s).await,
+                    Some(Ok(Message::Ping(x))) => socket.send(Message::Pong(x)).await?,
+                    Some(Ok(Message::Close(_))) | None => return Ok(()),
+                    Some(Ok(_)) => {}
+                    Some(Err(e)) => return Err(e.into()),
+                }
+            )
+        }
+    }
+
+    /// Joins and parts the BetterTTV channels to match the joined chat rooms.
+    async fn process_channels(
+        &mut self,
+        socket: &mut WebSocketStream<MaybeTlsStream<TcpStream>>,
+    ) -> Result<(), eyre::Error> {
+        let data = self
+            .instance_bundle
+            .bttv_websocket_data
+            .lock()
+            .await
+            .clone();
+
+        let joined_ids = data
+            .difference(&self.listening_channel_ids)
+            .cloned()
+            .collect::<Vec<UserId>>();
+        let parted_ids = self
+            .listening_channel_ids
+            .difference(&data)
+            .cloned()
+            .collect::<Vec<UserId>>();
+
+        for id in joined_ids {
+            if let Some(emotes) = self
+                .instance_bundle
+                .bttv_api_client
+                .get_channel_emotes(id.clone().take())
+                .await
+            {
+                self.emote_names.insert(
+                    id.clone(),
+                    emotes.into_iter().map(|x| (x.id, x.name)).collect(),
+                );
+            }
+
+            self.send_channel(socket, "join_channel", &id).await?;
+            self.listening_channel_ids.insert(id);
+        }
+
+        for id in parted_ids {
+            self.send_channel(socket, "part_channel", &id).await?;
+            self.listening_channel_ids.remove(&id);
+            self.emote_names.remove(&id);
+        }
+
+        Ok(())
+    }
+
+    async fn send_channel(
+        &self,
+        socket: &mut WebSocketStream<MaybeTlsStream<TcpStream>>,
+        name: &str,
+        channel_id: &UserId,
+    ) -> Result<(), eyre::Error> {
+        let data = BetterTTVPayload {
+            name: name.to_string(),
+            data: BetterTTVChannel {
+                name: format!("twitch:{}", channel_id.as_str()),
+            },
+        };
+
+        socket
+            .send(Message::Text(serde_json::to_string(&data)?))
+            .await?;
+
+        Ok(())
+    }
+
+    async fn process_message(&mut self, message: String) {
+        let payload = match serde_json::from_str::<BetterTTVPayload<Value>>(message.as_str()) {
+            Ok(v) => v,
+            Err(_) => return,
+        };
+
+        let event = match serde_json::from_value::<BetterTTVEmoteEvent>(payload.data) {
+            Ok(v) => v,
+            Err(_) => return,
+        };
+
+        let channel_id = match event.channel.strip_prefix("twitch:") {
+            Some(v) => UserId::new(v.to_string()),
+            None => return,
+        };
+
+        let emote_names = self.emote_names.entry(channel_id.clone()).or_default();
+
+        let change = match (payload.name.as_str(), event.emote, event.emote_id) {
+            ("emote_create", Some(emote), _) => {
+                emote_names.insert(emote.id.clone(), emote.code.clone());
+                EmoteChange::Pushed(ProviderEmote {
+                    id: emote.id,
+                    name: emote.code,
+                })
+            }
+            ("emote_update", Some(emote), _) => {
+                match emote_names.insert(emote.id.clone(), emote.code.clone()) {
+                    Some(old_name) if old_name.ne(&emote.code) => EmoteChange::Updated(
+                        old_name,
+                        ProviderEmote {
+                            id: emote.id,
+                            name: emote.code,
+                        },
+                    ),
+                    _ => return,
+                }
+            }
+            ("emote_delete", _, Some(emote_id)) => match emote_names.remove(&emote_id) {
+                Some(name) => EmoteChange::Pulled(ProviderEmote { id: emote_id, name }),
+                None => return,
+            },
+            _ => return,
+        };
+
+        let alias_id = match channel_id.as_str().parse::<i32>() {
+            Ok(v) => v,
+            Err(_) => return,
+        };
+
+        announce_emote_changes(
+            &self.instance_bundle,
+            alias_id,
+            EmoteProvider::BetterTTV,
+            vec![change],
+        )
+        .await;
+    }
+}
@@ -0,0 +1,110 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use chrono::NaiveDateTime;
+use tokio::sync::Mutex;
+
+use crate::{instance_bundle::InstanceBundle, shared_variables::EMOTE_CACHE_TTL_SEC};
+
+use super::EmoteProvider;
+
+/// The emote of the chat room with the details the provider tells about it.
+#[derive(Debug, Clone)]
+pub struct EmoteDetails {
+    pub id: String,
+    pub name: String,
+    pub author: Option<String>,
+    /// The time the emote was added to the chat room.
+    pub added_at: Option<NaiveDateTime>,
+    /// The 7TV ID of the user who added the emote to the chat room.
+    pub actor_id: Option<String>,
+}
+
+type ChannelEmoteDetails = HashMap<(EmoteProvider, i32), (Instant, Vec<EmoteDetails>)>;
+
+/// Caches the emote details of the chat rooms,
+/// so the providers' APIs are not requested on every lookup.
+/// The entries are fetched again after [EMOTE_CACHE_TTL_SEC] or once the emotes have changed.
+pub struct EmoteDetailsCache {
+    channel_emotes: Mutex<ChannelEmoteDetails>,
+    seventv_user_names: Mutex<HashMap<String, (Instant, String)>>,
+}
+
+impl EmoteDetailsCache {
+    pub fn new() -> Self {
+        Self {
+            channel_emotes: Mutex::new(HashMap::new()),
+            seventv_user_names: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub async fn get_channel_emotes(
+        &self,
+        instance_bundle: &InstanceBundle,
+        provider: &EmoteProvider,
+        alias_id: i32,
+    ) -> Option<Vec<EmoteDetails>> {
+        let key = (provider.clone(), alias_id);
+
+        if let Some((updated_at, emotes)) = self.channel_emotes.lock().await.get(&key) {
+            if updated_at.elapsed() < Duration::from_secs(EMOTE_CACHE_TTL_SEC) {
+                return Some(emotes.clone());
+            }
+        }
+
+        let emotes = provider
+            .get_channel_emote_details(instance_bundle, alias_id.to_string())
+            .await?;
+
+        self.channel_emotes
+            .lock()
+            .await
+            .insert(key, (Instant::now(), emotes.clone()));
+
+        Some(emotes)
+    }
+
+    /// Drops the cached emotes of the chat room, so they are fetched again on the next lookup.
+    pub async fn invalidate(&self, provider: &EmoteProvider, alias_id: i32) {
+        self.channel_emotes
+            .lock()
+            .await
+            .remove(&(provider.clone(), alias_id));
+    }
+
+    /// Gets the name of the 7TV user, preferring their Twitch login.
+    pub async fn get_seventv_user_name(
+        &self,
+        instance_bundle: &InstanceBundle,
+        id: &str,
+    ) -> Option<String> {
+        if let Some((updated_at, name)) = self.seventv_user_names.lock().await.get(id) {
+            if updated_at.elapsed() < Duration::from_secs(EMOTE_CACHE_TTL_SEC) {
+                return Some(name.clone());
+            }
+        }
+
+        let user = instance_bundle
+            .seventv_api_client
+            .get_user(id.to_string())
+            .await?;
+
+        let name = match user
+            .connections
+            .into_iter()
+            .find(|x| x.platform.eq("TWITCH"))
+        {
+            Some(v) => v.username,
+            None => user.username,
+        };
+
+        self.seventv_user_names
+            .lock()
+            .await
+            .insert(id.to_string(), (Instant::now(), name.clone()));
+
+        Some(name)
+    }
+}
@@ -0,0 +1,186 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use common::{establish_connection, schema::channels::dsl as ch};
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
+use log::error;
+use serde::Deserialize;
+
+use crate::{
+    http::{HttpClient, RequestOptions},
+    instance_bundle::InstanceBundle,
+    shared_variables::FFZ_UPDATE_CHECK_DELAY,
+};
+
+use super::{
+    announce_emote_changes, details::EmoteDetails, EmoteChange, EmoteProvider, ProviderEmote,
+};
+
+const FFZ_URL: &str = "https://api.frankerfacez.com/v1";
+
+#[derive(Deserialize)]
+struct FrankerFaceZRoomResponse {
+    pub sets: HashMap<String, FrankerFaceZEmoteSet>,
+}
+
+#[derive(Deserialize)]
+struct FrankerFaceZEmoteSet {
+    pub emoticons: Vec<FrankerFaceZEmote>,
+}
+
+#[derive(Deserialize)]
+struct FrankerFaceZEmote {
+    pub id: u32,
+    pub name: String,
+    pub owner: Option<FrankerFaceZEmoteOwner>,
+}
+
+#[derive(Deserialize)]
+struct FrankerFaceZEmoteOwner {
+    pub name: String,
+}
+
+pub struct FrankerFaceZAPIClient {
+    http_client: Arc<HttpClient>,
+}
+
+impl FrankerFaceZAPIClient {
+    pub fn new(http_client: Arc<HttpClient>) -> Self {
+        Self { http_client }
+    }
+
+    pub async fn get_channel_emotes(&self, twitch_id: String) -> Option<Vec<ProviderEmote>> {
+        self.get_channel_emote_details(twitch_id).await.map(|x| {
+            x.into_iter()
+                .map(|y| ProviderEmote {
+                    id: y.id,
+                    name: y.name,
+                })
+                .collect()
+        })
+    }
+
+    pub async fn get_channel_emote_details(&self, twitch_id: String) -> Option<Vec<EmoteDetails>> {
+        let url = format!("{FFZ_URL}/room/id/{twitch_id}");
+
+        let data = self
+            .http_client
+            .get_json::<FrankerFaceZRoomResponse>(url.as_str(), &RequestOptions::default())
+            .await
+            .ok()?;
+
+        Some(
+            data.sets
+                .into_values()
+                .flat_map(|x| x.emoticons)
+                .map(|x| EmoteDetails {
+                    id: x.id.to_string(),
+                    name: x.name,
+                    author: x.owner.map(|y| y.name),
+                    added_at: None,
+                    actor_id: None,
+                })
+                .collect(),
+        )
+    }
+}
+
+/// FrankerFaceZ has no event API, so the emotes of the joined chat rooms
+/// are compared with the previous check.
+pub struct FrankerFaceZUpdatesHelper {
+    instance_bundle: Arc<InstanceBundle>,
+    emote_names: HashMap<i32, HashMap<String, String>>,
+}
+
+impl FrankerFaceZUpdatesHelper {
+    pub fn new(instance_bundle: Arc<InstanceBundle>) -> Self {
+        Self {
+            instance_bundle,
+            emote_names: HashMap::new(),
+        }
+    }
+
+    pub async fn run(&mut self) {
+        loop {
+            self.process_channels().await;
+            tokio::time::sleep(Duration::from_secs(FFZ_UPDATE_CHECK_DELAY)).await;
+        }
+    }
+
+    async fn process_channels(&mut self) {
+        let conn = &mut establish_connection();
+
+        // every chat room is polled for the emote history, the feature only enables the announcements
+        let alias_ids = ch::channels
+            .filter(ch::opt_outed_at.is_null())
+            .select(ch::alias_id)
+            .load::<i32>(conn);
+
+        let alias_ids = match alias_ids {
+            Ok(v) => v,
+            Err(e) => {
+                error!("Failed to get channels: {}", e);
+                return;
+            }
+        };
+
+        self.emote_names.retain(|x, _| alias_ids.contains(x));
+
+        for alias_id in alias_ids {
+            let emotes = match self
+                .instance_bundle
+                .ffz_api_client
+                .get_channel_emotes(alias_id.to_string())
+                .await
+            {
+                Some(v) => v
+                    .into_iter()
+                    .map(|x| (x.id, x.name))
+                    .collect::<HashMap<String, String>>(),
+                None => continue,
+            };
+
+            let old_emotes = match self.emote_names.insert(alias_id, emotes.clone()) {
+                Some(v) => v,
+                None => continue,
+            };
+
+            let mut changes: Vec<EmoteChange> = Vec::new();
+
+            for (id, name) in &emotes {
+                match old_emotes.get(id) {
+                    Some(old_name) if old_name.ne(name) => changes.push(EmoteChange::Updated(
+                        old_name.clone(),
+                        ProviderEmote {
+                            id: id.clone(),
+                            name: name.clone(),
+                        },
+                    )),
+                    Some(_) => {}
+                    None => changes.push(EmoteChange::Pushed(ProviderEmote {
+                        id: id.clone(),
+                        name: name.clone(),
+                    })),
+                }
+            }
+
+            for (id, name) in &old_emotes {
+                if !emotes.contains_key(id) {
+                    changes.push(EmoteChange::Pulled(ProviderEmote {
+                        id: id.clone(),
+                        name: name.clone(),
+                    }));
+                }
+            }
+
+            if !changes.is_empty() {
+                announce_emote_changes(
+                    &self.instance_bundle,
+                    alias_id,
+                    EmoteProvider::FrankerFaceZ,
+                    changes,
+                )
+                .await;
+            }
+        }
+    }
+}
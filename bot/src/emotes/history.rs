@@ -0,0 +1,145 @@
+use common::{
+    models::{EmoteEvent, EmoteEventAction, NewEmoteEvent},
+    schema::{channels::dsl as ch, emote_events::dsl as ee},
+};
+use diesel::{
+    insert_into, ExpressionMethods, OptionalExtension, PgConnection, QueryDsl, RunQueryDsl,
+};
+use log::error;
+
+use super::{EmoteChange, EmoteProvider};
+
+/// Stores the emote changes of the chat room, so they can be looked up later.
+/// The actor is unknown for the providers that don't tell who made the changes.
+pub fn record_emote_changes(
+    conn: &mut PgConnection,
+    alias_id: i32,
+    provider: &EmoteProvider,
+    actor_name: Option<String>,
+    changes: &[EmoteChange],
+) {
+    if changes.is_empty() {
+        return;
+    }
+
+    let channel_id = match ch::channels
+        .filter(ch::alias_id.eq(&alias_id))
+        .select(ch::id)
+        .first::<i32>(conn)
+        .optional()
+    {
+        Ok(Some(v)) => v,
+        Ok(None) => return,
+        Err(e) => {
+            error!("Failed to get channel: {}", e);
+            return;
+        }
+    };
+
+    let values = changes
+        .iter()
+        .map(|x| {
+            let (emote, old_emote_name, action) = match x {
+                EmoteChange::Pushed(emote) => (emote, None, EmoteEventAction::Push),
+                EmoteChange::Pulled(emote) => (emote, None, EmoteEventAction::Pull),
+                EmoteChange::Updated(old_name, emote) => {
+                    (emote, Some(old_name.clone()), EmoteEventAction::Update)
+                }
+            };
+
+            NewEmoteEvent {
+                channel_id,
+                provider: provider.to_string(),
+                emote_id: emote.id.clone(),
+                emote_name: emote.name.clone(),
+                old_emote_name,
+                action,
+                actor_name: actor_name.clone(),
+            }
+        })
+        .collect::<Vec<NewEmoteEvent>>();
+
+    if let Err(e) = insert_into(ee::emote_events).values(&values).execute(conn) {
+        error!("Failed to create emote events: {}", e);
+    }
+}
+
+/// Gets the latest emote changes of the chat room, the newest ones first.
+pub fn get_emote_events(
+    conn: &mut PgConnection,
+    channel_id: i32,
+    provider: &EmoteProvider,
+    amount: i64,
+) -> Vec<EmoteEvent> {
+    ee::emote_events
+        .filter(ee::channel_id.eq(&channel_id))
+        .filter(ee::provider.eq(provider.to_string()))
+        .order(ee::created_at.desc())
+        .limit(amount)
+        .load::<EmoteEvent>(conn)
+        .expect("Failed to get emote events")
+}
+
+/// Gets the latest addition of the emote to the chat room.
+pub fn get_emote_addition(
+    conn: &mut PgConnection,
+    channel_id: i32,
+    provider: &EmoteProvider,
+    emote_id: &str,
+) -> Option<EmoteEvent> {
+    ee::emote_events
+        .filter(ee::channel_id.eq(&channel_id))
+        .filter(ee::provider.eq(provider.to_string()))
+        .filter(ee::emote_id.eq(emote_id))
+        .filter(ee::action.eq(EmoteEventAction::Push))
+        .order(ee::created_at.desc())
+        .first::<EmoteEvent>(conn)
+        .optional()
+        .expect("Failed to get emote event")
+}
+
+/// Joins the items, leaving out the ones that don't fit into `max_length` bytes.
+fn join_within_length(items: &[String], separator: &str, max_length: usize) -> String {
+    let mut list = String::new();
+
+    for (i, item) in items.iter().enumerate() {
+        let length = if list.is_empty() {
+            item.len()
+        } else {
+            list.len() + separator.len() + item.len()
+        };
+
+        // the ellipsis must fit too if there are more items left
+        let reserved = if i + 1 < items.len() { 4 } else { 0 };
+
+        if length + reserved > max_length {
+            list.push_str(if list.is_empty() { "..." } else { " ..." });
+            break;
+        }
+
+        if !list.is_empty() {
+            list.push_str(separator);
+        }
+
+        list.push_str(item);
+    }
+
+    list
+}
+
+/// Formats a chat message with the lists, leaving out the items that don't fit into it.
+/// The space left in the message is shared equally between the lists.
+pub fn format_with_lists<F>(lists: &[(&[String], &str)], format: F) -> String
+where
+    F: Fn(Vec<String>) -> String,
+{
+    let empty_message = format(vec![String::new(); lists.len()]);
+    let max_length = 500usize.saturating_sub(empty_message.len()) / lists.len().max(1);
+
+    format(
+        lists
+            .iter()
+            .map(|(items, separator)| join_within_length(items, separator, max_length))
+            .collect(),
+    )
+}
@@ -0,0 +1,330 @@
+pub mod bttv;
+pub mod details;
+pub mod ffz;
+pub mod history;
+pub mod stats;
+pub mod usage;
+
+use std::{fmt::Display, str::FromStr};
+
+use chrono::NaiveDateTime;
+use common::{
+    establish_connection,
+    models::ChannelFeature,
+    schema::{channel_preferences::dsl as chp, channels::dsl as ch},
+};
+use diesel::{ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl};
+use log::error;
+use twitch_api::types::{NicknameRef, UserIdRef};
+
+use crate::{
+    commands::{request::Request, response::ResponseError},
+    instance_bundle::InstanceBundle,
+    localization::LineId,
+};
+
+use self::{details::EmoteDetails, history::record_emote_changes};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum EmoteProvider {
+    Twitch,
+    SevenTV,
+    BetterTTV,
+    FrankerFaceZ,
+}
+
+impl FromStr for EmoteProvider {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "twitch" => Ok(Self::Twitch),
+            "7tv" => Ok(Self::SevenTV),
+            "bttv" => Ok(Self::BetterTTV),
+            "ffz" => Ok(Self::FrankerFaceZ),
+            _ => Err("Failed to parse an emote provider".to_string()),
+        }
+    }
+}
+
+impl Display for EmoteProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Twitch => "twitch",
+                Self::SevenTV => "7tv",
+                Self::BetterTTV => "bttv",
+                Self::FrankerFaceZ => "ffz",
+            }
+        )
+    }
+}
+
+impl EmoteProvider {
+    /// Gets the provider from the `--provider` option of the request. 7TV is used by default.
+    pub fn from_request(request: &Request) -> Result<Self, ResponseError> {
+        match request.options.get("provider") {
+            Some(v) if !v.is_empty() => {
+                Self::from_str(v.as_str()).map_err(|_| ResponseError::IncorrectArgument(v.clone()))
+            }
+            _ => Ok(Self::SevenTV),
+        }
+    }
+
+    pub fn line_id(&self) -> LineId {
+        match self {
+            Self::Twitch => LineId::ProviderTwitch,
+            Self::SevenTV => LineId::Provider7TV,
+            Self::BetterTTV => LineId::ProviderBTTV,
+            Self::FrankerFaceZ => LineId::ProviderFFZ,
+        }
+    }
+
+    /// Gets the feature that enables the notifications about the provider's emote changes.
+    pub fn feature(&self) -> Option<ChannelFeature> {
+        match self {
+            Self::Twitch => None,
+            Self::SevenTV => Some(ChannelFeature::Notify7TVUpdates),
+            Self::BetterTTV => Some(ChannelFeature::NotifyBTTVUpdates),
+            Self::FrankerFaceZ => Some(ChannelFeature::NotifyFFZUpdates),
+        }
+    }
+
+    /// Gets the emotes the channel has in the provider by its Twitch ID.
+    pub async fn get_channel_emotes(
+        &self,
+        instance_bundle: &InstanceBundle,
+        twitch_id: String,
+    ) -> Option<Vec<ProviderEmote>> {
+        match self {
+            Self::Twitch => instance_bundle
+                .twitch_api_client
+                .get_channel_emotes_from_id(
+                    UserIdRef::from_str(twitch_id.as_str()),
+                    &*instance_bundle.twitch_api_token,
+                )
+                .await
+                .ok()
+                .map(|x| {
+                    x.into_iter()
+                        .map(|y| ProviderEmote {
+                            id: y.id.take(),
+                            name: y.name,
+                        })
+                        .collect()
+                }),
+            Self::SevenTV => instance_bundle
+                .seventv_api_client
+                .get_user_by_twitch_id(twitch_id)
+                .await
+                .map(|x| {
+                    x.emote_set
+                        .emotes
+                        .into_iter()
+                        .map(|y| ProviderEmote {
+                            id: y.id,
+                            name: y.name,
+                        })
+                        .collect()
+                }),
+            Self::BetterTTV => {
+                instance_bundle
+                    .bttv_api_client
+                    .get_channel_emotes(twitch_id)
+                    .await
+            }
+            Self::FrankerFaceZ => {
+                instance_bundle
+                    .ffz_api_client
+                    .get_channel_emotes(twitch_id)
+                    .await
+            }
+        }
+    }
+
+    /// Gets the emotes the channel has in the provider by its Twitch ID,
+    /// with the details the provider tells about them.
+    pub async fn get_channel_emote_details(
+        &self,
+        instance_bundle: &InstanceBundle,
+        twitch_id: String,
+    ) -> Option<Vec<EmoteDetails>> {
+        match self {
+            Self::SevenTV => instance_bundle
+                .seventv_api_client
+                .get_user_by_twitch_id(twitch_id)
+                .await
+                .map(|x| {
+                    x.emote_set
+                        .emotes
+                        .into_iter()
+                        .map(|y| EmoteDetails {
+                            id: y.id,
+                            name: y.name,
+                            author: y.data.and_then(|z| z.owner).map(|z| z.username),
+                            added_at: y.timestamp.and_then(NaiveDateTime::from_timestamp_millis),
+                            actor_id: y.actor_id,
+                        })
+                        .collect()
+                }),
+            Self::BetterTTV => {
+                instance_bundle
+                    .bttv_api_client
+                    .get_channel_emote_details(twitch_id)
+                    .await
+            }
+            Self::FrankerFaceZ => {
+                instance_bundle
+                    .ffz_api_client
+                    .get_channel_emote_details(twitch_id)
+                    .await
+            }
+            Self::Twitch => self
+                .get_channel_emotes(instance_bundle, twitch_id)
+                .await
+                .map(|x| {
+                    x.into_iter()
+                        .map(|y| EmoteDetails {
+                            id: y.id,
+                            name: y.name,
+                            author: None,
+                            added_at: None,
+                            actor_id: None,
+                        })
+                        .collect()
+                }),
+        }
+    }
+
+    /// Gets the link to the emote's page, or to its image if the provider has no pages.
+    pub fn emote_url(&self, emote_id: &str) -> String {
+        match self {
+            Self::Twitch => format!(
+                "https://static-cdn.jtvnw.net/emoticons/v2/{}/default/dark/3.0",
+                emote_id
+            ),
+            Self::SevenTV => format!("https://7tv.app/emotes/{}", emote_id),
+            Self::BetterTTV => format!("https://betterttv.com/emotes/{}", emote_id),
+            Self::FrankerFaceZ => format!("https://www.frankerfacez.com/emoticon/{}", emote_id),
+        }
+    }
+
+    /// Gets the emotes the channel has in the provider by its Twitch login.
+    pub async fn get_channel_emotes_by_login(
+        &self,
+        instance_bundle: &InstanceBundle,
+        login: &str,
+    ) -> Option<Vec<ProviderEmote>> {
+        match instance_bundle
+            .twitch_api_client
+            .get_user_from_login(
+                NicknameRef::from_str(login),
+                &*instance_bundle.twitch_api_token,
+            )
+            .await
+        {
+            Ok(Some(user)) => {
+                self.get_channel_emotes(instance_bundle, user.id.take())
+                    .await
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ProviderEmote {
+    pub id: String,
+    pub name: String,
+}
+
+pub enum EmoteChange {
+    Pushed(ProviderEmote),
+    Pulled(ProviderEmote),
+    /// The old name of the emote and the emote with its new name.
+    Updated(String, ProviderEmote),
+}
+
+/// Announces the emote changes in the chat room if it has enabled the provider's notifications.
+/// The providers that don't tell who made the changes are announced without an actor.
+pub async fn announce_emote_changes(
+    instance_bundle: &InstanceBundle,
+    alias_id: i32,
+    provider: EmoteProvider,
+    changes: Vec<EmoteChange>,
+) {
+    let conn = &mut establish_connection();
+
+    record_emote_changes(conn, alias_id, &provider, None, &changes);
+
+    instance_bundle
+        .emote_details_cache
+        .invalidate(&provider, alias_id)
+        .await;
+
+    let preference = ch::channels
+        .inner_join(chp::channel_preferences)
+        .filter(ch::alias_id.eq(&alias_id))
+        .filter(ch::opt_outed_at.is_null())
+        .select((ch::alias_name, chp::language, chp::features))
+        .first::<(String, String, Vec<Option<String>>)>(conn)
+        .optional();
+
+    let (channel_name, language, features) = match preference {
+        Ok(Some(v)) => v,
+        Ok(None) => return,
+        Err(e) => {
+            error!("Failed to get channel preference: {}", e);
+            return;
+        }
+    };
+
+    let feature = match provider.feature() {
+        Some(v) => v,
+        None => return,
+    };
+
+    if !features
+        .iter()
+        .flatten()
+        .any(|x| ChannelFeature::from_str(x.as_str()).is_ok_and(|y| y == feature))
+    {
+        return;
+    }
+
+    let localizator = &instance_bundle.localizator;
+    let provider_name = localizator
+        .get_literal_text(language.as_str(), provider.line_id())
+        .unwrap_or_default();
+
+    for change in changes {
+        let (line_id, parameters) = match change {
+            EmoteChange::Pushed(emote) => (
+                LineId::EmotesPushedAnonymous,
+                vec![provider_name.clone(), emote.name],
+            ),
+            EmoteChange::Pulled(emote) => (
+                LineId::EmotesPulledAnonymous,
+                vec![provider_name.clone(), emote.name],
+            ),
+            EmoteChange::Updated(old_name, emote) => (
+                LineId::EmotesUpdatedAnonymous,
+                vec![provider_name.clone(), old_name, emote.name],
+            ),
+        };
+
+        if let Some(message) =
+            localizator.get_formatted_text(language.as_str(), line_id, parameters)
+        {
+            if let Err(e) = instance_bundle
+                .twitch_irc_client
+                .say(channel_name.clone(), message)
+                .await
+            {
+                error!("Failed to announce emote changes: {}", e);
+            }
+        }
+    }
+}
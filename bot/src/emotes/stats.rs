@@ -0,0 +1,88 @@
+use std::{env, time::Duration};
+
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::{
+    commands::response::ResponseError,
+    http::{HttpClient, HttpError, RequestOptions},
+    models::stats::{ChannelEmote, ChannelEmoteUsage, Response as StatsResponse},
+    shared_variables::STATS_CACHE_TTL_SEC,
+};
+
+/// Gets the emotes of the channel from the external stats service.
+pub async fn get_channel_emotes(
+    http_client: &HttpClient,
+    channel_id: &str,
+) -> Result<Vec<ChannelEmote>, ResponseError> {
+    fetch(http_client, format!("{}/emotes", channel_id)).await
+}
+
+/// Gets the usage counts of the channel's emotes from the external stats service.
+pub async fn get_channel_emote_usages(
+    http_client: &HttpClient,
+    channel_id: &str,
+) -> Result<Vec<ChannelEmoteUsage>, ResponseError> {
+    fetch(http_client, format!("{}/emotes/usage", channel_id)).await
+}
+
+async fn fetch<T: DeserializeOwned + Default>(
+    http_client: &HttpClient,
+    path: String,
+) -> Result<T, ResponseError> {
+    let url = format!(
+        "{}/api/v1/channel/twitch/{}",
+        env::var("STATS_API_HOSTNAME").expect("STATS_API_HOSTNAME must be set for emote stats"),
+        path
+    );
+
+    let response = match http_client
+        .get_json::<StatsResponse<T>>(url.as_str(), &get_request_options())
+        .await
+    {
+        Ok(v) => v,
+        // the service explains the errors in the same response format
+        Err(HttpError::Status(code, Some(body))) => {
+            return Err(
+                match serde_json::from_str::<StatsResponse<Value>>(body.as_str()) {
+                    Ok(v) => ResponseError::ExternalAPIError(v.status_code, v.message),
+                    Err(_) => HttpError::Status(code, None).into(),
+                },
+            )
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    if response.status_code != 200 {
+        return Err(ResponseError::ExternalAPIError(
+            response.status_code,
+            response.message,
+        ));
+    }
+
+    Ok(response.data.unwrap_or_default())
+}
+
+/// Gets the credentials from `STATS_API_PASSWORD` in the `name:password` format.
+fn get_request_options() -> RequestOptions {
+    let basic_auth = env::var("STATS_API_PASSWORD").ok().map(|credentials| {
+        let (name, password) = match credentials.split_once(':') {
+            Some((x, y)) => (x.to_string(), y.to_string()),
+            None => (credentials, String::new()),
+        };
+
+        (
+            name,
+            if password.is_empty() {
+                None
+            } else {
+                Some(password)
+            },
+        )
+    });
+
+    RequestOptions {
+        basic_auth,
+        cache_ttl: Some(Duration::from_secs(STATS_CACHE_TTL_SEC)),
+    }
+}
@@ -0,0 +1,513 @@
+use std::{
+    collections::{HashMap, HashSet},
+    env,
+    time::{Duration, Instant},
+};
+
+use chrono::{NaiveDate, Utc};
+use common::{
+    establish_connection,
+    models::{EmoteUsage, NewEmoteDailyUsage, NewEmoteUsage, NewUser},
+    schema::{
+        channels::dsl as ch, emote_daily_usages::dsl as edu, emote_usages::dsl as eu,
+        users::dsl as us,
+    },
+};
+use diesel::{
+    dsl, insert_into, upsert::excluded, ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl,
+};
+use tokio::sync::Mutex;
+use twitch_irc::message::PrivmsgMessage;
+
+use crate::{
+    commands::{request::Request, response::ResponseError},
+    instance_bundle::InstanceBundle,
+    shared_variables::EMOTE_CACHE_TTL_SEC,
+    utils::parse_duration,
+};
+
+use super::EmoteProvider;
+
+#[derive(PartialEq, Eq, Hash, Clone)]
+struct EmoteKey {
+    alias_id: i32,
+    provider: EmoteProvider,
+    emote_id: String,
+}
+
+#[derive(PartialEq, Eq, Hash)]
+struct UserEmoteKey {
+    emote: EmoteKey,
+    user_alias_id: i32,
+    day: NaiveDate,
+}
+
+struct PendingUsage {
+    emote_name: String,
+    user_alias_name: String,
+    count: i32,
+}
+
+struct ChannelEmotes {
+    updated_at: Instant,
+    emotes: HashMap<String, (EmoteProvider, String)>,
+}
+
+/// Counts the emotes used in the chat rooms and writes the counts to the database in batches.
+/// It is used when the external stats service is not configured.
+pub struct EmoteUsageCounter {
+    channel_emotes: Mutex<HashMap<i32, ChannelEmotes>>,
+    /// The chat rooms whose emotes are missing or outdated in the cache.
+    requested_channels: Mutex<HashSet<i32>>,
+    pending_usages: Mutex<HashMap<UserEmoteKey, PendingUsage>>,
+}
+
+impl EmoteUsageCounter {
+    pub fn new() -> Self {
+        Self {
+            channel_emotes: Mutex::new(HashMap::new()),
+            requested_channels: Mutex::new(HashSet::new()),
+            pending_usages: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn is_enabled() -> bool {
+        env::var("STATS_API_HOSTNAME").is_err()
+    }
+
+    /// Counts the emotes of the message. The third-party emotes are looked up in the cache only,
+    /// so they aren't counted until [Self::refresh_channel_emotes] has fetched them.
+    pub async fn count(&self, message: &PrivmsgMessage) {
+        if !Self::is_enabled() {
+            return;
+        }
+
+        let (alias_id, user_alias_id) = match (
+            message.channel_id.parse::<i32>(),
+            message.sender.id.parse::<i32>(),
+        ) {
+            (Ok(x), Ok(y)) => (x, y),
+            _ => return,
+        };
+
+        let mut usages: Vec<(EmoteKey, String)> = Vec::new();
+
+        // Twitch emotes are marked in the message by Twitch itself
+        for emote in &message.emotes {
+            usages.push((
+                EmoteKey {
+                    alias_id,
+                    provider: EmoteProvider::Twitch,
+                    emote_id: emote.id.clone(),
+                },
+                emote.code.clone(),
+            ));
+        }
+
+        let twitch_codes = message
+            .emotes
+            .iter()
+            .map(|x| x.code.as_str())
+            .collect::<HashSet<&str>>();
+
+        {
+            let channel_emotes = self.channel_emotes.lock().await;
+            let cache = channel_emotes.get(&alias_id);
+
+            if cache
+                .is_none_or(|x| x.updated_at.elapsed() >= Duration::from_secs(EMOTE_CACHE_TTL_SEC))
+            {
+                self.requested_channels.lock().await.insert(alias_id);
+            }
+
+            if let Some(cache) = cache {
+                for word in message.message_text.split_ascii_whitespace() {
+                    if twitch_codes.contains(word) {
+                        continue;
+                    }
+
+                    if let Some((provider, emote_id)) = cache.emotes.get(word) {
+                        usages.push((
+                            EmoteKey {
+                                alias_id,
+                                provider: provider.clone(),
+                                emote_id: emote_id.clone(),
+                            },
+                            word.to_string(),
+                        ));
+                    }
+                }
+            }
+        }
+
+        if usages.is_empty() {
+            return;
+        }
+
+        let day = message.server_timestamp.date_naive();
+        let mut pending_usages = self.pending_usages.lock().await;
+
+        for (key, name) in usages {
+            let usage = pending_usages
+                .entry(UserEmoteKey {
+                    emote: key,
+                    user_alias_id,
+                    day,
+                })
+                .or_insert(PendingUsage {
+                    emote_name: name.clone(),
+                    user_alias_name: message.sender.login.clone(),
+                    count: 0,
+                });
+
+            usage.emote_name = name;
+            usage.count += 1;
+        }
+    }
+
+    /// Fetches the third-party emotes of the chat rooms that have been requested by [Self::count].
+    /// They are fetched again after [EMOTE_CACHE_TTL_SEC].
+    pub async fn refresh_channel_emotes(&self, instance_bundle: &InstanceBundle) {
+        let alias_ids = self
+            .requested_channels
+            .lock()
+            .await
+            .drain()
+            .collect::<Vec<i32>>();
+
+        for alias_id in alias_ids {
+            let is_fresh = self
+                .channel_emotes
+                .lock()
+                .await
+                .get(&alias_id)
+                .is_some_and(|x| x.updated_at.elapsed() < Duration::from_secs(EMOTE_CACHE_TTL_SEC));
+
+            if is_fresh {
+                continue;
+            }
+
+            let emotes = Self::get_channel_emotes(instance_bundle, alias_id).await;
+
+            self.channel_emotes.lock().await.insert(
+                alias_id,
+                ChannelEmotes {
+                    updated_at: Instant::now(),
+                    emotes,
+                },
+            );
+        }
+    }
+
+    /// Gets the third-party emotes of the chat room by their names.
+    async fn get_channel_emotes(
+        instance_bundle: &InstanceBundle,
+        alias_id: i32,
+    ) -> HashMap<String, (EmoteProvider, String)> {
+        let mut emotes: HashMap<String, (EmoteProvider, String)> = HashMap::new();
+
+        // 7TV emotes take precedence over the emotes of the same name like in the 7TV extension
+        for provider in [
+            EmoteProvider::FrankerFaceZ,
+            EmoteProvider::BetterTTV,
+            EmoteProvider::SevenTV,
+        ] {
+            if let Some(v) = provider
+                .get_channel_emotes(instance_bundle, alias_id.to_string())
+                .await
+            {
+                emotes.extend(v.into_iter().map(|x| (x.name, (provider.clone(), x.id))));
+            }
+        }
+
+        emotes
+    }
+
+    /// Writes the counted usages to the database:
+    /// the all-time counts of the chat rooms and the daily counts of the users.
+    pub async fn flush(&self) {
+        let usages = self
+            .pending_usages
+            .lock()
+            .await
+            .drain()
+            .collect::<Vec<(UserEmoteKey, PendingUsage)>>();
+
+        if usages.is_empty() {
+            return;
+        }
+
+        let conn = &mut establish_connection();
+
+        let alias_ids = usages
+            .iter()
+            .map(|(x, _)| x.emote.alias_id)
+            .collect::<HashSet<i32>>();
+
+        let channel_ids = ch::channels
+            .filter(ch::alias_id.eq_any(alias_ids))
+            .select((ch::alias_id, ch::id))
+            .load::<(i32, i32)>(conn)
+            .expect("Failed to get channels")
+            .into_iter()
+            .collect::<HashMap<i32, i32>>();
+
+        let user_ids = get_user_ids(
+            conn,
+            usages
+                .iter()
+                .map(|(x, y)| (x.user_alias_id, y.user_alias_name.clone()))
+                .collect(),
+        );
+
+        let mut channel_usages: HashMap<EmoteKey, (String, i32)> = HashMap::new();
+        let mut daily_usages: Vec<NewEmoteDailyUsage> = Vec::new();
+
+        for (key, usage) in usages {
+            let channel_id = match channel_ids.get(&key.emote.alias_id) {
+                Some(v) => *v,
+                None => continue,
+            };
+
+            let channel_usage = channel_usages
+                .entry(key.emote.clone())
+                .or_insert((usage.emote_name.clone(), 0));
+            channel_usage.1 += usage.count;
+
+            if let Some(user_id) = user_ids.get(&key.user_alias_id) {
+                daily_usages.push(NewEmoteDailyUsage {
+                    channel_id,
+                    user_id: *user_id,
+                    provider: key.emote.provider.to_string(),
+                    emote_id: key.emote.emote_id,
+                    emote_name: usage.emote_name,
+                    day: key.day,
+                    usage_count: usage.count,
+                });
+            }
+        }
+
+        let values = channel_usages
+            .into_iter()
+            .map(|(key, (name, count))| NewEmoteUsage {
+                channel_id: channel_ids[&key.alias_id],
+                provider: key.provider.to_string(),
+                emote_id: key.emote_id,
+                emote_name: name,
+                usage_count: count,
+            })
+            .collect::<Vec<NewEmoteUsage>>();
+
+        for chunk in values.chunks(1000) {
+            insert_into(eu::emote_usages)
+                .values(chunk)
+                .on_conflict((eu::channel_id, eu::provider, eu::emote_id))
+                .do_update()
+                .set((
+                    eu::usage_count.eq(eu::usage_count + excluded(eu::usage_count)),
+                    eu::emote_name.eq(excluded(eu::emote_name)),
+                    eu::updated_at.eq(Utc::now().naive_utc()),
+                ))
+                .execute(conn)
+                .expect("Failed to update emote usages");
+        }
+
+        for chunk in daily_usages.chunks(1000) {
+            insert_into(edu::emote_daily_usages)
+                .values(chunk)
+                .on_conflict((
+                    edu::channel_id,
+                    edu::user_id,
+                    edu::provider,
+                    edu::emote_id,
+                    edu::day,
+                ))
+                .do_update()
+                .set((
+                    edu::usage_count.eq(edu::usage_count + excluded(edu::usage_count)),
+                    edu::emote_name.eq(excluded(edu::emote_name)),
+                ))
+                .execute(conn)
+                .expect("Failed to update daily emote usages");
+        }
+    }
+}
+
+/// Gets the IDs of the users by their Twitch IDs, creating the ones who are not stored yet.
+fn get_user_ids(conn: &mut PgConnection, users: HashMap<i32, String>) -> HashMap<i32, i32> {
+    let alias_ids = users.keys().cloned().collect::<Vec<i32>>();
+
+    let mut user_ids = us::users
+        .filter(us::alias_id.eq_any(&alias_ids))
+        .select((us::alias_id, us::id))
+        .load::<(i32, i32)>(conn)
+        .expect("Failed to get users")
+        .into_iter()
+        .collect::<HashMap<i32, i32>>();
+
+    let new_users = users
+        .into_iter()
+        .filter(|(x, _)| !user_ids.contains_key(x))
+        .map(|(alias_id, alias_name)| NewUser {
+            alias_id,
+            alias_name,
+        })
+        .collect::<Vec<NewUser>>();
+
+    if !new_users.is_empty() {
+        // the name may be taken by a renamed user, such usages are skipped
+        user_ids.extend(
+            insert_into(us::users)
+                .values(&new_users)
+                .on_conflict_do_nothing()
+                .returning((us::alias_id, us::id))
+                .get_results::<(i32, i32)>(conn)
+                .expect("Failed to create new users"),
+        );
+    }
+
+    user_ids
+}
+
+/// Gets the counted usages of the provider's emotes in the chat room.
+pub fn get_emote_usages(
+    conn: &mut PgConnection,
+    channel_id: i32,
+    provider: &EmoteProvider,
+) -> Vec<EmoteUsage> {
+    eu::emote_usages
+        .filter(eu::channel_id.eq(&channel_id))
+        .filter(eu::provider.eq(provider.to_string()))
+        .load::<EmoteUsage>(conn)
+        .expect("Failed to get emote usages")
+}
+
+/// Gets the date from the `--since` option of the request, e.g. `--since=7d`.
+/// Returns the option's value too, so it can be shown in the response.
+pub fn get_since_date(request: &Request) -> Result<Option<(String, NaiveDate)>, ResponseError> {
+    let value = match request.options.get("since") {
+        Some(v) if !v.is_empty() => v.clone(),
+        _ => return Ok(None),
+    };
+
+    let date = match parse_duration(value.as_str())
+        .filter(|x| *x > 0)
+        .and_then(|x| chrono::Duration::from_std(Duration::from_secs(x)).ok())
+        .and_then(|x| Utc::now().checked_sub_signed(x))
+    {
+        Some(v) => v.date_naive(),
+        None => return Err(ResponseError::IncorrectArgument(value)),
+    };
+
+    Ok(Some((value, date)))
+}
+
+/// Gets the current names of the counted emotes in the chat room by their IDs.
+/// The daily counts keep the names the emotes had on that day.
+fn get_emote_names(
+    conn: &mut PgConnection,
+    channel_id: i32,
+    provider: &EmoteProvider,
+) -> HashMap<String, String> {
+    get_emote_usages(conn, channel_id, provider)
+        .into_iter()
+        .map(|x| (x.emote_id, x.emote_name))
+        .collect()
+}
+
+/// Gets the emote counts in the chat room since the date, optionally only of the user.
+pub fn get_emote_counts(
+    conn: &mut PgConnection,
+    channel_id: i32,
+    provider: &EmoteProvider,
+    user_id: Option<i32>,
+    since: Option<NaiveDate>,
+) -> Vec<(String, i32)> {
+    let mut query = edu::emote_daily_usages
+        .filter(edu::channel_id.eq(&channel_id))
+        .filter(edu::provider.eq(provider.to_string()))
+        .group_by(edu::emote_id)
+        .select((
+            edu::emote_id,
+            dsl::max(edu::emote_name),
+            dsl::sum(edu::usage_count),
+        ))
+        .into_boxed();
+
+    if let Some(v) = user_id {
+        query = query.filter(edu::user_id.eq(v));
+    }
+
+    if let Some(v) = since {
+        query = query.filter(edu::day.ge(v));
+    }
+
+    let counts = query
+        .load::<(String, Option<String>, Option<i64>)>(conn)
+        .expect("Failed to get emote counts");
+
+    let names = get_emote_names(conn, channel_id, provider);
+
+    counts
+        .into_iter()
+        .map(|(id, name, count)| {
+            (
+                names.get(&id).cloned().or(name).unwrap_or(id),
+                count.unwrap_or_default() as i32,
+            )
+        })
+        .collect()
+}
+
+/// Gets the users' counts of the emotes in the chat room since the date,
+/// optionally only of the emote with the name.
+pub fn get_emote_user_counts(
+    conn: &mut PgConnection,
+    channel_id: i32,
+    provider: &EmoteProvider,
+    emote_name: Option<String>,
+    since: Option<NaiveDate>,
+) -> Vec<(String, i32)> {
+    let mut query = edu::emote_daily_usages
+        .inner_join(us::users)
+        .filter(edu::channel_id.eq(&channel_id))
+        .filter(edu::provider.eq(provider.to_string()))
+        .group_by(us::alias_name)
+        .select((us::alias_name, dsl::sum(edu::usage_count)))
+        .into_boxed();
+
+    // the emote is looked up by its current name, so the counts before renaming are included
+    if let Some(v) = emote_name {
+        let emote_ids = get_emote_names(conn, channel_id, provider)
+            .into_iter()
+            .filter(|(_, name)| name.eq(&v))
+            .map(|(id, _)| id)
+            .collect::<Vec<String>>();
+
+        query = query.filter(edu::emote_id.eq_any(emote_ids));
+    }
+
+    if let Some(v) = since {
+        query = query.filter(edu::day.ge(v));
+    }
+
+    query
+        .load::<(String, Option<i64>)>(conn)
+        .expect("Failed to get emote user counts")
+        .into_iter()
+        .map(|(x, y)| (x, y.unwrap_or_default() as i32))
+        .collect()
+}
+
+/// Gets the ID of the user by their login.
+/// Only the users who have talked in the joined chat rooms are stored.
+pub fn get_user_id(conn: &mut PgConnection, login: &str) -> Result<i32, ResponseError> {
+    match us::users
+        .filter(us::alias_name.eq(login))
+        .select(us::id)
+        .first::<i32>(conn)
+    {
+        Ok(v) => Ok(v),
+        Err(_) => Err(ResponseError::NotFound(login.to_string())),
+    }
+}
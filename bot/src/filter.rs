@@ -0,0 +1,306 @@
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use chrono::Utc;
+use common::{
+    establish_connection,
+    models::{BannedPhrase, BannedPhraseAction, LevelOfRights, NewBannedPhraseOffense, NewUser},
+    schema::{
+        banned_phrase_offenses::dsl as bpo, banned_phrases::dsl as bp,
+        channel_preferences::dsl as chp, channels::dsl as ch, rights::dsl as ri, users::dsl as us,
+    },
+};
+use diesel::{
+    insert_into, ExpressionMethods, OptionalExtension, PgConnection, QueryDsl, RunQueryDsl,
+};
+use log::error;
+use regex::Regex;
+use tokio::sync::Mutex;
+use twitch_api::types::{MsgIdRef, UserId};
+use twitch_irc::message::PrivmsgMessage;
+
+use crate::{
+    instance_bundle::InstanceBundle,
+    localization::LineId,
+    shared_variables::{
+        BANNED_PHRASE_CACHE_TTL_SEC, BANNED_PHRASE_DEFAULT_TIMEOUT_SEC,
+        BANNED_PHRASE_ESCALATION_FACTOR, BANNED_PHRASE_MAX_TIMEOUT_SEC,
+        BANNED_PHRASE_OFFENSE_WINDOW_SEC, DEFAULT_LANGUAGE,
+    },
+};
+
+/// Badges whose owners are never punished by the filter,
+/// along with bot administrators and users pinned to the VIP level or higher.
+const EXEMPT_BADGES: [&str; 3] = ["broadcaster", "moderator", "vip"];
+
+struct CompiledPhrase {
+    phrase: BannedPhrase,
+    regex: Regex,
+}
+
+struct ChannelPhrases {
+    channel_id: i32,
+    language: String,
+    phrases: Vec<CompiledPhrase>,
+}
+
+/// Checks the chat messages against the banned phrases of the chat room
+/// and punishes the senders, harder on every repeated offense.
+/// The phrases are cached and loaded again after [BANNED_PHRASE_CACHE_TTL_SEC].
+pub struct PhraseFilter {
+    channel_phrases: Mutex<HashMap<i32, (Instant, Arc<ChannelPhrases>)>>,
+}
+
+impl PhraseFilter {
+    pub fn new() -> Self {
+        Self {
+            channel_phrases: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Drops the cached phrases of the chat room, so the changes apply on the next message.
+    pub async fn invalidate(&self, alias_id: i32) {
+        self.channel_phrases.lock().await.remove(&alias_id);
+    }
+
+    /// Returns true if the message contained a banned phrase and its sender has been punished.
+    pub async fn check(&self, instance_bundle: &InstanceBundle, message: &PrivmsgMessage) -> bool {
+        if message
+            .badges
+            .iter()
+            .any(|x| EXEMPT_BADGES.contains(&x.name.as_str()))
+        {
+            return false;
+        }
+
+        let (alias_id, user_alias_id) = match (
+            message.channel_id.parse::<i32>(),
+            message.sender.id.parse::<i32>(),
+        ) {
+            (Ok(x), Ok(y)) => (x, y),
+            _ => return false,
+        };
+
+        let channel_phrases = self.get_channel_phrases(alias_id).await;
+
+        let phrase = match channel_phrases
+            .phrases
+            .iter()
+            .find(|x| x.regex.is_match(&message.message_text))
+        {
+            Some(v) => &v.phrase,
+            None => return false,
+        };
+
+        let conn = &mut establish_connection();
+
+        let (user_id, is_admin) = match get_user(conn, user_alias_id, &message.sender.login) {
+            Some(v) => v,
+            None => return false,
+        };
+
+        // the levels pinned with the rights command outweigh the badges
+        let pinned_level = ri::rights
+            .filter(ri::user_id.eq(&user_id))
+            .filter(ri::channel_id.eq(&channel_phrases.channel_id))
+            .filter(ri::is_fixed.eq(true))
+            .select(ri::level)
+            .first::<LevelOfRights>(conn)
+            .optional()
+            .expect("Failed to get rights");
+
+        if is_admin || pinned_level.is_some_and(|x| x >= LevelOfRights::Vip) {
+            return false;
+        }
+
+        let since =
+            Utc::now().naive_utc() - chrono::Duration::seconds(BANNED_PHRASE_OFFENSE_WINDOW_SEC);
+
+        let offenses = bpo::banned_phrase_offenses
+            .filter(bpo::channel_id.eq(&channel_phrases.channel_id))
+            .filter(bpo::user_id.eq(&user_id))
+            .filter(bpo::created_at.ge(&since))
+            .count()
+            .get_result::<i64>(conn)
+            .expect("Failed to count banned phrase offenses");
+
+        let (action, timeout_sec) = get_punishment(phrase, offenses);
+
+        let reason = instance_bundle
+            .localizator
+            .get_literal_text(channel_phrases.language.as_str(), LineId::FilterReason)
+            .unwrap_or_default();
+
+        let broadcaster_id = UserId::new(message.channel_id.clone());
+        let moderator_id = &instance_bundle.twitch_api_token.user_id;
+        let token = &*instance_bundle.twitch_api_token;
+
+        // timeouts and bans clear the recent messages of the user on their own
+        let result = match action {
+            BannedPhraseAction::Delete => instance_bundle
+                .twitch_api_client
+                .delete_chat_message(
+                    &broadcaster_id,
+                    moderator_id,
+                    MsgIdRef::from_str(&message.message_id),
+                    token,
+                )
+                .await
+                .map(|_| ()),
+            BannedPhraseAction::Timeout | BannedPhraseAction::Ban => instance_bundle
+                .twitch_api_client
+                .ban_user(
+                    &UserId::new(message.sender.id.clone()),
+                    reason.as_str(),
+                    timeout_sec.map(|x| x as u32),
+                    &broadcaster_id,
+                    moderator_id,
+                    token,
+                )
+                .await
+                .map(|_| ()),
+        };
+
+        // the offense is not counted if the punishment did not happen
+        if let Err(e) = result {
+            error!(
+                "Failed to punish {} in #{} for a banned phrase: {}",
+                message.sender.login, message.channel_login, e
+            );
+            return true;
+        }
+
+        insert_into(bpo::banned_phrase_offenses)
+            .values([NewBannedPhraseOffense {
+                channel_id: channel_phrases.channel_id,
+                user_id,
+                phrase_id: Some(phrase.id),
+                action,
+                timeout_sec,
+            }])
+            .execute(conn)
+            .expect("Failed to create a banned phrase offense");
+
+        true
+    }
+
+    async fn get_channel_phrases(&self, alias_id: i32) -> Arc<ChannelPhrases> {
+        if let Some((updated_at, phrases)) = self.channel_phrases.lock().await.get(&alias_id) {
+            if updated_at.elapsed() < Duration::from_secs(BANNED_PHRASE_CACHE_TTL_SEC) {
+                return phrases.clone();
+            }
+        }
+
+        let conn = &mut establish_connection();
+        let phrases = Arc::new(load_channel_phrases(conn, alias_id));
+
+        self.channel_phrases
+            .lock()
+            .await
+            .insert(alias_id, (Instant::now(), phrases.clone()));
+
+        phrases
+    }
+}
+
+fn load_channel_phrases(conn: &mut PgConnection, alias_id: i32) -> ChannelPhrases {
+    let channel_id = ch::channels
+        .filter(ch::alias_id.eq(&alias_id))
+        .filter(ch::opt_outed_at.is_null())
+        .select(ch::id)
+        .first::<i32>(conn)
+        .optional()
+        .expect("Failed to get channel");
+
+    let channel_id = match channel_id {
+        Some(v) => v,
+        None => {
+            return ChannelPhrases {
+                channel_id: 0,
+                language: DEFAULT_LANGUAGE.to_string(),
+                phrases: Vec::new(),
+            }
+        }
+    };
+
+    let language = chp::channel_preferences
+        .filter(chp::channel_id.eq(&channel_id))
+        .select(chp::language)
+        .first::<String>(conn)
+        .optional()
+        .expect("Failed to get channel preference")
+        .unwrap_or_else(|| DEFAULT_LANGUAGE.to_string());
+
+    let phrases = bp::banned_phrases
+        .filter(bp::channel_id.eq(&channel_id))
+        .order(bp::id.asc())
+        .load::<BannedPhrase>(conn)
+        .expect("Failed to get banned phrases")
+        .into_iter()
+        .filter_map(|phrase| match phrase.kind.compile(&phrase.pattern) {
+            Ok(regex) => Some(CompiledPhrase { phrase, regex }),
+            Err(e) => {
+                error!("Failed to compile banned phrase {}: {}", phrase.id, e);
+                None
+            }
+        })
+        .collect();
+
+    ChannelPhrases {
+        channel_id,
+        language,
+        phrases,
+    }
+}
+
+/// Gets the punishment for the offense, given the amount of the user's recent offenses.
+/// Deletions turn into timeouts on the second offense,
+/// and timeouts get [BANNED_PHRASE_ESCALATION_FACTOR] times longer on each one.
+fn get_punishment(phrase: &BannedPhrase, offenses: i64) -> (BannedPhraseAction, Option<i32>) {
+    let (base, steps) = match phrase.action {
+        BannedPhraseAction::Ban => return (BannedPhraseAction::Ban, None),
+        BannedPhraseAction::Delete if offenses == 0 => return (BannedPhraseAction::Delete, None),
+        BannedPhraseAction::Delete => (BANNED_PHRASE_DEFAULT_TIMEOUT_SEC, offenses - 1),
+        BannedPhraseAction::Timeout => (
+            phrase
+                .timeout_sec
+                .unwrap_or(BANNED_PHRASE_DEFAULT_TIMEOUT_SEC),
+            offenses,
+        ),
+    };
+
+    let duration = (base as i64)
+        .saturating_mul(BANNED_PHRASE_ESCALATION_FACTOR.saturating_pow(steps as u32))
+        .min(BANNED_PHRASE_MAX_TIMEOUT_SEC);
+
+    (BannedPhraseAction::Timeout, Some(duration as i32))
+}
+
+/// Gets the ID of the user by their Twitch ID and whether they are a bot administrator,
+/// creating them if they are not stored yet.
+fn get_user(conn: &mut PgConnection, alias_id: i32, alias_name: &str) -> Option<(i32, bool)> {
+    if let Some(user) = us::users
+        .filter(us::alias_id.eq(&alias_id))
+        .select((us::id, us::is_admin))
+        .first::<(i32, bool)>(conn)
+        .optional()
+        .expect("Failed to get user")
+    {
+        return Some(user);
+    }
+
+    // the name may be taken by a renamed user
+    insert_into(us::users)
+        .values([NewUser {
+            alias_id,
+            alias_name: alias_name.to_string(),
+        }])
+        .on_conflict_do_nothing()
+        .returning((us::id, us::is_admin))
+        .get_result::<(i32, bool)>(conn)
+        .optional()
+        .expect("Failed to create a new user")
+}
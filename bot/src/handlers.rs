@@ -1,22 +1,39 @@
-use std::{collections::HashSet, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    env,
+    str::FromStr,
+    sync::Arc,
+};
 
-use chrono::Utc;
-use diesel::{insert_into, update, BelongingToDsl, ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl};
-use twitch_api::{types::UserId, helix::chat::GetChattersRequest};
+use chrono::{Duration, NaiveDateTime, Utc};
+use diesel::{delete, insert_into, update, BelongingToDsl, ExpressionMethods, OptionalExtension, PgConnection, QueryDsl, RunQueryDsl};
+use twitch_api::{types::UserId, twitch_oauth2::TwitchToken};
+use log::{error, info, warn};
+use serde::Deserialize;
 use twitch_irc::message::PrivmsgMessage;
 
 use crate::{
-    commands::{request::Request, response::Response, CommandLoader},
+    chatters::{get_massping_chatters, record_active_chatter},
+    commands::{
+        request::Request,
+        response::{Response, ResponseError},
+        CommandLoader,
+    },
     instance_bundle::InstanceBundle,
-    utils::split_and_wrap_lines,
+    shared_variables::{DEFAULT_LANGUAGE, DEFAULT_PREFIX, DIGEST_INTERVAL_SEC, EVENT_REMINDER_OFFSETS_SEC, TWITCH_HELIX_API_URL},
+    utils::{cron::CronSchedule, format_timestamp, split_and_wrap_lines},
 };
 
 use common::{
     establish_connection, models::{
-        Channel, CustomCommand, Event, EventFlag, EventSubscription, EventType, NewAction, Timer, User
+        Channel, ChannelUpdate, ChannelUpdateAction, CustomCommand, DigestNotification, Event, EventFlag, EventRunSource, EventSubscription, EventTrigger, EventType,
+        NewAction, NewChannelPreference, NewDigestNotification, NewEventRun, NotificationDelivery, Timer, User, UserPreference
     }, schema::{
-        channels::dsl as ch, custom_commands::dsl as cc,
-        events::dsl as ev, timers::dsl as ti, users::dsl as us, actions::dsl as ac
+        channel_preferences::dsl as chp, channel_updates::dsl as chu, channels::dsl as ch, custom_commands::dsl as cc, digest_notifications::dsl as dn,
+        events::dsl as ev, event_runs::dsl as er, event_subscriptions::dsl as evs, rights::dsl as ri, event_triggers::dsl as et, timers::dsl as ti, users::dsl as us, actions::dsl as ac,
+        user_preferences::dsl as up, emote_usages::dsl as eu,
+        emote_daily_usages::dsl as edu, emote_events::dsl as ee,
+        banned_phrases::dsl as bp, banned_phrase_offenses::dsl as bpo
     }
 };
 
@@ -25,6 +42,19 @@ pub async fn handle_chat_message(
     command_loader: &CommandLoader,
     message: PrivmsgMessage,
 ) {
+    record_active_chatter(&instance_bundle, &message).await;
+
+    // punished messages don't count towards the emote statistics
+    if instance_bundle
+        .phrase_filter
+        .check(&instance_bundle, &message)
+        .await
+    {
+        return;
+    }
+
+    instance_bundle.emote_usage_counter.count(&message).await;
+
     let conn = &mut establish_connection();
 
     if let Some(request) = Request::try_from(&message, command_loader, conn) {
@@ -134,6 +164,422 @@ pub async fn handle_timers(instance_bundle: &InstanceBundle) {
     }
 }
 
+pub async fn handle_scheduled_events(instance_bundle: &InstanceBundle) {
+    let current_timestamp = Utc::now().naive_utc();
+    let conn = &mut establish_connection();
+    let max_reminder_offset = EVENT_REMINDER_OFFSETS_SEC
+        .iter()
+        .max()
+        .copied()
+        .unwrap_or(0);
+    let events = ev::events
+        .filter(ev::is_enabled.eq(true))
+        .filter(ev::scheduled_at.le(current_timestamp + Duration::seconds(max_reminder_offset)))
+        .load::<Event>(conn)
+        .expect("Failed to get scheduled events");
+
+    for event in events {
+        let scheduled_at = match event.scheduled_at {
+            Some(v) => v,
+            None => continue,
+        };
+
+        let is_reminder = scheduled_at > current_timestamp;
+
+        if is_reminder {
+            // only the events showing the countdown are worth a reminder
+            if !event.message.contains("{countdown}") || event.flags.contains(&EventFlag::Silent) {
+                continue;
+            }
+
+            let reminder_at = EVENT_REMINDER_OFFSETS_SEC
+                .iter()
+                .map(|x| scheduled_at - Duration::seconds(*x))
+                .filter(|x| *x <= current_timestamp)
+                .max();
+
+            match reminder_at {
+                Some(v) if event.reminded_at.is_none_or(|x| x < v) => {}
+                _ => continue,
+            }
+
+            update(ev::events.find(event.id))
+                .set(ev::reminded_at.eq(Some(current_timestamp)))
+                .execute(conn)
+                .expect("Failed to update the event reminder");
+        } else {
+            // one-off events are unscheduled, recurring ones move to the next occurrence
+            let next_scheduled_at = event
+                .schedule_cron
+                .as_ref()
+                .and_then(|x| CronSchedule::from_str(x).ok())
+                .and_then(|x| x.next_after(current_timestamp));
+
+            update(ev::events.find(event.id))
+                .set((
+                    ev::scheduled_at.eq(&next_scheduled_at),
+                    ev::reminded_at.eq(Some(current_timestamp)),
+                ))
+                .execute(conn)
+                .expect("Failed to update the event schedule");
+        }
+
+        let channel = ch::channels
+            .find(event.channel_id)
+            .filter(ch::opt_outed_at.is_null())
+            .first::<Channel>(conn)
+            .optional()
+            .expect("Failed to get the event channel");
+
+        let channel = match channel {
+            Some(v) => v,
+            None => continue,
+        };
+
+        // the firing keeps its own schedule, so its countdown ends right now
+        let result = if is_reminder {
+            send_event_reminder(instance_bundle, &channel, &event).await
+        } else {
+            fire_event(
+                conn,
+                instance_bundle,
+                &channel,
+                &event,
+                Vec::new(),
+                EventRunSource::Schedule,
+            )
+            .await
+        };
+
+        if let Err(e) = result {
+            error!("Failed to fire the scheduled event ID {}: {:?}", event.id, e);
+        }
+    }
+}
+
+/// Fires the custom events triggered through the API.
+pub async fn handle_event_triggers(instance_bundle: &InstanceBundle) {
+    let conn = &mut establish_connection();
+    let triggers = et::event_triggers
+        .order(et::created_at.asc())
+        .load::<EventTrigger>(conn)
+        .expect("Failed to get event triggers");
+
+    for trigger in triggers {
+        delete(et::event_triggers.find(trigger.id))
+            .execute(conn)
+            .expect("Failed to delete the event trigger");
+
+        let event = ev::events
+            .find(trigger.event_id)
+            .filter(ev::is_enabled.eq(true))
+            .first::<Event>(conn)
+            .optional()
+            .expect("Failed to get the triggered event");
+
+        let event = match event {
+            Some(v) => v,
+            None => continue,
+        };
+
+        let channel = ch::channels
+            .find(event.channel_id)
+            .filter(ch::opt_outed_at.is_null())
+            .first::<Channel>(conn)
+            .optional()
+            .expect("Failed to get the event channel");
+
+        let channel = match channel {
+            Some(v) => v,
+            None => continue,
+        };
+
+        if let Err(e) = fire_event(
+            conn,
+            instance_bundle,
+            &channel,
+            &event,
+            trigger.parameters,
+            EventRunSource::Api,
+        )
+        .await
+        {
+            error!("Failed to fire the triggered event ID {}: {:?}", event.id, e);
+        }
+    }
+}
+
+/// Joins the chat room and starts listening to its 7TV and stream events.
+pub async fn join_channel(conn: &mut PgConnection, instance_bundle: &InstanceBundle, channel: &Channel) {
+    if let Err(e) = instance_bundle
+        .twitch_irc_client
+        .join(channel.alias_name.clone())
+    {
+        error!("Failed to join chat room ID {}: {}", channel.id, e);
+        return;
+    }
+
+    // channels added through the API don't have preferences yet
+    insert_into(chp::channel_preferences)
+        .values([NewChannelPreference {
+            channel_id: channel.id,
+            prefix: match env::var("BOT_DEFAULT_PREFIX") {
+                Ok(v) => v,
+                Err(_) => DEFAULT_PREFIX.to_string(),
+            },
+            language: match env::var("BOT_DEFAULT_LANGUAGE") {
+                Ok(v) => v,
+                Err(_) => DEFAULT_LANGUAGE.to_string(),
+            },
+        }])
+        .on_conflict(chp::channel_id)
+        .do_nothing()
+        .execute(conn)
+        .expect("Failed to insert preferences for the channel");
+
+    let channel_id = UserId::new(channel.alias_id.to_string());
+
+    instance_bundle
+        .seventv_eventapi_removed_data
+        .lock()
+        .await
+        .remove(&channel_id);
+    instance_bundle
+        .seventv_eventapi_data
+        .lock()
+        .await
+        .insert(channel_id.clone());
+    instance_bundle
+        .bttv_websocket_data
+        .lock()
+        .await
+        .insert(channel_id);
+
+    let targets = ev::events
+        .filter(ev::channel_id.eq(&channel.id))
+        .filter(ev::target_alias_id.is_not_null())
+        .select(ev::target_alias_id)
+        .load::<Option<i32>>(conn)
+        .expect("Failed to get events");
+
+    instance_bundle
+        .twitch_livestream_websocket_data
+        .lock()
+        .await
+        .extend(targets.iter().flatten().map(|x| UserId::new(x.to_string())));
+
+    info!(
+        "Joined chat room: ID: {}, alias ID: {}, alias name: {}",
+        channel.id, channel.alias_id, channel.alias_name
+    );
+}
+
+/// Leaves the chat room and stops listening to its 7TV and stream events.
+pub async fn part_channel(conn: &mut PgConnection, instance_bundle: &InstanceBundle, channel: &Channel) {
+    instance_bundle
+        .twitch_irc_client
+        .part(channel.alias_name.clone());
+
+    let channel_id = UserId::new(channel.alias_id.to_string());
+
+    instance_bundle
+        .seventv_eventapi_data
+        .lock()
+        .await
+        .remove(&channel_id);
+    instance_bundle
+        .bttv_websocket_data
+        .lock()
+        .await
+        .remove(&channel_id);
+    instance_bundle
+        .seventv_eventapi_removed_data
+        .lock()
+        .await
+        .insert(channel_id);
+
+    let targets = ev::events
+        .filter(ev::channel_id.eq(&channel.id))
+        .filter(ev::target_alias_id.is_not_null())
+        .select(ev::target_alias_id)
+        .load::<Option<i32>>(conn)
+        .expect("Failed to get events")
+        .into_iter()
+        .flatten()
+        .collect::<Vec<i32>>();
+
+    // stream events of other chat rooms may target the same users
+    let remaining_targets = ev::events
+        .inner_join(ch::channels)
+        .filter(ch::opt_outed_at.is_null())
+        .filter(ev::target_alias_id.eq_any(&targets))
+        .select(ev::target_alias_id)
+        .load::<Option<i32>>(conn)
+        .expect("Failed to get events")
+        .into_iter()
+        .flatten()
+        .collect::<Vec<i32>>();
+
+    let mut ids = instance_bundle.twitch_livestream_websocket_data.lock().await;
+
+    for target in targets.iter().filter(|x| !remaining_targets.contains(x)) {
+        ids.remove(&UserId::new(target.to_string()));
+    }
+
+    drop(ids);
+
+    info!(
+        "Parted chat room: ID: {}, alias ID: {}, alias name: {}",
+        channel.id, channel.alias_id, channel.alias_name
+    );
+}
+
+/// Joins and parts chat rooms to match their opt-out state in the database,
+/// e.g. after it was changed through the API.
+pub async fn handle_channel_sync(instance_bundle: &InstanceBundle) {
+    let conn = &mut establish_connection();
+    let channels = ch::channels
+        .load::<Channel>(conn)
+        .expect("Failed to get channels");
+
+    for channel in channels {
+        let (is_wanted, _) = instance_bundle
+            .twitch_irc_client
+            .get_channel_status(channel.alias_name.clone())
+            .await;
+
+        match (channel.opt_outed_at.is_none(), is_wanted) {
+            (true, false) => join_channel(conn, instance_bundle, &channel).await,
+            (false, true) => part_channel(conn, instance_bundle, &channel).await,
+            _ => {}
+        }
+    }
+}
+
+/// Joins and parts chat rooms requested through the API right away.
+pub async fn handle_channel_updates(instance_bundle: &InstanceBundle) {
+    let conn = &mut establish_connection();
+    let updates = chu::channel_updates
+        .order(chu::created_at.asc())
+        .load::<ChannelUpdate>(conn)
+        .expect("Failed to get channel updates");
+
+    for channel_update in updates {
+        delete(chu::channel_updates.find(channel_update.id))
+            .execute(conn)
+            .expect("Failed to delete the channel update");
+
+        let channel = ch::channels
+            .find(channel_update.channel_id)
+            .first::<Channel>(conn)
+            .optional()
+            .expect("Failed to get the updated channel");
+
+        let channel = match channel {
+            Some(v) => v,
+            None => continue,
+        };
+
+        let (is_wanted, _) = instance_bundle
+            .twitch_irc_client
+            .get_channel_status(channel.alias_name.clone())
+            .await;
+
+        // the update may be outdated if the channel was joined and parted in a row
+        match (channel_update.action, channel.opt_outed_at.is_none(), is_wanted) {
+            (ChannelUpdateAction::Join, true, false) => {
+                join_channel(conn, instance_bundle, &channel).await
+            }
+            (ChannelUpdateAction::Part, false, true) => {
+                part_channel(conn, instance_bundle, &channel).await
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Deletes the data of the opted out chat rooms whose grace period has ended.
+pub fn handle_channel_purges() {
+    let conn = &mut establish_connection();
+    let channels = ch::channels
+        .filter(ch::opt_outed_at.is_not_null())
+        .filter(ch::purge_at.le(Utc::now().naive_utc()))
+        .load::<Channel>(conn)
+        .expect("Failed to get channels to purge");
+
+    for channel in channels {
+        let event_ids = ev::events
+            .filter(ev::channel_id.eq(&channel.id))
+            .select(ev::id)
+            .load::<i32>(conn)
+            .expect("Failed to get events to purge");
+
+        delete(evs::event_subscriptions.filter(evs::event_id.eq_any(&event_ids)))
+            .execute(conn)
+            .expect("Failed to purge event subscriptions");
+
+        // subscriptions delivered to this chat room fall back to the event's chat room
+        update(evs::event_subscriptions.filter(evs::channel_id.eq(&channel.id)))
+            .set(evs::channel_id.eq(None::<i32>))
+            .execute(conn)
+            .expect("Failed to update event subscriptions");
+
+        delete(ev::events.filter(ev::channel_id.eq(&channel.id)))
+            .execute(conn)
+            .expect("Failed to purge events");
+
+        delete(ti::timers.filter(ti::channel_id.eq(&channel.id)))
+            .execute(conn)
+            .expect("Failed to purge timers");
+
+        delete(cc::custom_commands.filter(cc::channel_id.eq(&channel.id)))
+            .execute(conn)
+            .expect("Failed to purge custom commands");
+
+        delete(ri::rights.filter(ri::channel_id.eq(&channel.id)))
+            .execute(conn)
+            .expect("Failed to purge rights");
+
+        delete(ac::actions.filter(ac::channel_id.eq(&channel.id)))
+            .execute(conn)
+            .expect("Failed to purge actions");
+
+        delete(eu::emote_usages.filter(eu::channel_id.eq(&channel.id)))
+            .execute(conn)
+            .expect("Failed to purge emote usages");
+
+        delete(edu::emote_daily_usages.filter(edu::channel_id.eq(&channel.id)))
+            .execute(conn)
+            .expect("Failed to purge daily emote usages");
+
+        delete(ee::emote_events.filter(ee::channel_id.eq(&channel.id)))
+            .execute(conn)
+            .expect("Failed to purge emote events");
+
+        delete(bpo::banned_phrase_offenses.filter(bpo::channel_id.eq(&channel.id)))
+            .execute(conn)
+            .expect("Failed to purge banned phrase offenses");
+
+        delete(bp::banned_phrases.filter(bp::channel_id.eq(&channel.id)))
+            .execute(conn)
+            .expect("Failed to purge banned phrases");
+
+        delete(chp::channel_preferences.filter(chp::channel_id.eq(&channel.id)))
+            .execute(conn)
+            .expect("Failed to purge channel preferences");
+
+        update(ch::channels.find(&channel.id))
+            .set(ch::purge_at.eq(None::<NaiveDateTime>))
+            .execute(conn)
+            .expect("Failed to update the channel");
+
+        info!(
+            "Purged chat room data: ID: {}, alias ID: {}, alias name: {}",
+            channel.id, channel.alias_id, channel.alias_name
+        );
+    }
+}
+
 pub async fn handle_custom_commands(
     conn: &mut PgConnection,
     instance_bundle: &InstanceBundle,
@@ -176,6 +622,7 @@ pub async fn handle_stream_event(
     let events = match ev::events
         .filter(ev::target_alias_id.eq(&target_id))
         .filter(ev::event_type.eq(&event_type))
+        .filter(ev::is_enabled.eq(true))
         .load::<Event>(conn)
     {
         Ok(v) => v,
@@ -186,105 +633,457 @@ pub async fn handle_stream_event(
     };
 
     for event in events {
-        tokio::spawn({
-            let instance_bundle = instance_bundle.clone();
-            let channel = match ch::channels.find(event.channel_id).first::<Channel>(conn) {
-                Ok(v) => v,
-                Err(e) => {
-                    println!(
-                        "[STREAM EVENT HANDLER] Failed to get channel for event ID {}: {}",
-                        event.id, e
-                    );
-                    continue;
-                }
-            };
-            let subs = match EventSubscription::belonging_to(&event).load::<EventSubscription>(conn)
+        let channel = match ch::channels.find(event.channel_id).first::<Channel>(conn) {
+            Ok(v) => v,
+            Err(e) => {
+                println!(
+                    "[STREAM EVENT HANDLER] Failed to get channel for event ID {}: {}",
+                    event.id, e
+                );
+                continue;
+            }
+        };
+
+        let instance_bundle = instance_bundle.clone();
+        let parameters = parameters.clone();
+
+        // every event is fired in its own task, so a slow one doesn't delay the others
+        tokio::spawn(async move {
+            let conn = &mut establish_connection();
+
+            if let Err(e) = fire_event(
+                conn,
+                &instance_bundle,
+                &channel,
+                &event,
+                parameters,
+                EventRunSource::Stream,
+            )
+            .await
             {
-                Ok(v) => v,
-                Err(e) => {
-                    println!(
-                        "[STREAM EVENT HANDLER] Failed to get subscriptions for event ID {}: {}",
-                        event.id, e
-                    );
-                    continue;
-                }
+                println!(
+                    "[STREAM EVENT HANDLER] Failed to fire event ID {}: {:?}",
+                    event.id, e
+                );
+            }
+        });
+    }
+}
+
+/// Sends the event message to the channel, mentioning its subscribers
+/// and following the event flags.
+pub async fn fire_event(
+    conn: &mut PgConnection,
+    instance_bundle: &InstanceBundle,
+    channel: &Channel,
+    event: &Event,
+    parameters: Vec<String>,
+    source: EventRunSource,
+) -> Result<(), ResponseError> {
+    let mut subs: HashSet<String> = HashSet::new();
+    let mut remote_subs: HashMap<i32, HashSet<String>> = HashMap::new();
+    let mut whisper_user_ids: Vec<i32> = Vec::new();
+    let mut digest_user_ids: Vec<i32> = Vec::new();
+
+    if !event.flags.contains(&EventFlag::NoMentions) {
+        let event_subs = EventSubscription::belonging_to(event)
+            .load::<EventSubscription>(conn)
+            .expect("Failed to load subscriptions for event");
+
+        let user_ids = event_subs.iter().map(|x| x.user_id).collect::<Vec<i32>>();
+
+        let users = us::users
+            .filter(us::id.eq_any(&user_ids))
+            .load::<User>(conn)
+            .expect("Failed to load users for event");
+
+        let preferences = up::user_preferences
+            .filter(up::user_id.eq_any(&user_ids))
+            .load::<UserPreference>(conn)
+            .expect("Failed to load user preferences for event");
+
+        let current_timestamp = Utc::now().naive_utc();
+
+        for user in users {
+            let preference = preferences.iter().find(|x| x.user_id == user.id);
+
+            let (delivery, is_quiet) = match preference {
+                Some(p) if p.is_muted => continue,
+                Some(p) => (
+                    p.notification_delivery.clone(),
+                    p.is_quiet_at(current_timestamp),
+                ),
+                None => (NotificationDelivery::Chat, false),
             };
 
-            let users = match us::users.load::<User>(conn) {
-                Ok(v) => v,
-                Err(e) => {
-                    println!(
-                        "[STREAM EVENT HANDLER] Failed to get users for event ID {}: {}",
-                        event.id, e
-                    );
-                    continue;
+            match delivery {
+                NotificationDelivery::Chat if !is_quiet => {
+                    let delivery_channel_id = event_subs
+                        .iter()
+                        .find(|x| x.user_id == user.id)
+                        .and_then(|x| x.channel_id)
+                        .unwrap_or(channel.id);
+
+                    if delivery_channel_id == channel.id {
+                        subs.insert(format!("@{}", user.alias_name));
+                    } else {
+                        remote_subs
+                            .entry(delivery_channel_id)
+                            .or_default()
+                            .insert(format!("@{}", user.alias_name));
+                    }
                 }
-            };
+                NotificationDelivery::Whisper if !is_quiet => {
+                    whisper_user_ids.push(user.alias_id)
+                }
+                NotificationDelivery::Digest => digest_user_ids.push(user.id),
+                _ => {}
+            }
+        }
 
-            let users = users
-                .iter()
-                .filter(|x| subs.iter().any(|y| y.user_id == x.id))
-                .map(|x| format!("@{}", x.alias_name))
-                .collect::<Vec<String>>();
+        // the subscribers are still mentioned if the chatters can't be fetched
+        if event.flags.contains(&EventFlag::Massping) {
+            match get_massping_chatters(conn, instance_bundle, channel, None).await {
+                Ok(chatters) => subs.extend(chatters.iter().map(|x| format!("@{}", x))),
+                Err(e) => error!(
+                    "Failed to get chatters to massping for event ID {}: {:?}",
+                    event.id, e
+                ),
+            }
+        }
+    }
 
-            let mut subs: HashSet<String> = HashSet::new();
+    let line = format_event_message(instance_bundle, event, parameters.clone());
 
-            subs.extend(users);
+    let mut run = NewEventRun {
+        event_id: event.id,
+        source,
+        parameters,
+        notified_count: 0,
+        message_ids: Vec::new(),
+    };
 
-            let parameters = parameters.clone();
+    if event.flags.contains(&EventFlag::Silent) {
+        info!(
+            "Event ID {} has been fired silently in channel ID {}",
+            event.id, channel.id
+        );
 
-            async move {
-                if event.flags.contains(&EventFlag::Massping) {
-                    let broadcaster_id = channel.alias_id.to_string();
-                    let moderator_id = instance_bundle.twitch_api_token.user_id.clone().take();
+        disable_once_event(conn, event);
 
-                    let chatters = match instance_bundle.twitch_api_client.req_get(GetChattersRequest::new(broadcaster_id.as_str(), moderator_id.as_str()), &*instance_bundle.twitch_api_token).await {
-                        Ok(v) => v,
-                        Err(e) => {
-                            println!("[STREAM EVENT HANDLER] Failed to get chatters for channel ID {}: {}", channel.id, e);
-                            return; 
-                        }
-                    };
+        insert_into(er::event_runs)
+            .values([run])
+            .execute(conn)
+            .expect("Failed to record the event run");
 
-                    let chatters = chatters.data.iter().map(|x| format!("@{}", x.user_login)).collect::<HashSet<String>>();
+        return Ok(());
+    }
 
-                    subs.extend(chatters);
-                }
+    run.notified_count = (subs.len()
+        + remote_subs.values().map(|x| x.len()).sum::<usize>()
+        + whisper_user_ids.len()
+        + digest_user_ids.len()) as i32;
 
-                let placeholders = instance_bundle.localizator.parse_placeholders(&event.message);
-                let line = instance_bundle.localizator.replace_placeholders(event.message, placeholders, parameters, None);
+    let remote_line = format!("{} · {}", channel.alias_name, line);
+    let notification = format!("⚡ {}", remote_line);
 
-                if subs.is_empty() {
-                    instance_bundle
-                        .twitch_irc_client
-                        .say(channel.alias_name.clone(), format!("⚡ {}", line))
-                        .await
-                        .expect("Failed to send a message");
-                    return;
-                }
+    if !whisper_user_ids.is_empty() {
+        let mut queue = instance_bundle.twitch_whisper_queue.lock().await;
 
-                let formatted_subs = split_and_wrap_lines(
-                    subs.into_iter()
-                        .collect::<Vec<String>>()
-                        .join(", ")
-                        .as_str(),
-                    ", ",
-                    300 - line.len(),
-                );
+        for user_id in whisper_user_ids {
+            queue.push_back((UserId::new(user_id.to_string()), notification.clone()));
+        }
+    }
 
-                for formatted_sub in formatted_subs {
-                    instance_bundle
-                        .twitch_irc_client
-                        .say(
-                            channel.alias_name.clone(),
-                            format!("⚡ {} · {}", line, formatted_sub),
-                        )
-                        .await
-                        .expect("Failed to send a message");
-                }
+    if !digest_user_ids.is_empty() {
+        insert_into(dn::digest_notifications)
+            .values(
+                digest_user_ids
+                    .iter()
+                    .map(|x| NewDigestNotification {
+                        user_id: *x,
+                        message: notification.clone(),
+                    })
+                    .collect::<Vec<NewDigestNotification>>(),
+            )
+            .execute(conn)
+            .expect("Failed to insert digest notifications");
+    }
+
+    let mut is_sent = false;
+
+    for line in format_event_lines(&line, subs) {
+        if let Ok(id) = send_event_line(instance_bundle, channel, event, line).await {
+            is_sent = true;
+            run.message_ids.extend(id);
+        }
+    }
+
+    for (channel_id, mentions) in remote_subs {
+        let remote_channel = match ch::channels.find(channel_id).first::<Channel>(conn) {
+            Ok(v) if v.opt_outed_at.is_none() => v,
+            _ => continue,
+        };
+
+        for line in format_event_lines(&remote_line, mentions) {
+            if let Ok(id) = send_event_line(instance_bundle, &remote_channel, event, line).await {
+                is_sent = true;
+                run.message_ids.extend(id);
             }
-        })
+        }
+    }
+
+    // a failed event can be fired again
+    if is_sent {
+        disable_once_event(conn, event);
+    }
+
+    insert_into(er::event_runs)
+        .values([run])
+        .execute(conn)
+        .expect("Failed to record the event run");
+
+    if is_sent {
+        Ok(())
+    } else {
+        Err(ResponseError::SomethingWentWrong)
+    }
+}
+
+/// Posts the event message ahead of its scheduled firing, without mentioning anyone.
+async fn send_event_reminder(
+    instance_bundle: &InstanceBundle,
+    channel: &Channel,
+    event: &Event,
+) -> Result<(), ResponseError> {
+    let line = format_event_message(instance_bundle, event, Vec::new());
+
+    for line in format_event_lines(&line, HashSet::new()) {
+        send_event_line(instance_bundle, channel, event, line).await?;
+    }
+
+    Ok(())
+}
+
+fn format_event_message(
+    instance_bundle: &InstanceBundle,
+    event: &Event,
+    parameters: Vec<String>,
+) -> String {
+    // {countdown} is the time left until the scheduled firing
+    let countdown = event
+        .scheduled_at
+        .map(|x| (x - Utc::now().naive_utc()).num_seconds().max(0) as u64)
+        .unwrap_or(0);
+    let message = event
+        .message
+        .replace("{countdown}", &format_timestamp(countdown));
+
+    let placeholders = instance_bundle.localizator.parse_placeholders(&message);
+
+    instance_bundle
+        .localizator
+        .replace_placeholders(message, placeholders, parameters, None)
+}
+
+fn disable_once_event(conn: &mut PgConnection, event: &Event) {
+    if event.flags.contains(&EventFlag::Once) {
+        update(ev::events.find(event.id))
+            .set(ev::is_enabled.eq(false))
+            .execute(conn)
+            .expect("Failed to disable the event");
+    }
+}
+
+/// Sends a line of the event message to the chat room following the event flags.
+/// Returns the ID of the sent message if it is known.
+async fn send_event_line(
+    instance_bundle: &InstanceBundle,
+    channel: &Channel,
+    event: &Event,
+    line: String,
+) -> Result<Option<String>, ResponseError> {
+    if event.flags.contains(&EventFlag::Announce) {
+        let broadcaster_id = channel.alias_id.to_string();
+        let moderator_id = instance_bundle.twitch_api_token.user_id.clone().take();
+        let color = event
+            .announcement_color
+            .clone()
+            .unwrap_or_else(|| "primary".to_string());
+
+        match instance_bundle
+            .twitch_api_client
+            .send_chat_announcement(
+                broadcaster_id.as_str(),
+                moderator_id.as_str(),
+                line.as_str(),
+                color.as_str(),
+                &*instance_bundle.twitch_api_token,
+            )
+            .await
+        {
+            Ok(_) => return Ok(None),
+            // the bot may not be a moderator in the chat room
+            Err(e) => warn!(
+                "Failed to announce event ID {} in channel ID {}, sending it as a message: {}",
+                event.id, channel.id, e
+            ),
+        }
+    } else if event.flags.contains(&EventFlag::Me) {
+        return match instance_bundle
+            .twitch_irc_client
+            .me(channel.alias_name.clone(), line)
+            .await
+        {
+            Ok(_) => Ok(None),
+            Err(e) => {
+                error!(
+                    "Failed to send a message to channel ID {}: {}",
+                    channel.id, e
+                );
+                Err(ResponseError::SomethingWentWrong)
+            }
+        };
+    }
+
+    send_chat_message(instance_bundle, channel, line).await
+}
+
+#[derive(Deserialize)]
+struct SentChatMessageResponse {
+    pub data: Vec<SentChatMessage>,
+}
+
+#[derive(Deserialize)]
+struct SentChatMessage {
+    pub message_id: String,
+    pub is_sent: bool,
+}
+
+/// Sends a message through Helix to get its ID back.
+/// Falls back to IRC if the request fails (e.g. the token lacks the `user:write:chat` scope).
+async fn send_chat_message(
+    instance_bundle: &InstanceBundle,
+    channel: &Channel,
+    message: String,
+) -> Result<Option<String>, ResponseError> {
+    let token = &*instance_bundle.twitch_api_token;
+
+    // since HelixClient doesn't support the Send Chat Message endpoint yet,
+    // the request is made manually with the client the Helix requests are sent with
+    let response = instance_bundle
+        .twitch_api_client
+        .get_client()
+        .post(format!("{}/chat/messages", TWITCH_HELIX_API_URL))
+        .bearer_auth(token.access_token.secret())
+        .header("Client-Id", token.client_id().as_str())
+        .json(&serde_json::json!({
+            "broadcaster_id": channel.alias_id.to_string(),
+            "sender_id": token.user_id.as_str(),
+            "message": message,
+        }))
+        .send()
+        .await;
+
+    if let Ok(response) = response {
+        if response.status() == reqwest::StatusCode::OK {
+            return Ok(match response.json::<SentChatMessageResponse>().await {
+                Ok(json) => json
+                    .data
+                    .into_iter()
+                    .find(|x| x.is_sent)
+                    .map(|x| x.message_id),
+                Err(_) => None,
+            });
+        }
+    }
+
+    match instance_bundle
+        .twitch_irc_client
+        .say(channel.alias_name.clone(), message)
         .await
-        .unwrap();
+    {
+        Ok(_) => Ok(None),
+        Err(e) => {
+            error!(
+                "Failed to send a message to channel ID {}: {}",
+                channel.id, e
+            );
+            Err(ResponseError::SomethingWentWrong)
+        }
+    }
+}
+
+fn format_event_lines(line: &str, mentions: HashSet<String>) -> Vec<String> {
+    if mentions.is_empty() {
+        return vec![format!("⚡ {}", line)];
+    }
+
+    split_and_wrap_lines(
+        mentions.into_iter().collect::<Vec<String>>().join(", ").as_str(),
+        ", ",
+        300usize.saturating_sub(line.len()),
+    )
+    .iter()
+    .map(|x| format!("⚡ {} · {}", line, x))
+    .collect::<Vec<String>>()
+}
+
+pub async fn handle_notification_digests(instance_bundle: &InstanceBundle) {
+    let current_timestamp = Utc::now().naive_utc();
+    let conn = &mut establish_connection();
+
+    let digests = dn::digest_notifications
+        .order(dn::created_at.asc())
+        .load::<DigestNotification>(conn)
+        .expect("Failed to get digest notifications");
+
+    let mut user_ids = digests.iter().map(|x| x.user_id).collect::<Vec<i32>>();
+    user_ids.sort();
+    user_ids.dedup();
+
+    for user_id in user_ids {
+        let user_digests = digests
+            .iter()
+            .filter(|x| x.user_id == user_id)
+            .collect::<Vec<&DigestNotification>>();
+
+        // the oldest notification decides when the digest is due
+        if current_timestamp.timestamp() - user_digests[0].created_at.timestamp()
+            < DIGEST_INTERVAL_SEC
+        {
+            continue;
+        }
+
+        let user = match us::users.find(user_id).first::<User>(conn) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+
+        let lines = split_and_wrap_lines(
+            user_digests
+                .iter()
+                .map(|x| x.message.clone())
+                .collect::<Vec<String>>()
+                .join(" | ")
+                .as_str(),
+            " | ",
+            500,
+        );
+
+        let mut queue = instance_bundle.twitch_whisper_queue.lock().await;
+
+        for line in lines {
+            queue.push_back((UserId::new(user.alias_id.to_string()), line));
+        }
+
+        drop(queue);
+
+        delete(
+            dn::digest_notifications
+                .filter(dn::id.eq_any(user_digests.iter().map(|x| x.id).collect::<Vec<i32>>())),
+        )
+        .execute(conn)
+        .expect("Failed to delete digest notifications");
     }
 }
@@ -0,0 +1,190 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use log::warn;
+use reqwest::{Client, StatusCode, Url};
+use serde::de::DeserializeOwned;
+use tokio::sync::Mutex;
+
+use crate::{
+    commands::response::ResponseError,
+    shared_variables::{HTTP_DEFAULT_TIMEOUT_SEC, HTTP_MAX_RETRIES, HTTP_RETRY_BASE_DELAY_MS},
+};
+
+/// The error of a request to a third-party API.
+#[derive(Debug)]
+pub enum HttpError {
+    /// The request could not be sent or the response could not be received.
+    Request(String),
+    /// The API responded with an unsuccessful status code and, if any, a body.
+    Status(u16, Option<String>),
+    /// The response body is not what was expected.
+    Decode(String),
+}
+
+impl From<HttpError> for ResponseError {
+    fn from(value: HttpError) -> Self {
+        match value {
+            HttpError::Request(e) => Self::ExternalAPIError(0, Some(e)),
+            HttpError::Status(code, _) => Self::ExternalAPIError(
+                code as u32,
+                StatusCode::from_u16(code)
+                    .ok()
+                    .and_then(|x| x.canonical_reason())
+                    .map(|x| x.to_string()),
+            ),
+            HttpError::Decode(e) => Self::ExternalAPIError(200, Some(e)),
+        }
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct RequestOptions {
+    pub basic_auth: Option<(String, Option<String>)>,
+    /// How long the successful response is reused for the same URL.
+    /// The response is not cached if it's not set.
+    pub cache_ttl: Option<Duration>,
+}
+
+struct CachedResponse {
+    expires_at: Instant,
+    body: String,
+}
+
+/// The HTTP client shared by the commands and the emote providers that request third-party APIs.
+/// Failed requests are retried with an exponential backoff,
+/// unless the API has refused the request itself or the request has run out of time.
+pub struct HttpClient {
+    client: Client,
+    host_timeouts: HashMap<String, Duration>,
+    cache: Mutex<HashMap<String, CachedResponse>>,
+}
+
+impl HttpClient {
+    pub fn new(client: Client) -> Self {
+        Self {
+            client,
+            host_timeouts: HashMap::new(),
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Sets the timeout for the requests to the URL's host instead of [HTTP_DEFAULT_TIMEOUT_SEC].
+    /// The timeout covers all the attempts of the request.
+    pub fn with_host_timeout(mut self, url: &str, timeout: Duration) -> Self {
+        if let Some(host) = Url::parse(url)
+            .ok()
+            .and_then(|x| x.host_str().map(String::from))
+        {
+            self.host_timeouts.insert(host, timeout);
+        }
+
+        self
+    }
+
+    /// The underlying client for the requests that must not be retried, e.g. the mutations.
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    pub async fn get_json<T: DeserializeOwned>(
+        &self,
+        url: &str,
+        options: &RequestOptions,
+    ) -> Result<T, HttpError> {
+        let body = self.get_text(url, options).await?;
+        serde_json::from_str::<T>(body.as_str()).map_err(|e| HttpError::Decode(e.to_string()))
+    }
+
+    pub async fn get_text(&self, url: &str, options: &RequestOptions) -> Result<String, HttpError> {
+        let cache_key = match &options.basic_auth {
+            Some((name, _)) => format!("{}@{}", name, url),
+            None => url.to_string(),
+        };
+
+        if options.cache_ttl.is_some() {
+            if let Some(v) = self.cache.lock().await.get(&cache_key) {
+                if v.expires_at > Instant::now() {
+                    return Ok(v.body.clone());
+                }
+            }
+        }
+
+        let url = Url::parse(url).map_err(|e| HttpError::Request(e.to_string()))?;
+
+        let timeout = url
+            .host_str()
+            .and_then(|x| self.host_timeouts.get(x))
+            .cloned()
+            .unwrap_or(Duration::from_secs(HTTP_DEFAULT_TIMEOUT_SEC));
+
+        let deadline = Instant::now() + timeout;
+        let mut attempts: u32 = 0;
+
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let mut request = self.client.get(url.clone()).timeout(remaining);
+
+            if let Some((name, password)) = &options.basic_auth {
+                request = request.basic_auth(name, password.clone());
+            }
+
+            let (error, is_retryable) = match request.send().await {
+                Ok(response) if response.status().is_success() => match response.text().await {
+                    Ok(body) => {
+                        if let Some(ttl) = options.cache_ttl {
+                            let mut cache = self.cache.lock().await;
+                            let now = Instant::now();
+
+                            cache.retain(|_, v| v.expires_at > now);
+                            cache.insert(
+                                cache_key,
+                                CachedResponse {
+                                    expires_at: now + ttl,
+                                    body: body.clone(),
+                                },
+                            );
+                        }
+
+                        return Ok(body);
+                    }
+                    Err(e) => (HttpError::Request(e.to_string()), true),
+                },
+                Ok(response) => {
+                    let status = response.status();
+                    let body = response.text().await.ok().filter(|x| !x.is_empty());
+
+                    (
+                        HttpError::Status(status.as_u16(), body),
+                        status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS,
+                    )
+                }
+                Err(e) => (HttpError::Request(e.to_string()), true),
+            };
+
+            attempts += 1;
+
+            let delay = Duration::from_millis(HTTP_RETRY_BASE_DELAY_MS * 2u64.pow(attempts - 1));
+
+            // the next attempt must have some time left after the delay
+            if !is_retryable
+                || attempts > HTTP_MAX_RETRIES
+                || deadline.saturating_duration_since(Instant::now())
+                    <= delay + Duration::from_millis(HTTP_RETRY_BASE_DELAY_MS)
+            {
+                return Err(error);
+            }
+
+            warn!(
+                "Request to {} failed ({:?}), retrying in {}ms",
+                url.host_str().unwrap_or_default(),
+                error,
+                delay.as_millis()
+            );
+
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
@@ -1,11 +1,24 @@
-use std::{collections::HashSet, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::Arc,
+};
 
 use reqwest::Client;
-use tokio::sync::Mutex;
+use tokio::{sync::Mutex, task::JoinHandle};
 use twitch_api::{twitch_oauth2::UserToken, types::UserId, HelixClient};
 use twitch_irc::{login::StaticLoginCredentials, SecureTCPTransport, TwitchIRCClient};
 
-use crate::{localization::Localizator, seventv::api::SevenTVAPIClient};
+use crate::{
+    chatters::ActiveChatters,
+    emotes::{
+        bttv::BetterTTVAPIClient, details::EmoteDetailsCache, ffz::FrankerFaceZAPIClient,
+        usage::EmoteUsageCounter,
+    },
+    filter::PhraseFilter,
+    http::HttpClient,
+    localization::Localizator,
+    seventv::api::SevenTVAPIClient,
+};
 
 pub struct InstanceBundle {
     pub twitch_irc_client: Arc<TwitchIRCClient<SecureTCPTransport, StaticLoginCredentials>>,
@@ -13,8 +26,24 @@ pub struct InstanceBundle {
     pub twitch_api_token: Arc<UserToken>,
     pub localizator: Arc<Localizator>,
 
+    pub twitch_whisper_queue: Arc<Mutex<VecDeque<(UserId, String)>>>,
+    pub active_chatters: Arc<Mutex<ActiveChatters>>,
+    pub spam_tasks: Arc<Mutex<HashMap<i32, JoinHandle<()>>>>,
+
     pub twitch_livestream_websocket_data: Arc<Mutex<HashSet<UserId>>>,
 
     pub seventv_api_client: Arc<SevenTVAPIClient>,
     pub seventv_eventapi_data: Arc<Mutex<HashSet<UserId>>>,
+    pub seventv_eventapi_removed_data: Arc<Mutex<HashSet<UserId>>>,
+
+    pub bttv_api_client: Arc<BetterTTVAPIClient>,
+    pub bttv_websocket_data: Arc<Mutex<HashSet<UserId>>>,
+    pub ffz_api_client: Arc<FrankerFaceZAPIClient>,
+
+    pub emote_usage_counter: Arc<EmoteUsageCounter>,
+    pub emote_details_cache: Arc<EmoteDetailsCache>,
+
+    pub phrase_filter: Arc<PhraseFilter>,
+
+    pub http_client: Arc<HttpClient>,
 }
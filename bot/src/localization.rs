@@ -1,5 +1,5 @@
 use include_dir::{include_dir, Dir};
-use std::{collections::HashMap, fmt::Display, str::from_utf8};
+use std::{collections::HashMap, env, fmt::Display, fs, str::from_utf8, sync::RwLock};
 
 use crate::commands::request::Request;
 
@@ -16,10 +16,14 @@ pub enum LineId {
     MsgHint,
 
     HintUrlSpam,
+    HintUrlRights,
+    HintUrlFilter,
 
     HintUrlMassping,
     HintUrlHoliday,
     HintUrlJoin,
+    HintUrlPart,
+    HintUrlAdmin,
     HintUrlTimer,
     HintUrlCmd,
     HintUrlEvent,
@@ -28,6 +32,11 @@ pub enum LineId {
     HintUrlEcount,
     HintUrlEtop,
     HintUrlEsim,
+    HintUrlEhistory,
+    HintUrlEwho,
+    HintUrlEdiff,
+    HintUrlEmote,
+    HintUrl7TV,
 
     MsgError,
     ErrorNotEnoughArguments,
@@ -41,13 +50,22 @@ pub enum LineId {
     ErrorSomethingWentWrong,
 
     MiscDescending,
+    MiscAllTime,
+    MiscLastPeriod,
     MiscAscending,
 
     EmotesPushed,
     EmotesUpdated,
     EmotesPulled,
+    EmotesUpdatedAnonymous,
+    EmotesPushedAnonymous,
+    EmotesPulledAnonymous,
+    EmoteSetSwitched,
 
     Provider7TV,
+    ProviderBTTV,
+    ProviderFFZ,
+    ProviderTwitch,
 
     MsgNoMessage,
 
@@ -57,13 +75,34 @@ pub enum LineId {
     CommandSpamNoCount,
     CommandSpamInvalidCount,
     CommandSpamResponse,
+    CommandSpamCancelled,
+    CommandSpamNotRunning,
     CommandHolidayEmpty,
     CommandHolidayResponse,
 
     CommandJoinAlreadyJoined,
+    CommandJoinAlreadyJoinedOther,
     CommandJoinResponse,
+    CommandJoinResponseOther,
     CommandJoinResponseInChat,
+    CommandPartResponse,
+    CommandPartResponsePurge,
+    CommandAdminSuspend,
+    CommandAdminUnsuspend,
+    CommandAdminPart,
+    CommandAdminReload,
+    CommandAdminConfig,
+    CommandRightsSet,
+    CommandRightsReset,
+    CommandRightsInfo,
+    CommandRightsInfoFixed,
+    CommandFilterAdded,
+    CommandFilterRemoved,
+    CommandFilterList,
+    CommandFilterListEmpty,
+    FilterReason,
     JoinOtherchatroom,
+    JoinSessionExpired,
 
     CommandTimerDeleted,
     CommandTimerEnabled,
@@ -88,6 +127,10 @@ pub enum LineId {
     EventOff,
     EventFlagOn,
     EventFlagOff,
+    EventEnabled,
+    EventDisabled,
+    EventScheduled,
+    EventScheduleOff,
 
     NotifySub,
     NotifyAlreadySub,
@@ -97,20 +140,51 @@ pub enum LineId {
     NotifyNoSubs,
     NotifyList,
     NotifyListEmpty,
+    NotifyMode,
+    NotifyTimezone,
+    NotifyQuietHours,
+    NotifyQuietHoursOff,
+    NotifyMuted,
+    NotifyUnmuted,
+    NotifyMasspingOptOut,
+    NotifyMasspingOptIn,
 
     SettingsPrefix,
     SettingsLocale,
     SettingsFeatureOn,
     SettingsFeatureOff,
+    SettingsExcludeOn,
+    SettingsExcludeOff,
 
     EmoteCountUsage,
+    EmoteCountUsageSince,
+    EmoteCountUserUsage,
+    EmoteCountUserUsageSince,
     EmoteCountNotFound,
 
     EmoteTopResponse,
+    EmoteTopUserResponse,
+    EmoteTopUsers,
+    EmoteTopEmoteUsers,
     EmoteTopNoEmotes,
 
     EmoteSimilaritySetSimilar,
     EmoteSimilaritySetNotSimilar,
+    EmoteSimilarityShared,
+    EmoteSimilarityMissing,
+    EmoteSimilarityNothingMissing,
+    EmoteHistoryResponse,
+    EmoteHistoryNoChanges,
+    EmoteWhoResponse,
+    EmoteWhoAnonymous,
+    EmoteWhoUnknown,
+    EmoteDiffResponse,
+    EmoteDiffSame,
+    EmoteInfoResponse,
+    EmoteInfoAuthor,
+    EmoteInfoAdded,
+    EmoteInfoAddedBy,
+    EmoteInfoUsage,
     UserIdFound,
     UserIdChatban,
 
@@ -134,9 +208,13 @@ impl LineId {
             "argument.amount" => Some(Self::ArgumentAmount),
             "msg.hint" => Some(Self::MsgHint),
             "hint.url.spam" => Some(Self::HintUrlSpam),
+            "hint.url.rights" => Some(Self::HintUrlRights),
+            "hint.url.filter" => Some(Self::HintUrlFilter),
             "hint.url.massping" => Some(Self::HintUrlMassping),
             "hint.url.holiday" => Some(Self::HintUrlHoliday),
             "hint.url.join" => Some(Self::HintUrlJoin),
+            "hint.url.part" => Some(Self::HintUrlPart),
+            "hint.url.admin" => Some(Self::HintUrlAdmin),
             "hint.url.timer" => Some(Self::HintUrlTimer),
             "hint.url.cmd" => Some(Self::HintUrlCmd),
             "hint.url.event" => Some(Self::HintUrlEvent),
@@ -145,6 +223,11 @@ impl LineId {
             "hint.url.ecount" => Some(Self::HintUrlEcount),
             "hint.url.etop" => Some(Self::HintUrlEtop),
             "hint.url.esim" => Some(Self::HintUrlEsim),
+            "hint.url.ehistory" => Some(Self::HintUrlEhistory),
+            "hint.url.ewho" => Some(Self::HintUrlEwho),
+            "hint.url.ediff" => Some(Self::HintUrlEdiff),
+            "hint.url.emote" => Some(Self::HintUrlEmote),
+            "hint.url.7tv" => Some(Self::HintUrl7TV),
             "msg.error" => Some(Self::MsgError),
             "error.not_enough_arguments" => Some(Self::ErrorNotEnoughArguments),
             "error.wrong_argument_type" => Some(Self::ErrorWrongArgumentType),
@@ -156,10 +239,19 @@ impl LineId {
             "error.insufficient_rights" => Some(Self::ErrorInsufficientRights),
             "error.something_went_wrong" => Some(Self::ErrorSomethingWentWrong),
             "misc.descending" => Some(Self::MiscDescending),
+            "misc.all_time" => Some(Self::MiscAllTime),
+            "misc.last_period" => Some(Self::MiscLastPeriod),
             "misc.ascending" => Some(Self::MiscAscending),
             "provider.7tv" => Some(Self::Provider7TV),
+            "provider.bttv" => Some(Self::ProviderBTTV),
+            "provider.ffz" => Some(Self::ProviderFFZ),
+            "provider.twitch" => Some(Self::ProviderTwitch),
             "emotes.update" => Some(Self::EmotesUpdated),
             "emotes.pull" => Some(Self::EmotesPulled),
+            "emotes.update.anonymous" => Some(Self::EmotesUpdatedAnonymous),
+            "emotes.push.anonymous" => Some(Self::EmotesPushedAnonymous),
+            "emotes.pull.anonymous" => Some(Self::EmotesPulledAnonymous),
+            "emotes.set.switch" => Some(Self::EmoteSetSwitched),
             "emotes.push" => Some(Self::EmotesPushed),
             "msg.no_message" => Some(Self::MsgNoMessage),
             "cmd.ping.response" => Some(Self::CommandPingResponse),
@@ -167,12 +259,33 @@ impl LineId {
             "cmd.spam.no_count" => Some(Self::CommandSpamNoCount),
             "cmd.spam.invalid_count" => Some(Self::CommandSpamInvalidCount),
             "cmd.spam.response" => Some(Self::CommandSpamResponse),
+            "cmd.spam.cancelled" => Some(Self::CommandSpamCancelled),
+            "cmd.spam.not_running" => Some(Self::CommandSpamNotRunning),
             "cmd.holiday.empty" => Some(Self::CommandHolidayEmpty),
             "cmd.holiday.response" => Some(Self::CommandHolidayResponse),
             "cmd.join.already_joined" => Some(Self::CommandJoinAlreadyJoined),
+            "cmd.join.already_joined.other" => Some(Self::CommandJoinAlreadyJoinedOther),
             "cmd.join.response" => Some(Self::CommandJoinResponse),
+            "cmd.join.response.other" => Some(Self::CommandJoinResponseOther),
             "cmd.join.response_in_chat" => Some(Self::CommandJoinResponseInChat),
+            "cmd.part.response" => Some(Self::CommandPartResponse),
+            "cmd.part.response.purge" => Some(Self::CommandPartResponsePurge),
+            "cmd.admin.suspend" => Some(Self::CommandAdminSuspend),
+            "cmd.admin.unsuspend" => Some(Self::CommandAdminUnsuspend),
+            "cmd.admin.part" => Some(Self::CommandAdminPart),
+            "cmd.admin.reload" => Some(Self::CommandAdminReload),
+            "cmd.admin.config" => Some(Self::CommandAdminConfig),
+            "cmd.rights.set" => Some(Self::CommandRightsSet),
+            "cmd.rights.reset" => Some(Self::CommandRightsReset),
+            "cmd.rights.info" => Some(Self::CommandRightsInfo),
+            "cmd.rights.info.fixed" => Some(Self::CommandRightsInfoFixed),
+            "cmd.filter.added" => Some(Self::CommandFilterAdded),
+            "cmd.filter.removed" => Some(Self::CommandFilterRemoved),
+            "cmd.filter.list" => Some(Self::CommandFilterList),
+            "cmd.filter.list.empty" => Some(Self::CommandFilterListEmpty),
+            "filter.reason" => Some(Self::FilterReason),
             "join.other_chat_room" => Some(Self::JoinOtherchatroom),
+            "join.session_expired" => Some(Self::JoinSessionExpired),
             "cmd.timer.deleted" => Some(Self::CommandTimerDeleted),
             "cmd.timer.enabled" => Some(Self::CommandTimerEnabled),
             "cmd.timer.disabled" => Some(Self::CommandTimerDisabled),
@@ -195,6 +308,10 @@ impl LineId {
             "event.off" => Some(Self::EventOff),
             "event.flag.on" => Some(Self::EventFlagOn),
             "event.flag.off" => Some(Self::EventFlagOff),
+            "event.enabled" => Some(Self::EventEnabled),
+            "event.disabled" => Some(Self::EventDisabled),
+            "event.scheduled" => Some(Self::EventScheduled),
+            "event.schedule.off" => Some(Self::EventScheduleOff),
             "event.already_exists" => Some(Self::EventAlreadyExistsError),
             "notify.sub" => Some(Self::NotifySub),
             "notify.already_sub" => Some(Self::NotifyAlreadySub),
@@ -204,16 +321,47 @@ impl LineId {
             "notify.no_subs" => Some(Self::NotifyNoSubs),
             "notify.list" => Some(Self::NotifyList),
             "notify.list.empty" => Some(Self::NotifyListEmpty),
+            "notify.mode" => Some(Self::NotifyMode),
+            "notify.timezone" => Some(Self::NotifyTimezone),
+            "notify.quiet_hours" => Some(Self::NotifyQuietHours),
+            "notify.quiet_hours.off" => Some(Self::NotifyQuietHoursOff),
+            "notify.muted" => Some(Self::NotifyMuted),
+            "notify.unmuted" => Some(Self::NotifyUnmuted),
+            "notify.massping.off" => Some(Self::NotifyMasspingOptOut),
+            "notify.massping.on" => Some(Self::NotifyMasspingOptIn),
             "settings.prefix" => Some(Self::SettingsPrefix),
             "settings.locale" => Some(Self::SettingsLocale),
             "settings.feature.on" => Some(Self::SettingsFeatureOn),
             "settings.feature.off" => Some(Self::SettingsFeatureOff),
+            "settings.exclude.on" => Some(Self::SettingsExcludeOn),
+            "settings.exclude.off" => Some(Self::SettingsExcludeOff),
             "emote_count.usage" => Some(Self::EmoteCountUsage),
+            "emote_count.usage.since" => Some(Self::EmoteCountUsageSince),
+            "emote_count.user_usage" => Some(Self::EmoteCountUserUsage),
+            "emote_count.user_usage.since" => Some(Self::EmoteCountUserUsageSince),
             "emote_count.not_found" => Some(Self::EmoteCountNotFound),
             "emote_top.response" => Some(Self::EmoteTopResponse),
+            "emote_top.user_response" => Some(Self::EmoteTopUserResponse),
+            "emote_top.users" => Some(Self::EmoteTopUsers),
+            "emote_top.emote_users" => Some(Self::EmoteTopEmoteUsers),
             "emote_top.no_emotes" => Some(Self::EmoteTopNoEmotes),
             "emote_similarity.set_similar" => Some(Self::EmoteSimilaritySetSimilar),
             "emote_similarity.set_not_similar" => Some(Self::EmoteSimilaritySetNotSimilar),
+            "emote_similarity.shared" => Some(Self::EmoteSimilarityShared),
+            "emote_similarity.missing" => Some(Self::EmoteSimilarityMissing),
+            "emote_similarity.nothing_missing" => Some(Self::EmoteSimilarityNothingMissing),
+            "emote_history.response" => Some(Self::EmoteHistoryResponse),
+            "emote_history.no_changes" => Some(Self::EmoteHistoryNoChanges),
+            "emote_who.response" => Some(Self::EmoteWhoResponse),
+            "emote_who.anonymous" => Some(Self::EmoteWhoAnonymous),
+            "emote_who.unknown" => Some(Self::EmoteWhoUnknown),
+            "emote_diff.response" => Some(Self::EmoteDiffResponse),
+            "emote_diff.same" => Some(Self::EmoteDiffSame),
+            "emote_info.response" => Some(Self::EmoteInfoResponse),
+            "emote_info.author" => Some(Self::EmoteInfoAuthor),
+            "emote_info.added" => Some(Self::EmoteInfoAdded),
+            "emote_info.added_by" => Some(Self::EmoteInfoAddedBy),
+            "emote_info.usage" => Some(Self::EmoteInfoUsage),
             "userid.found" => Some(Self::UserIdFound),
             "userid.chat_ban" => Some(Self::UserIdChatban),
             "help.general" => Some(Self::HelpGeneral),
@@ -223,15 +371,17 @@ impl LineId {
     }
 }
 
+type Localizations = HashMap<String, HashMap<LineId, String>>;
+
 pub struct Localizator {
-    localizations: HashMap<String, HashMap<LineId, String>>,
+    localizations: RwLock<Localizations>,
 }
 
 const LOCALIZATION_DIR: Dir = include_dir!("$CARGO_MANIFEST_DIR/localizations");
 
 impl Localizator {
     pub fn new() -> Self {
-        let mut localizations: HashMap<String, HashMap<LineId, String>> = HashMap::new();
+        let mut localizations: Localizations = HashMap::new();
 
         for file in LOCALIZATION_DIR.files() {
             let file_name = file.path();
@@ -240,20 +390,67 @@ impl Localizator {
             let contents = from_utf8(file.contents()).expect("Failed to read file");
             let data: HashMap<String, String> = serde_json::from_str(contents).unwrap();
 
-            let map = localizations.entry(language.to_string()).or_default();
+            Self::insert_lines(&mut localizations, language, data);
+        }
 
-            for (line_id, line) in data {
-                if let Some(line_id) = LineId::from_string(line_id) {
-                    map.insert(line_id, line);
-                }
+        Self {
+            localizations: RwLock::new(localizations),
+        }
+    }
+
+    /// Reloads the localizations from the `BOT_LOCALIZATION_DIR` directory
+    /// without restarting the bot. Returns the number of loaded languages.
+    pub fn reload(&self) -> Result<usize, String> {
+        let path = env::var("BOT_LOCALIZATION_DIR")
+            .map_err(|_| "BOT_LOCALIZATION_DIR is not set".to_string())?;
+
+        let mut localizations: Localizations = HashMap::new();
+
+        for entry in fs::read_dir(&path).map_err(|e| e.to_string())? {
+            let file_name = entry.map_err(|e| e.to_string())?.path();
+
+            if file_name.extension().and_then(|s| s.to_str()) != Some("json") {
+                continue;
             }
+
+            let language = match file_name.file_stem().and_then(|s| s.to_str()) {
+                Some(v) => v.to_string(),
+                None => continue,
+            };
+
+            let contents = fs::read_to_string(&file_name).map_err(|e| e.to_string())?;
+            let data: HashMap<String, String> =
+                serde_json::from_str(&contents).map_err(|e| format!("{}: {}", language, e))?;
+
+            Self::insert_lines(&mut localizations, &language, data);
         }
 
-        Self { localizations }
+        if localizations.is_empty() {
+            return Err(format!("No localizations found in {}", path));
+        }
+
+        let count = localizations.len();
+        *self.localizations.write().unwrap() = localizations;
+
+        Ok(count)
+    }
+
+    fn insert_lines(
+        localizations: &mut Localizations,
+        language: &str,
+        data: HashMap<String, String>,
+    ) {
+        let map = localizations.entry(language.to_string()).or_default();
+
+        for (line_id, line) in data {
+            if let Some(line_id) = LineId::from_string(line_id) {
+                map.insert(line_id, line);
+            }
+        }
     }
 
     pub fn get_literal_text(&self, locale_id: &str, line_id: LineId) -> Option<String> {
-        if let Some(locale) = self.localizations.get(locale_id) {
+        if let Some(locale) = self.localizations.read().unwrap().get(locale_id) {
             if let Some(line) = locale.get(&line_id) {
                 return Some(line.clone());
             }
@@ -392,8 +589,13 @@ impl Localizator {
         line
     }
 
-    pub fn localization_names(&self) -> Vec<&String> {
-        self.localizations.keys().collect::<Vec<&String>>()
+    pub fn localization_names(&self) -> Vec<String> {
+        self.localizations
+            .read()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect::<Vec<String>>()
     }
 }
 
@@ -1,12 +1,35 @@
-use std::{collections::HashSet, env, process::exit, sync::Arc, time::Duration};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    env,
+    process::exit,
+    sync::Arc,
+    time::Duration,
+};
 
 use crate::{
     commands::CommandLoader,
-    handlers::{handle_chat_message, handle_timers},
+    emotes::{
+        bttv::{BetterTTVAPIClient, BetterTTVWebsocketClient},
+        details::EmoteDetailsCache,
+        ffz::{FrankerFaceZAPIClient, FrankerFaceZUpdatesHelper},
+        usage::EmoteUsageCounter,
+    },
+    filter::PhraseFilter,
+    handlers::{
+        handle_channel_purges, handle_channel_sync, handle_channel_updates, handle_chat_message,
+        handle_event_triggers, handle_notification_digests, handle_scheduled_events,
+        handle_timers,
+    },
+    http::HttpClient,
     instance_bundle::InstanceBundle,
     localization::Localizator,
     seventv::{api::SevenTVAPIClient, SevenTVWebsocketClient},
-    shared_variables::{START_TIME, TIMER_CHECK_DELAY},
+    shared_variables::{
+        CHANNEL_SYNC_DELAY, CHANNEL_UPDATE_CHECK_DELAY, DIGEST_CHECK_DELAY,
+        EMOTE_CACHE_REFRESH_DELAY, EMOTE_USAGE_FLUSH_DELAY, EVENT_TRIGGER_CHECK_DELAY,
+        MCSRV_API_URL, MCSRV_TIMEOUT_SEC, START_TIME, TIMER_CHECK_DELAY,
+    },
+    whispers::TwitchWhisperHelper,
 };
 
 use common::{
@@ -33,8 +56,12 @@ use twitch_irc::{
     TwitchIRCClient,
 };
 
+mod chatters;
 mod commands;
+mod emotes;
+mod filter;
 mod handlers;
+mod http;
 mod instance_bundle;
 mod livestream;
 mod localization;
@@ -43,6 +70,7 @@ mod modules;
 mod seventv;
 mod shared_variables;
 mod utils;
+mod whispers;
 
 #[tokio::main]
 async fn main() {
@@ -117,7 +145,7 @@ async fn main() {
         },
     );
 
-    let helix_client = Arc::new(HelixClient::with_client(reqwest_client));
+    let helix_client = Arc::new(HelixClient::with_client(reqwest_client.clone()));
 
     let conn = &mut establish_connection();
 
@@ -196,16 +224,32 @@ async fn main() {
             .collect::<HashSet<UserId>>()
     }));
 
-    let seventv_api = Arc::new(SevenTVAPIClient::new(Client::new()));
+    let http_client = Arc::new(
+        HttpClient::new(reqwest_client)
+            .with_host_timeout(MCSRV_API_URL, Duration::from_secs(MCSRV_TIMEOUT_SEC)),
+    );
+
+    let seventv_api = Arc::new(SevenTVAPIClient::new(http_client.clone()));
 
     let instances = Arc::new(InstanceBundle {
         twitch_irc_client: irc_client.clone(),
         twitch_api_token: helix_token.clone(),
         twitch_api_client: helix_client.clone(),
         localizator: localizator.clone(),
+        twitch_whisper_queue: Arc::new(Mutex::new(VecDeque::new())),
+        active_chatters: Arc::new(Mutex::new(HashMap::new())),
+        spam_tasks: Arc::new(Mutex::new(HashMap::new())),
         twitch_livestream_websocket_data: livestream_data.clone(),
         seventv_api_client: seventv_api.clone(),
         seventv_eventapi_data: seventv_data.clone(),
+        seventv_eventapi_removed_data: Arc::new(Mutex::new(HashSet::new())),
+        bttv_api_client: Arc::new(BetterTTVAPIClient::new(http_client.clone())),
+        bttv_websocket_data: Arc::new(Mutex::new(seventv_data.lock().await.clone())),
+        ffz_api_client: Arc::new(FrankerFaceZAPIClient::new(http_client.clone())),
+        emote_usage_counter: Arc::new(EmoteUsageCounter::new()),
+        emote_details_cache: Arc::new(EmoteDetailsCache::new()),
+        phrase_filter: Arc::new(PhraseFilter::new()),
+        http_client: http_client.clone(),
     });
 
     let timer_thread = tokio::spawn({
@@ -213,23 +257,107 @@ async fn main() {
         async move {
             loop {
                 handle_timers(&instances).await;
+                handle_scheduled_events(&instances).await;
                 tokio::time::sleep(Duration::from_secs(TIMER_CHECK_DELAY)).await;
             }
         }
     });
 
+    let digest_thread = tokio::spawn({
+        let instances = instances.clone();
+        async move {
+            loop {
+                handle_notification_digests(&instances).await;
+                tokio::time::sleep(Duration::from_secs(DIGEST_CHECK_DELAY)).await;
+            }
+        }
+    });
+
+    let emote_usage_thread = tokio::spawn({
+        let instances = instances.clone();
+        async move {
+            loop {
+                tokio::time::sleep(Duration::from_secs(EMOTE_USAGE_FLUSH_DELAY)).await;
+                instances.emote_usage_counter.flush().await;
+            }
+        }
+    });
+
+    let emote_cache_thread = tokio::spawn({
+        let instances = instances.clone();
+        async move {
+            loop {
+                if EmoteUsageCounter::is_enabled() {
+                    instances
+                        .emote_usage_counter
+                        .refresh_channel_emotes(&instances)
+                        .await;
+                }
+
+                tokio::time::sleep(Duration::from_secs(EMOTE_CACHE_REFRESH_DELAY)).await;
+            }
+        }
+    });
+
+    let trigger_thread = tokio::spawn({
+        let instances = instances.clone();
+        async move {
+            loop {
+                handle_event_triggers(&instances).await;
+                tokio::time::sleep(Duration::from_secs(EVENT_TRIGGER_CHECK_DELAY)).await;
+            }
+        }
+    });
+
+    let channel_update_thread = tokio::spawn({
+        let instances = instances.clone();
+        async move {
+            loop {
+                handle_channel_updates(&instances).await;
+                tokio::time::sleep(Duration::from_secs(CHANNEL_UPDATE_CHECK_DELAY)).await;
+            }
+        }
+    });
+
+    let channel_thread = tokio::spawn({
+        let instances = instances.clone();
+        async move {
+            loop {
+                handle_channel_sync(&instances).await;
+                handle_channel_purges();
+                tokio::time::sleep(Duration::from_secs(CHANNEL_SYNC_DELAY)).await;
+            }
+        }
+    });
+
+    let mut whisper_helper = TwitchWhisperHelper::new(instances.clone());
+
+    let whisper_thread = tokio::spawn(async move {
+        whisper_helper.run().await;
+    });
+
     let mut livestream_helper = TwitchLivestreamHelper::new(instances.clone());
 
     let livestream_thread = tokio::spawn(async move {
         livestream_helper.run().await;
     });
 
-    let mut seventv_client = SevenTVWebsocketClient::new(instances.clone())
-        .await
-        .unwrap();
+    let mut seventv_client = SevenTVWebsocketClient::new(instances.clone());
 
     let seventv_thread = tokio::spawn(async move {
-        seventv_client.run().await.unwrap();
+        seventv_client.run().await;
+    });
+
+    let mut bttv_client = BetterTTVWebsocketClient::new(instances.clone());
+
+    let bttv_thread = tokio::spawn(async move {
+        bttv_client.run().await;
+    });
+
+    let mut ffz_helper = FrankerFaceZUpdatesHelper::new(instances.clone());
+
+    let ffz_thread = tokio::spawn(async move {
+        ffz_helper.run().await;
     });
 
     let irc_thread = tokio::spawn(async move {
@@ -248,5 +376,19 @@ async fn main() {
         }
     });
 
-    let _ = tokio::join!(irc_thread, timer_thread, livestream_thread, seventv_thread);
+    let _ = tokio::join!(
+        irc_thread,
+        timer_thread,
+        digest_thread,
+        emote_usage_thread,
+        emote_cache_thread,
+        trigger_thread,
+        channel_update_thread,
+        channel_thread,
+        whisper_thread,
+        livestream_thread,
+        seventv_thread,
+        bttv_thread,
+        ffz_thread
+    );
 }
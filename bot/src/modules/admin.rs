@@ -0,0 +1,205 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use diesel::{update, ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl};
+use log::error;
+
+use crate::{
+    commands::{
+        request::Request,
+        response::{Response, ResponseError},
+        Command, CommandArgument,
+    },
+    handlers::part_channel,
+    instance_bundle::InstanceBundle,
+    localization::LineId,
+};
+
+use common::{
+    establish_connection,
+    models::{Channel, ChannelPreference, LevelOfRights, User},
+    schema::{channel_preferences::dsl as chp, channels::dsl as ch, users::dsl as us},
+};
+
+pub struct AdminCommand;
+
+#[async_trait]
+impl Command for AdminCommand {
+    fn get_name(&self) -> String {
+        "admin".to_string()
+    }
+
+    fn get_delay_sec(&self) -> i32 {
+        0
+    }
+
+    fn required_rights(&self) -> LevelOfRights {
+        LevelOfRights::Admin
+    }
+
+    fn get_subcommands(&self) -> Vec<String> {
+        vec![
+            "suspend".to_string(),
+            "unsuspend".to_string(),
+            "part".to_string(),
+            "reload".to_string(),
+            "config".to_string(),
+        ]
+    }
+
+    async fn execute(
+        &self,
+        instance_bundle: &InstanceBundle,
+        request: Request,
+    ) -> Result<Response, ResponseError> {
+        let subcommand_id = match request.subcommand_id.clone() {
+            Some(v) => v,
+            None => {
+                return Err(ResponseError::NotEnoughArguments(
+                    CommandArgument::Subcommand,
+                ))
+            }
+        };
+
+        if subcommand_id.eq("reload") {
+            return match instance_bundle.localizator.reload() {
+                Ok(count) => Ok(Response::Single(
+                    instance_bundle.localizator.formatted_text_by_request(
+                        &request,
+                        LineId::CommandAdminReload,
+                        vec![count],
+                    ),
+                )),
+                Err(e) => {
+                    error!("Failed to reload localizations: {}", e);
+                    Err(ResponseError::SomethingWentWrong)
+                }
+            };
+        }
+
+        let name = match request.message.clone() {
+            Some(v) => v
+                .split(' ')
+                .next()
+                .unwrap_or_default()
+                .trim_start_matches('@')
+                .to_lowercase(),
+            None => return Err(ResponseError::NotEnoughArguments(CommandArgument::Target)),
+        };
+
+        let conn = &mut establish_connection();
+
+        let response = match subcommand_id.as_str() {
+            "suspend" | "unsuspend" => {
+                let is_suspended = subcommand_id.eq("suspend");
+
+                let user = us::users
+                    .filter(us::alias_name.eq(&name))
+                    .first::<User>(conn)
+                    .optional()
+                    .expect("Failed to get the user");
+
+                let user = match user {
+                    Some(v) if !v.is_admin => v,
+                    Some(_) => return Err(ResponseError::IncorrectArgument(name)),
+                    None => return Err(ResponseError::NotFound(name)),
+                };
+
+                update(us::users.find(&user.id))
+                    .set(us::is_suspended.eq(is_suspended))
+                    .execute(conn)
+                    .expect("Failed to update the user");
+
+                instance_bundle.localizator.formatted_text_by_request(
+                    &request,
+                    if is_suspended {
+                        LineId::CommandAdminSuspend
+                    } else {
+                        LineId::CommandAdminUnsuspend
+                    },
+                    vec![user.alias_name],
+                )
+            }
+            "part" => {
+                let channel = ch::channels
+                    .filter(ch::alias_name.eq(&name))
+                    .filter(ch::opt_outed_at.is_null())
+                    .first::<Channel>(conn)
+                    .optional()
+                    .expect("Failed to get the channel");
+
+                let channel = match channel {
+                    Some(v) => v,
+                    None => return Err(ResponseError::NotFound(name)),
+                };
+
+                update(ch::channels.find(&channel.id))
+                    .set(ch::opt_outed_at.eq(Some(Utc::now().naive_utc())))
+                    .execute(conn)
+                    .expect("Failed to opt out the channel");
+
+                part_channel(conn, instance_bundle, &channel).await;
+
+                instance_bundle.localizator.formatted_text_by_request(
+                    &request,
+                    LineId::CommandAdminPart,
+                    vec![channel.alias_name],
+                )
+            }
+            "config" => {
+                let channel = ch::channels
+                    .filter(ch::alias_name.eq(&name))
+                    .first::<Channel>(conn)
+                    .optional()
+                    .expect("Failed to get the channel");
+
+                let channel = match channel {
+                    Some(v) => v,
+                    None => return Err(ResponseError::NotFound(name)),
+                };
+
+                let preference = chp::channel_preferences
+                    .filter(chp::channel_id.eq(&channel.id))
+                    .first::<ChannelPreference>(conn)
+                    .optional()
+                    .expect("Failed to get the channel preference");
+
+                let preference = match preference {
+                    Some(v) => v,
+                    None => return Err(ResponseError::NotFound(name)),
+                };
+
+                let features = preference
+                    .features
+                    .iter()
+                    .flatten()
+                    .cloned()
+                    .collect::<Vec<String>>();
+
+                let join_list = |x: Vec<String>| {
+                    if x.is_empty() {
+                        "-".to_string()
+                    } else {
+                        x.join(", ")
+                    }
+                };
+
+                instance_bundle.localizator.formatted_text_by_request(
+                    &request,
+                    LineId::CommandAdminConfig,
+                    vec![
+                        channel.alias_name,
+                        channel.alias_id.to_string(),
+                        preference.prefix,
+                        preference.language,
+                        join_list(features),
+                        join_list(preference.massping_exclusions),
+                        channel.joined_at.format("%Y-%m-%d").to_string(),
+                    ],
+                )
+            }
+            _ => return Err(ResponseError::SomethingWentWrong),
+        };
+
+        Ok(Response::Single(response))
+    }
+}
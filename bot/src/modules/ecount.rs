@@ -1,8 +1,6 @@
-use std::env;
-
 use async_trait::async_trait;
+use common::establish_connection;
 use eyre::Result;
-use log::error;
 
 use crate::{
     commands::{
@@ -10,9 +8,15 @@ use crate::{
         response::{Response, ResponseError},
         Command, CommandArgument,
     },
+    emotes::{
+        stats,
+        usage::{
+            get_emote_counts, get_emote_usages, get_since_date, get_user_id, EmoteUsageCounter,
+        },
+        EmoteProvider,
+    },
     instance_bundle::InstanceBundle,
     localization::LineId,
-    models::stats::{ChannelEmote, ChannelEmoteUsage, Response as StatsResponse},
 };
 
 pub struct EmoteCountCommand;
@@ -23,174 +27,164 @@ impl Command for EmoteCountCommand {
         "ecount".to_string()
     }
 
+    fn get_options(&self) -> Vec<String> {
+        vec!["provider".to_string(), "since".to_string()]
+    }
+
     async fn execute(
         &self,
         instance_bundle: &InstanceBundle,
         request: Request,
     ) -> Result<Response, ResponseError> {
-        if env::var("STATS_API_HOSTNAME").is_err() {
-            error!("Tried to run the !ecount command, but STATS_API_HOSTNAME is not set in the .env file");
-            return Err(ResponseError::SomethingWentWrong);
-        }
-
-        if let Some(message) = request.message.clone() {
-            let channel_id = request.channel.alias_id.to_string();
-            if let Some(response) = self.fetch_channel_emotes(channel_id.clone()).await {
-                if response.status_code != 200 {
-                    return Err(ResponseError::ExternalAPIError(
-                        response.status_code,
-                        response.message,
-                    ));
-                }
+        let provider = EmoteProvider::from_request(&request)?;
+        let since = get_since_date(&request)?;
+
+        let message = request.message.clone().unwrap_or_default();
+        let mut message_split = message.split_ascii_whitespace();
+
+        let (user_name, message) = match message_split.next() {
+            Some(v) if v.starts_with('@') => (
+                Some(v.trim_start_matches('@').to_lowercase()),
+                message_split.next(),
+            ),
+            v => (None, v),
+        };
+
+        let message = match message {
+            Some(v) => v.to_string(),
+            None => return Err(ResponseError::NotEnoughArguments(CommandArgument::Target)),
+        };
+
+        let channel_id = request.channel.alias_id.to_string();
+
+        let usage = if EmoteUsageCounter::is_enabled() {
+            let conn = &mut establish_connection();
+
+            let usages: Vec<(String, i32)> = if user_name.is_none() && since.is_none() {
+                get_emote_usages(conn, request.channel.id, &provider)
+                    .into_iter()
+                    .map(|x| (x.emote_name, x.usage_count))
+                    .collect()
+            } else {
+                let user_id = match &user_name {
+                    Some(v) => Some(get_user_id(conn, v)?),
+                    None => None,
+                };
+
+                get_emote_counts(
+                    conn,
+                    request.channel.id,
+                    &provider,
+                    user_id,
+                    since.as_ref().map(|x| x.1),
+                )
+            };
+
+            match usages.into_iter().find(|x| x.0.eq(&message)) {
+                Some(v) => Some(v),
+                // the emote may have not been used yet
+                None => provider
+                    .get_channel_emotes(instance_bundle, channel_id)
+                    .await
+                    .unwrap_or_default()
+                    .into_iter()
+                    .find(|x| x.name.eq(&message))
+                    .map(|x| (x.name, 0)),
+            }
+        } else {
+            // the external stats service doesn't keep the per-user and daily counts
+            if let Some(v) = &user_name {
+                return Err(ResponseError::IncorrectArgument(format!("@{}", v)));
+            }
 
-                let emotes = response.data.unwrap_or_default();
-
-                if let Some(response) = self.fetch_channel_emote_usages(channel_id).await {
-                    if response.status_code != 200 {
-                        return Err(ResponseError::ExternalAPIError(
-                            response.status_code,
-                            response.message,
-                        ));
-                    }
-
-                    let emote_usages = response.data.unwrap_or_default();
-
-                    if let Some(emote) = emotes.iter().find(|x| x.name.eq(&message)) {
-                        let usage =
-                            match emote_usages.iter().find(|x| x.emote_id.eq(&emote.emote_id)) {
-                                Some(usage) => usage.usage_count,
-                                None => 0,
-                            };
-
-                        return Ok(Response::Single(
-                            instance_bundle.localizator.formatted_text_by_request(
-                                &request,
-                                LineId::EmoteCountUsage,
-                                vec![
-                                    instance_bundle
-                                        .localizator
-                                        .get_literal_text(
-                                            request.channel_preference.language.as_str(),
-                                            LineId::Provider7TV,
-                                        )
-                                        .unwrap(),
-                                    emote.name.clone(),
-                                    usage.to_string(),
-                                ],
-                            ),
-                        ));
-                    }
-
-                    return Ok(Response::Single(
-                        instance_bundle.localizator.formatted_text_by_request(
-                            &request,
-                            LineId::EmoteCountNotFound,
-                            vec![
-                                instance_bundle
-                                    .localizator
-                                    .get_literal_text(
-                                        request.channel_preference.language.as_str(),
-                                        LineId::Provider7TV,
-                                    )
-                                    .unwrap(),
-                                message,
-                            ],
-                        ),
-                    ));
-                }
+            if let Some((v, _)) = &since {
+                return Err(ResponseError::IncorrectArgument(format!("--since={}", v)));
             }
-        }
 
-        Err(ResponseError::NotEnoughArguments(CommandArgument::Target))
+            self.get_stats_usage(instance_bundle, &request, &provider, channel_id, &message)
+                .await?
+        };
+
+        let provider_name = instance_bundle
+            .localizator
+            .get_literal_text(
+                request.channel_preference.language.as_str(),
+                provider.line_id(),
+            )
+            .unwrap();
+
+        Ok(Response::Single(match usage {
+            Some((name, usage)) => match (user_name, since) {
+                (Some(user_name), Some((since, _))) => {
+                    instance_bundle.localizator.formatted_text_by_request(
+                        &request,
+                        LineId::EmoteCountUserUsageSince,
+                        vec![provider_name, user_name, name, usage.to_string(), since],
+                    )
+                }
+                (Some(user_name), None) => instance_bundle.localizator.formatted_text_by_request(
+                    &request,
+                    LineId::EmoteCountUserUsage,
+                    vec![provider_name, user_name, name, usage.to_string()],
+                ),
+                (None, Some((since, _))) => instance_bundle.localizator.formatted_text_by_request(
+                    &request,
+                    LineId::EmoteCountUsageSince,
+                    vec![provider_name, name, usage.to_string(), since],
+                ),
+                (None, None) => instance_bundle.localizator.formatted_text_by_request(
+                    &request,
+                    LineId::EmoteCountUsage,
+                    vec![provider_name, name, usage.to_string()],
+                ),
+            },
+            None => instance_bundle.localizator.formatted_text_by_request(
+                &request,
+                LineId::EmoteCountNotFound,
+                vec![provider_name, message],
+            ),
+        }))
     }
 }
 
 impl EmoteCountCommand {
-    async fn fetch_channel_emotes(
+    /// Gets the name and the usage count of the emote from the external stats service.
+    async fn get_stats_usage(
         &self,
+        instance_bundle: &InstanceBundle,
+        request: &Request,
+        provider: &EmoteProvider,
         channel_id: String,
-    ) -> Option<StatsResponse<Vec<ChannelEmote>>> {
-        let url = format!(
-            "{}/api/v1/channel/twitch/{}/emotes",
-            env::var("STATS_API_HOSTNAME")
-                .expect("STATS_API_HOSTNAME must be set for !ecount command"),
-            channel_id
-        );
-
-        let client = reqwest::Client::default();
-        let mut request = client.get(url);
-
-        if let Ok(credentials) = env::var("STATS_API_PASSWORD") {
-            let mut split = credentials.split(':').collect::<Vec<&str>>();
-
-            if !split.is_empty() {
-                let name = split[0];
-                split.remove(0);
-
-                let password = split.join(":");
-
-                request = request.basic_auth(
-                    name,
-                    if password.is_empty() {
-                        None
-                    } else {
-                        Some(password)
-                    },
-                );
-            }
-        }
+        name: &str,
+    ) -> Result<Option<(String, i32)>, ResponseError> {
+        let http_client = &instance_bundle.http_client;
 
-        if let Ok(response) = request.send().await {
-            if let Ok(data) = response.json::<StatsResponse<Vec<ChannelEmote>>>().await {
-                return Some(data);
-            }
-        }
+        let mut emotes = stats::get_channel_emotes(http_client, channel_id.as_str()).await?;
 
-        None
-    }
-    async fn fetch_channel_emote_usages(
-        &self,
-        channel_id: String,
-    ) -> Option<StatsResponse<Vec<ChannelEmoteUsage>>> {
-        let url = format!(
-            "{}/api/v1/channel/twitch/{}/emotes/usage",
-            env::var("STATS_API_HOSTNAME")
-                .expect("STATS_API_HOSTNAME must be set for !ecount command"),
-            channel_id
-        );
-
-        let client = reqwest::Client::default();
-        let mut request = client.get(url);
-
-        if let Ok(credentials) = env::var("STATS_API_PASSWORD") {
-            let mut split = credentials.split(':').collect::<Vec<&str>>();
-
-            if !split.is_empty() {
-                let name = split[0];
-                split.remove(0);
-
-                let password = split.join(":");
-
-                request = request.basic_auth(
-                    name,
-                    if password.is_empty() {
-                        None
-                    } else {
-                        Some(password)
-                    },
-                );
-            }
-        }
-
-        if let Ok(response) = request.send().await {
-            if let Ok(data) = response
-                .json::<StatsResponse<Vec<ChannelEmoteUsage>>>()
+        // the stats service doesn't tell the providers apart,
+        // so its emotes are matched with the provider's current ones by name
+        if *provider != EmoteProvider::SevenTV {
+            let provider_emotes = match provider
+                .get_channel_emotes(instance_bundle, channel_id.clone())
                 .await
             {
-                return Some(data);
-            }
+                Some(v) => v,
+                None => return Err(ResponseError::NotFound(request.channel.alias_name.clone())),
+            };
+
+            emotes.retain(|x| provider_emotes.iter().any(|y| y.name.eq(&x.name)));
         }
 
-        None
+        let emote_usages =
+            stats::get_channel_emote_usages(http_client, channel_id.as_str()).await?;
+
+        Ok(emotes.into_iter().find(|x| x.name.eq(name)).map(|x| {
+            let usage = match emote_usages.iter().find(|y| y.emote_id.eq(&x.emote_id)) {
+                Some(usage) => usage.usage_count,
+                None => 0,
+            };
+
+            (x.name, usage)
+        }))
     }
 }
@@ -0,0 +1,126 @@
+use async_trait::async_trait;
+use eyre::Result;
+
+use crate::{
+    commands::{
+        request::Request,
+        response::{Response, ResponseError},
+        Command, CommandArgument,
+    },
+    emotes::{history::format_with_lists, EmoteProvider, ProviderEmote},
+    instance_bundle::InstanceBundle,
+    localization::LineId,
+};
+
+pub struct EmoteDiffCommand;
+
+#[async_trait]
+impl Command for EmoteDiffCommand {
+    fn get_name(&self) -> String {
+        "ediff".to_string()
+    }
+
+    fn get_options(&self) -> Vec<String> {
+        vec!["provider".to_string()]
+    }
+
+    async fn execute(
+        &self,
+        instance_bundle: &InstanceBundle,
+        request: Request,
+    ) -> Result<Response, ResponseError> {
+        let provider = EmoteProvider::from_request(&request)?;
+
+        let message = match request.message.clone() {
+            Some(v) => v,
+            None => return Err(ResponseError::NotEnoughArguments(CommandArgument::Target)),
+        };
+
+        let message_split = message.split_ascii_whitespace().collect::<Vec<&str>>();
+
+        let (origin_channel, target_channel) = match (message_split.first(), message_split.get(1)) {
+            (Some(x), Some(y)) => (x.to_string(), y.to_string()),
+            (Some(x), None) => (request.channel.alias_name.clone(), x.to_string()),
+            _ => return Err(ResponseError::NotEnoughArguments(CommandArgument::Target)),
+        };
+
+        let origin_emotes = match provider
+            .get_channel_emotes_by_login(instance_bundle, origin_channel.as_str())
+            .await
+        {
+            Some(v) => v,
+            None => return Err(ResponseError::NotFound(origin_channel)),
+        };
+
+        let target_emotes = match provider
+            .get_channel_emotes_by_login(instance_bundle, target_channel.as_str())
+            .await
+        {
+            Some(v) => v,
+            None => return Err(ResponseError::NotFound(target_channel)),
+        };
+
+        let origin_only = self.get_exclusive_names(&origin_emotes, &target_emotes);
+        let target_only = self.get_exclusive_names(&target_emotes, &origin_emotes);
+
+        let provider_name = instance_bundle
+            .localizator
+            .get_literal_text(
+                request.channel_preference.language.as_str(),
+                provider.line_id(),
+            )
+            .unwrap();
+
+        if origin_only.is_empty() && target_only.is_empty() {
+            return Ok(Response::Single(
+                instance_bundle.localizator.formatted_text_by_request(
+                    &request,
+                    LineId::EmoteDiffSame,
+                    vec![provider_name, origin_channel, target_channel],
+                ),
+            ));
+        }
+
+        Ok(Response::Single(format_with_lists(
+            &[(&origin_only, " "), (&target_only, " ")],
+            |lists| {
+                instance_bundle.localizator.formatted_text_by_request(
+                    &request,
+                    LineId::EmoteDiffResponse,
+                    vec![
+                        provider_name.clone(),
+                        origin_channel.clone(),
+                        origin_only.len().to_string(),
+                        self.format_names(&origin_only, &lists[0]),
+                        target_channel.clone(),
+                        target_only.len().to_string(),
+                        self.format_names(&target_only, &lists[1]),
+                    ],
+                )
+            },
+        )))
+    }
+}
+
+impl EmoteDiffCommand {
+    /// Gets the names of the emotes that are in the first set, but not in the second one.
+    fn get_exclusive_names(
+        &self,
+        emotes: &[ProviderEmote],
+        other: &[ProviderEmote],
+    ) -> Vec<String> {
+        emotes
+            .iter()
+            .filter(|x| !other.iter().any(|y| x.id.eq(&y.id)))
+            .map(|x| x.name.clone())
+            .collect()
+    }
+
+    fn format_names(&self, names: &[String], list: &str) -> String {
+        if names.is_empty() {
+            "-".to_string()
+        } else {
+            list.to_string()
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use common::{establish_connection, models::EmoteEventAction};
+use eyre::Result;
+
+use crate::{
+    commands::{
+        request::Request,
+        response::{Response, ResponseError},
+        Command,
+    },
+    emotes::{
+        history::{format_with_lists, get_emote_events},
+        EmoteProvider,
+    },
+    instance_bundle::InstanceBundle,
+    localization::LineId,
+    shared_variables::{EMOTE_HISTORY_DEFAULT_AMOUNT, EMOTE_HISTORY_MAX_AMOUNT},
+    utils::format_timestamp,
+};
+
+pub struct EmoteHistoryCommand;
+
+#[async_trait]
+impl Command for EmoteHistoryCommand {
+    fn get_name(&self) -> String {
+        "ehistory".to_string()
+    }
+
+    fn get_options(&self) -> Vec<String> {
+        vec!["provider".to_string()]
+    }
+
+    async fn execute(
+        &self,
+        instance_bundle: &InstanceBundle,
+        request: Request,
+    ) -> Result<Response, ResponseError> {
+        let provider = EmoteProvider::from_request(&request)?;
+
+        let amount = match request.message.clone() {
+            Some(v) => match v.trim().parse::<i64>() {
+                Ok(x) if x > 0 => x.min(EMOTE_HISTORY_MAX_AMOUNT),
+                _ => return Err(ResponseError::IncorrectArgument(v)),
+            },
+            None => EMOTE_HISTORY_DEFAULT_AMOUNT,
+        };
+
+        let conn = &mut establish_connection();
+
+        let events = get_emote_events(conn, request.channel.id, &provider, amount);
+
+        let provider_name = instance_bundle
+            .localizator
+            .get_literal_text(
+                request.channel_preference.language.as_str(),
+                provider.line_id(),
+            )
+            .unwrap();
+
+        if events.is_empty() {
+            return Ok(Response::Single(
+                instance_bundle.localizator.formatted_text_by_request(
+                    &request,
+                    LineId::EmoteHistoryNoChanges,
+                    vec![provider_name],
+                ),
+            ));
+        }
+
+        let now = Utc::now().naive_utc();
+
+        let changes = events
+            .into_iter()
+            .map(|x| {
+                let change = match x.action {
+                    EmoteEventAction::Push => format!("+{}", x.emote_name),
+                    EmoteEventAction::Pull => format!("-{}", x.emote_name),
+                    EmoteEventAction::Update => format!(
+                        "{} → {}",
+                        x.old_emote_name.unwrap_or_default(),
+                        x.emote_name
+                    ),
+                };
+
+                let ago = format_timestamp((now - x.created_at).num_seconds().max(0) as u64);
+
+                match x.actor_name {
+                    Some(actor_name) => format!("{} ({}, {})", change, actor_name, ago),
+                    None => format!("{} ({})", change, ago),
+                }
+            })
+            .collect::<Vec<String>>();
+
+        // the oldest changes are left out if they don't fit into the message
+        Ok(Response::Single(format_with_lists(
+            &[(&changes, ", ")],
+            |lists| {
+                instance_bundle.localizator.formatted_text_by_request(
+                    &request,
+                    LineId::EmoteHistoryResponse,
+                    vec![provider_name.clone(), lists[0].clone()],
+                )
+            },
+        )))
+    }
+}
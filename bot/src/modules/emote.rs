@@ -0,0 +1,150 @@
+use async_trait::async_trait;
+use common::establish_connection;
+use eyre::Result;
+
+use crate::{
+    commands::{
+        request::Request,
+        response::{Response, ResponseError},
+        Command, CommandArgument,
+    },
+    emotes::{
+        history::get_emote_addition,
+        usage::{get_emote_usages, EmoteUsageCounter},
+        EmoteProvider,
+    },
+    instance_bundle::InstanceBundle,
+    localization::LineId,
+};
+
+pub struct EmoteCommand;
+
+#[async_trait]
+impl Command for EmoteCommand {
+    fn get_name(&self) -> String {
+        "emote".to_string()
+    }
+
+    fn get_options(&self) -> Vec<String> {
+        vec!["provider".to_string()]
+    }
+
+    async fn execute(
+        &self,
+        instance_bundle: &InstanceBundle,
+        request: Request,
+    ) -> Result<Response, ResponseError> {
+        let name = match request.message.clone() {
+            Some(v) => v.trim().to_string(),
+            None => return Err(ResponseError::NotEnoughArguments(CommandArgument::Name)),
+        };
+
+        // without the provider, the emote is looked up in the order the 7TV extension shows them
+        let providers = if request.options.contains_key("provider") {
+            vec![EmoteProvider::from_request(&request)?]
+        } else {
+            vec![
+                EmoteProvider::SevenTV,
+                EmoteProvider::BetterTTV,
+                EmoteProvider::FrankerFaceZ,
+                EmoteProvider::Twitch,
+            ]
+        };
+
+        let cache = &instance_bundle.emote_details_cache;
+        let mut found = None;
+
+        for provider in providers {
+            if let Some(emote) = cache
+                .get_channel_emotes(instance_bundle, &provider, request.channel.alias_id)
+                .await
+                .and_then(|x| x.into_iter().find(|y| y.name.eq(&name)))
+            {
+                found = Some((provider, emote));
+                break;
+            }
+        }
+
+        let (provider, emote) = match found {
+            Some(v) => v,
+            None => return Err(ResponseError::NotFound(name)),
+        };
+
+        let conn = &mut establish_connection();
+        let language = request.channel_preference.language.as_str();
+        let localizator = &instance_bundle.localizator;
+
+        let mut parts: Vec<String> = Vec::new();
+
+        // the emotes without an author are uploaded by the channel itself
+        parts.push(
+            localizator
+                .get_formatted_text(
+                    language,
+                    LineId::EmoteInfoAuthor,
+                    vec![emote
+                        .author
+                        .clone()
+                        .unwrap_or_else(|| request.channel.alias_name.clone())],
+                )
+                .unwrap(),
+        );
+
+        // the recorded history is preferred, as only 7TV tells when and by whom the emote was added
+        let (added_at, actor_name) =
+            match get_emote_addition(conn, request.channel.id, &provider, &emote.id) {
+                Some(v) => (Some(v.created_at), v.actor_name),
+                None => (
+                    emote.added_at,
+                    match &emote.actor_id {
+                        Some(v) => cache.get_seventv_user_name(instance_bundle, v).await,
+                        None => None,
+                    },
+                ),
+            };
+
+        if let Some(added_at) = added_at {
+            let date = added_at.format("%Y-%m-%d").to_string();
+
+            parts.push(
+                match actor_name {
+                    Some(actor_name) => localizator.get_formatted_text(
+                        language,
+                        LineId::EmoteInfoAddedBy,
+                        vec![date, actor_name],
+                    ),
+                    None => {
+                        localizator.get_formatted_text(language, LineId::EmoteInfoAdded, vec![date])
+                    }
+                }
+                .unwrap(),
+            );
+        }
+
+        if EmoteUsageCounter::is_enabled() {
+            let usage_count = get_emote_usages(conn, request.channel.id, &provider)
+                .into_iter()
+                .find(|x| x.emote_id.eq(&emote.id))
+                .map(|x| x.usage_count)
+                .unwrap_or_default();
+
+            parts.push(
+                localizator
+                    .get_formatted_text(language, LineId::EmoteInfoUsage, vec![usage_count])
+                    .unwrap(),
+            );
+        }
+
+        parts.push(provider.emote_url(&emote.id));
+
+        let provider_name = localizator
+            .get_literal_text(language, provider.line_id())
+            .unwrap();
+
+        Ok(Response::Single(localizator.formatted_text_by_request(
+            &request,
+            LineId::EmoteInfoResponse,
+            vec![provider_name, emote.name, parts.join(" | ")],
+        )))
+    }
+}
@@ -1,8 +1,5 @@
-use std::sync::Arc;
-
 use async_trait::async_trait;
 use eyre::Result;
-use twitch_api::{twitch_oauth2::UserToken, types::NicknameRef, HelixClient};
 
 use crate::{
     commands::{
@@ -10,12 +7,9 @@ use crate::{
         response::{Response, ResponseError},
         Command, CommandArgument,
     },
+    emotes::{history::format_with_lists, EmoteProvider, ProviderEmote},
     instance_bundle::InstanceBundle,
     localization::LineId,
-    seventv::api::{
-        schema::{Emote, EmoteSet},
-        SevenTVAPIClient,
-    },
 };
 
 pub struct EmoteSimilarityCommand;
@@ -26,11 +20,21 @@ impl Command for EmoteSimilarityCommand {
         "esim".to_string()
     }
 
+    fn get_options(&self) -> Vec<String> {
+        vec!["provider".to_string()]
+    }
+
+    fn get_subcommands(&self) -> Vec<String> {
+        vec!["shared".to_string(), "missing".to_string()]
+    }
+
     async fn execute(
         &self,
         instance_bundle: &InstanceBundle,
         request: Request,
     ) -> Result<Response, ResponseError> {
+        let provider = EmoteProvider::from_request(&request)?;
+
         if let Some(message) = request.message.clone() {
             let message_split = message.split_ascii_whitespace().collect::<Vec<&str>>();
 
@@ -46,32 +50,89 @@ impl Command for EmoteSimilarityCommand {
                 )
             };
 
-            if let Some(origin_emote_set) = self
-                .get_emote_set(
-                    origin_channel.clone(),
-                    instance_bundle.twitch_api_client.clone(),
-                    instance_bundle.twitch_api_token.clone(),
-                    instance_bundle.seventv_api_client.clone(),
-                )
+            if let Some(origin_emotes) = provider
+                .get_channel_emotes_by_login(instance_bundle, origin_channel.as_str())
                 .await
             {
-                if let Some(target_emote_set) = self
-                    .get_emote_set(
-                        target_channel.clone(),
-                        instance_bundle.twitch_api_client.clone(),
-                        instance_bundle.twitch_api_token.clone(),
-                        instance_bundle.seventv_api_client.clone(),
-                    )
+                if let Some(target_emotes) = provider
+                    .get_channel_emotes_by_login(instance_bundle, target_channel.as_str())
                     .await
                 {
-                    let final_emote_set = origin_emote_set
-                        .emotes
+                    let final_emote_set = origin_emotes
                         .iter()
-                        .filter(|x| target_emote_set.emotes.iter().any(|y| x.id.eq(&y.id)))
-                        .collect::<Vec<&Emote>>();
+                        .filter(|x| target_emotes.iter().any(|y| x.id.eq(&y.id)))
+                        .collect::<Vec<&ProviderEmote>>();
+
+                    let provider_name = instance_bundle
+                        .localizator
+                        .get_literal_text(
+                            request.channel_preference.language.as_str(),
+                            provider.line_id(),
+                        )
+                        .unwrap();
 
-                    let percentage = ((final_emote_set.len() as f32
-                        / origin_emote_set.emotes.len() as f32)
+                    if request.subcommand_id.as_deref() == Some("missing") {
+                        let missing_emotes = origin_emotes
+                            .iter()
+                            .filter(|x| !target_emotes.iter().any(|y| x.id.eq(&y.id)))
+                            .map(|x| x.name.clone())
+                            .collect::<Vec<String>>();
+
+                        if missing_emotes.is_empty() {
+                            return Ok(Response::Single(
+                                instance_bundle.localizator.formatted_text_by_request(
+                                    &request,
+                                    LineId::EmoteSimilarityNothingMissing,
+                                    vec![provider_name, origin_channel, target_channel],
+                                ),
+                            ));
+                        }
+
+                        return Ok(Response::Single(format_with_lists(
+                            &[(&missing_emotes, " ")],
+                            |lists| {
+                                instance_bundle.localizator.formatted_text_by_request(
+                                    &request,
+                                    LineId::EmoteSimilarityMissing,
+                                    vec![
+                                        provider_name.clone(),
+                                        origin_channel.clone(),
+                                        target_channel.clone(),
+                                        missing_emotes.len().to_string(),
+                                        lists[0].clone(),
+                                    ],
+                                )
+                            },
+                        )));
+                    }
+
+                    if request.subcommand_id.as_deref() == Some("shared")
+                        && !final_emote_set.is_empty()
+                    {
+                        let shared_emotes = final_emote_set
+                            .iter()
+                            .map(|x| x.name.clone())
+                            .collect::<Vec<String>>();
+
+                        return Ok(Response::Single(format_with_lists(
+                            &[(&shared_emotes, " ")],
+                            |lists| {
+                                instance_bundle.localizator.formatted_text_by_request(
+                                    &request,
+                                    LineId::EmoteSimilarityShared,
+                                    vec![
+                                        provider_name.clone(),
+                                        origin_channel.clone(),
+                                        target_channel.clone(),
+                                        shared_emotes.len().to_string(),
+                                        lists[0].clone(),
+                                    ],
+                                )
+                            },
+                        )));
+                    }
+
+                    let percentage = ((final_emote_set.len() as f32 / origin_emotes.len() as f32)
                         * 100.0)
                         .trunc();
 
@@ -80,17 +141,7 @@ impl Command for EmoteSimilarityCommand {
                             instance_bundle.localizator.formatted_text_by_request(
                                 &request,
                                 LineId::EmoteSimilaritySetNotSimilar,
-                                vec![
-                                    instance_bundle
-                                        .localizator
-                                        .get_literal_text(
-                                            request.channel_preference.language.as_str(),
-                                            LineId::Provider7TV,
-                                        )
-                                        .unwrap(),
-                                    origin_channel,
-                                    target_channel,
-                                ],
+                                vec![provider_name, origin_channel, target_channel],
                             ),
                         ));
                     }
@@ -100,17 +151,11 @@ impl Command for EmoteSimilarityCommand {
                             &request,
                             LineId::EmoteSimilaritySetSimilar,
                             vec![
-                                instance_bundle
-                                    .localizator
-                                    .get_literal_text(
-                                        request.channel_preference.language.as_str(),
-                                        LineId::Provider7TV,
-                                    )
-                                    .unwrap(),
+                                provider_name,
                                 origin_channel,
                                 target_channel,
                                 final_emote_set.len().to_string(),
-                                origin_emote_set.emotes.len().to_string(),
+                                origin_emotes.len().to_string(),
                                 percentage.to_string(),
                             ],
                         ),
@@ -126,27 +171,3 @@ impl Command for EmoteSimilarityCommand {
         Err(ResponseError::NotEnoughArguments(CommandArgument::Target))
     }
 }
-
-impl EmoteSimilarityCommand {
-    async fn get_emote_set(
-        &self,
-        twitch_user_name: String,
-        twitch_api: Arc<HelixClient<'static, reqwest::Client>>,
-        twitch_token: Arc<UserToken>,
-        seventv_api: Arc<SevenTVAPIClient>,
-    ) -> Option<EmoteSet> {
-        if let Ok(Some(user)) = twitch_api
-            .get_user_from_login(
-                NicknameRef::from_str(twitch_user_name.as_str()),
-                &*twitch_token,
-            )
-            .await
-        {
-            if let Some(stv_user) = seventv_api.get_user_by_twitch_id(user.id.take()).await {
-                return Some(stv_user.emote_set);
-            }
-        }
-
-        None
-    }
-}
@@ -1,8 +1,7 @@
-use std::env;
-
 use async_trait::async_trait;
+use common::{establish_connection, models::Channel, schema::channels::dsl as ch};
+use diesel::{ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl};
 use eyre::Result;
-use log::error;
 use twitch_api::types::NicknameRef;
 
 use crate::{
@@ -11,9 +10,16 @@ use crate::{
         response::{Response, ResponseError},
         Command,
     },
+    emotes::{
+        stats,
+        usage::{
+            get_emote_counts, get_emote_usages, get_emote_user_counts, get_since_date, get_user_id,
+            EmoteUsageCounter,
+        },
+        EmoteProvider,
+    },
     instance_bundle::InstanceBundle,
     localization::LineId,
-    models::stats::{ChannelEmote, ChannelEmoteUsage, Response as StatsResponse},
 };
 
 pub struct EmoteTopCommand;
@@ -24,8 +30,12 @@ impl Command for EmoteTopCommand {
         "etop".to_string()
     }
 
+    fn get_options(&self) -> Vec<String> {
+        vec!["provider".to_string(), "since".to_string()]
+    }
+
     fn get_subcommands(&self) -> Vec<String> {
-        vec!["desc".to_string(), "asc".to_string()]
+        vec!["desc".to_string(), "asc".to_string(), "users".to_string()]
     }
 
     async fn execute(
@@ -33,10 +43,8 @@ impl Command for EmoteTopCommand {
         instance_bundle: &InstanceBundle,
         request: Request,
     ) -> Result<Response, ResponseError> {
-        if env::var("STATS_API_HOSTNAME").is_err() {
-            error!("Tried to run the !etop command, but STATS_API_HOSTNAME is not set in the .env file");
-            return Err(ResponseError::SomethingWentWrong);
-        }
+        let provider = EmoteProvider::from_request(&request)?;
+        let since = get_since_date(&request)?;
 
         let subcommand_id = match request.subcommand_id.clone() {
             Some(v) => v,
@@ -44,29 +52,54 @@ impl Command for EmoteTopCommand {
         };
 
         let message = request.message.clone().unwrap_or_default();
-        let message_split = message.split_ascii_whitespace().collect::<Vec<&str>>();
+        let mut message_split = message.split_ascii_whitespace().collect::<Vec<&str>>();
+
+        let is_users_mode = subcommand_id.eq("users");
+
+        let user_name = match message_split.first() {
+            Some(v) if v.starts_with('@') && !is_users_mode => {
+                let name = v.trim_start_matches('@').to_lowercase();
+                message_split.remove(0);
+                Some(name)
+            }
+            _ => None,
+        };
 
         let mut amount: usize = 10;
+        let mut emote_name: Option<String> = None;
 
-        let origin_name = match message_split.first() {
-            Some(v) => {
+        let origin_name = if is_users_mode {
+            // the top users are shown for the emote in the current chat room
+            for v in message_split.iter().take(2) {
                 if let Ok(x) = v.to_string().parse::<usize>() {
                     amount = x;
-                    request.channel.alias_name.clone()
                 } else {
-                    v.to_string()
+                    emote_name = Some(v.to_string());
                 }
             }
-            None => request.channel.alias_name.clone(),
-        };
 
-        if let Some(v) = message_split.get(1) {
-            if let Ok(x) = v.to_string().parse::<usize>() {
-                amount = x;
+            request.channel.alias_name.clone()
+        } else {
+            if let Some(v) = message_split.get(1) {
+                if let Ok(x) = v.to_string().parse::<usize>() {
+                    amount = x;
+                }
             }
-        }
 
-        if let Ok(Some(user)) = instance_bundle
+            match message_split.first() {
+                Some(v) => {
+                    if let Ok(x) = v.to_string().parse::<usize>() {
+                        amount = x;
+                        request.channel.alias_name.clone()
+                    } else {
+                        v.to_string()
+                    }
+                }
+                None => request.channel.alias_name.clone(),
+            }
+        };
+
+        let user = match instance_bundle
             .twitch_api_client
             .get_user_from_login(
                 NicknameRef::from_str(origin_name.as_str()),
@@ -74,213 +107,243 @@ impl Command for EmoteTopCommand {
             )
             .await
         {
-            let channel_id = user.id.take();
-
-            if let Some(response) = self.fetch_channel_emotes(channel_id.clone()).await {
-                if response.status_code != 200 {
-                    return Err(ResponseError::ExternalAPIError(
-                        response.status_code,
-                        response.message,
-                    ));
-                }
+            Ok(Some(v)) => v,
+            _ => return Err(ResponseError::NotFound(origin_name)),
+        };
 
-                let emotes = response.data.unwrap_or_default();
+        let channel_id = user.id.take();
 
-                if let Some(response) = self.fetch_channel_emote_usages(channel_id).await {
-                    if response.status_code != 200 {
-                        return Err(ResponseError::ExternalAPIError(
-                            response.status_code,
-                            response.message,
-                        ));
-                    }
+        let mut usages: Vec<(String, i32)> = if EmoteUsageCounter::is_enabled() {
+            let conn = &mut establish_connection();
 
-                    let emote_usages = response.data.unwrap_or_default();
+            // the emotes are counted only in the joined chat rooms
+            let channel = match ch::channels
+                .filter(ch::alias_id.eq(channel_id.parse::<i32>().unwrap()))
+                .first::<Channel>(conn)
+                .optional()
+                .expect("Failed to get channel")
+            {
+                Some(v) => v,
+                None => return Err(ResponseError::NotFound(origin_name)),
+            };
+
+            if is_users_mode {
+                get_emote_user_counts(
+                    conn,
+                    channel.id,
+                    &provider,
+                    emote_name.clone(),
+                    since.as_ref().map(|x| x.1),
+                )
+            } else if user_name.is_some() || since.is_some() {
+                let user_id = match &user_name {
+                    Some(v) => Some(get_user_id(conn, v)?),
+                    None => None,
+                };
+
+                get_emote_counts(
+                    conn,
+                    channel.id,
+                    &provider,
+                    user_id,
+                    since.as_ref().map(|x| x.1),
+                )
+            } else {
+                get_emote_usages(conn, channel.id, &provider)
+                    .into_iter()
+                    .map(|x| (x.emote_name, x.usage_count))
+                    .collect()
+            }
+        } else {
+            // the external stats service doesn't keep the per-user and daily counts
+            if is_users_mode {
+                return Err(ResponseError::IncorrectArgument(subcommand_id));
+            }
 
-                    let mut usages: Vec<(String, i32)> = Vec::new();
+            if let Some(v) = &user_name {
+                return Err(ResponseError::IncorrectArgument(format!("@{}", v)));
+            }
 
-                    for usage in emote_usages {
-                        if let Some(emote) = emotes.iter().find(|x| x.emote_id.eq(&usage.emote_id))
-                        {
-                            if let Some(u_emote) = usages.iter_mut().find(|x| x.0.eq(&emote.name)) {
-                                if u_emote.1 < usage.usage_count {
-                                    u_emote.1 = usage.usage_count;
-                                }
+            if let Some((v, _)) = &since {
+                return Err(ResponseError::IncorrectArgument(format!("--since={}", v)));
+            }
 
-                                continue;
-                            }
+            self.get_stats_usages(instance_bundle, &provider, channel_id, &origin_name)
+                .await?
+        };
 
-                            usages.push((emote.name.clone(), usage.usage_count));
-                        }
-                    }
+        usages.sort_by(|a, b| {
+            if subcommand_id.eq("asc") {
+                a.1.cmp(&b.1)
+            } else {
+                b.1.cmp(&a.1)
+            }
+        });
 
-                    usages.sort_by(|a, b| {
-                        if subcommand_id.eq("asc") {
-                            a.1.cmp(&b.1)
-                        } else {
-                            b.1.cmp(&a.1)
-                        }
-                    });
+        if amount > 50 {
+            amount = 50;
+        }
 
-                    if amount > 50 {
-                        amount = 50;
-                    }
+        if amount > usages.len() {
+            amount = usages.len()
+        }
 
-                    if amount > usages.len() {
-                        amount = usages.len()
-                    }
+        usages.drain(amount..);
 
-                    usages.drain(amount..);
+        let mut message_parts: Vec<String> = Vec::new();
 
-                    let mut message_parts: Vec<String> = Vec::new();
+        for usage in usages {
+            message_parts.push(format!("{} ({})", usage.0, usage.1));
+        }
 
-                    for usage in usages {
-                        message_parts.push(format!("{} ({})", usage.0, usage.1));
-                    }
+        let language = request.channel_preference.language.as_str();
+
+        let provider_name = instance_bundle
+            .localizator
+            .get_literal_text(language, provider.line_id())
+            .unwrap();
+
+        if message_parts.is_empty() {
+            return Ok(Response::Single(
+                instance_bundle.localizator.formatted_text_by_request(
+                    &request,
+                    LineId::EmoteTopNoEmotes,
+                    vec![provider_name, origin_name],
+                ),
+            ));
+        }
 
-                    if message_parts.is_empty() {
-                        return Ok(Response::Single(
-                            instance_bundle.localizator.formatted_text_by_request(
-                                &request,
-                                LineId::EmoteTopNoEmotes,
-                                vec![
-                                    instance_bundle
-                                        .localizator
-                                        .get_literal_text(
-                                            request.channel_preference.language.as_str(),
-                                            LineId::Provider7TV,
-                                        )
-                                        .unwrap(),
-                                    origin_name,
-                                ],
-                            ),
-                        ));
-                    }
+        let period = match &since {
+            Some((v, _)) => instance_bundle
+                .localizator
+                .get_formatted_text(language, LineId::MiscLastPeriod, vec![v.clone()])
+                .unwrap(),
+            None => instance_bundle
+                .localizator
+                .get_literal_text(language, LineId::MiscAllTime)
+                .unwrap(),
+        };
 
-                    return Ok(Response::Single(
-                        instance_bundle.localizator.formatted_text_by_request(
-                            &request,
-                            LineId::EmoteTopResponse,
-                            vec![
-                                instance_bundle
-                                    .localizator
-                                    .get_literal_text(
-                                        request.channel_preference.language.as_str(),
-                                        LineId::Provider7TV,
-                                    )
-                                    .unwrap(),
-                                origin_name,
-                                amount.to_string(),
-                                instance_bundle
-                                    .localizator
-                                    .get_literal_text(
-                                        request.channel_preference.language.as_str(),
-                                        if subcommand_id.eq("asc") {
-                                            LineId::MiscAscending
-                                        } else {
-                                            LineId::MiscDescending
-                                        },
-                                    )
-                                    .unwrap(),
-                                message_parts.join(", "),
-                            ],
-                        ),
-                    ));
-                }
+        let response = if is_users_mode {
+            match emote_name {
+                Some(emote_name) => instance_bundle.localizator.formatted_text_by_request(
+                    &request,
+                    LineId::EmoteTopEmoteUsers,
+                    vec![
+                        provider_name,
+                        origin_name,
+                        amount.to_string(),
+                        emote_name,
+                        period,
+                        message_parts.join(", "),
+                    ],
+                ),
+                None => instance_bundle.localizator.formatted_text_by_request(
+                    &request,
+                    LineId::EmoteTopUsers,
+                    vec![
+                        provider_name,
+                        origin_name,
+                        amount.to_string(),
+                        period,
+                        message_parts.join(", "),
+                    ],
+                ),
             }
         } else {
-            return Err(ResponseError::NotFound(origin_name));
-        }
-
-        Err(ResponseError::SomethingWentWrong)
-    }
-}
-
-impl EmoteTopCommand {
-    async fn fetch_channel_emotes(
-        &self,
-        channel_id: String,
-    ) -> Option<StatsResponse<Vec<ChannelEmote>>> {
-        let url = format!(
-            "{}/api/v1/channel/twitch/{}/emotes",
-            env::var("STATS_API_HOSTNAME")
-                .expect("STATS_API_HOSTNAME must be set for !etop command"),
-            channel_id
-        );
-
-        let client = reqwest::Client::default();
-        let mut request = client.get(url);
-
-        if let Ok(credentials) = env::var("STATS_API_PASSWORD") {
-            let mut split = credentials.split(':').collect::<Vec<&str>>();
-
-            if !split.is_empty() {
-                let name = split[0];
-                split.remove(0);
-
-                let password = split.join(":");
-
-                request = request.basic_auth(
-                    name,
-                    if password.is_empty() {
-                        None
+            let mut descriptor = instance_bundle
+                .localizator
+                .get_literal_text(
+                    language,
+                    if subcommand_id.eq("asc") {
+                        LineId::MiscAscending
                     } else {
-                        Some(password)
+                        LineId::MiscDescending
                     },
-                );
+                )
+                .unwrap();
+
+            if since.is_some() {
+                descriptor = format!("{}, {}", descriptor, period);
             }
-        }
 
-        if let Ok(response) = request.send().await {
-            if let Ok(data) = response.json::<StatsResponse<Vec<ChannelEmote>>>().await {
-                return Some(data);
+            match user_name {
+                Some(user_name) => instance_bundle.localizator.formatted_text_by_request(
+                    &request,
+                    LineId::EmoteTopUserResponse,
+                    vec![
+                        provider_name,
+                        user_name,
+                        amount.to_string(),
+                        origin_name,
+                        descriptor,
+                        message_parts.join(", "),
+                    ],
+                ),
+                None => instance_bundle.localizator.formatted_text_by_request(
+                    &request,
+                    LineId::EmoteTopResponse,
+                    vec![
+                        provider_name,
+                        origin_name,
+                        amount.to_string(),
+                        descriptor,
+                        message_parts.join(", "),
+                    ],
+                ),
             }
-        }
+        };
 
-        None
+        Ok(Response::Single(response))
     }
-    async fn fetch_channel_emote_usages(
+}
+
+impl EmoteTopCommand {
+    /// Gets the names and the usage counts of the emotes from the external stats service.
+    async fn get_stats_usages(
         &self,
+        instance_bundle: &InstanceBundle,
+        provider: &EmoteProvider,
         channel_id: String,
-    ) -> Option<StatsResponse<Vec<ChannelEmoteUsage>>> {
-        let url = format!(
-            "{}/api/v1/channel/twitch/{}/emotes/usage",
-            env::var("STATS_API_HOSTNAME")
-                .expect("STATS_API_HOSTNAME must be set for !etop command"),
-            channel_id
-        );
-
-        let client = reqwest::Client::default();
-        let mut request = client.get(url);
-
-        if let Ok(credentials) = env::var("STATS_API_PASSWORD") {
-            let mut split = credentials.split(':').collect::<Vec<&str>>();
-
-            if !split.is_empty() {
-                let name = split[0];
-                split.remove(0);
-
-                let password = split.join(":");
-
-                request = request.basic_auth(
-                    name,
-                    if password.is_empty() {
-                        None
-                    } else {
-                        Some(password)
-                    },
-                );
-            }
-        }
+        origin_name: &str,
+    ) -> Result<Vec<(String, i32)>, ResponseError> {
+        let http_client = &instance_bundle.http_client;
+
+        let mut emotes = stats::get_channel_emotes(http_client, channel_id.as_str()).await?;
 
-        if let Ok(response) = request.send().await {
-            if let Ok(data) = response
-                .json::<StatsResponse<Vec<ChannelEmoteUsage>>>()
+        // the stats service doesn't tell the providers apart,
+        // so its emotes are matched with the provider's current ones by name
+        if *provider != EmoteProvider::SevenTV {
+            let provider_emotes = match provider
+                .get_channel_emotes(instance_bundle, channel_id.clone())
                 .await
             {
-                return Some(data);
+                Some(v) => v,
+                None => return Err(ResponseError::NotFound(origin_name.to_string())),
+            };
+
+            emotes.retain(|x| provider_emotes.iter().any(|y| y.name.eq(&x.name)));
+        }
+
+        let emote_usages =
+            stats::get_channel_emote_usages(http_client, channel_id.as_str()).await?;
+
+        let mut usages: Vec<(String, i32)> = Vec::new();
+
+        for usage in emote_usages {
+            if let Some(emote) = emotes.iter().find(|x| x.emote_id.eq(&usage.emote_id)) {
+                if let Some(u_emote) = usages.iter_mut().find(|x| x.0.eq(&emote.name)) {
+                    if u_emote.1 < usage.usage_count {
+                        u_emote.1 = usage.usage_count;
+                    }
+
+                    continue;
+                }
+
+                usages.push((emote.name.clone(), usage.usage_count));
             }
         }
 
-        None
+        Ok(usages)
     }
 }
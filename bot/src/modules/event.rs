@@ -1,14 +1,12 @@
-use std::{collections::HashSet, str::FromStr};
+use std::str::FromStr;
 
 use async_trait::async_trait;
+use chrono::{Duration, NaiveDateTime, Utc};
 use diesel::{
     delete, insert_into, update, BelongingToDsl, ExpressionMethods, QueryDsl, RunQueryDsl,
 };
 use eyre::Result;
-use twitch_api::{
-    helix::chat::GetChattersRequest,
-    types::{NicknameRef, UserId},
-};
+use twitch_api::types::{NicknameRef, UserId};
 
 use crate::{
     commands::{
@@ -16,17 +14,21 @@ use crate::{
         response::{Response, ResponseError},
         Command, CommandArgument,
     },
+    handlers::fire_event,
     instance_bundle::InstanceBundle,
     localization::LineId,
-    utils::split_and_wrap_lines,
+    utils::{cron::CronSchedule, format_timestamp, parse_duration},
 };
 
 use common::{
     establish_connection,
-    models::{Event, EventFlag, EventSubscription, EventType, LevelOfRights, NewEvent, User},
-    schema::{event_subscriptions::dsl as evs, events::dsl as ev, users::dsl as us},
+    models::{Event, EventFlag, EventRunSource, EventType, LevelOfRights, NewEvent},
+    schema::{event_subscriptions::dsl as evs, events::dsl as ev},
 };
 
+/// Colors accepted by the Helix chat announcements endpoint.
+const ANNOUNCEMENT_COLORS: [&str; 5] = ["primary", "blue", "green", "orange", "purple"];
+
 pub struct EventCommand;
 
 #[async_trait]
@@ -45,6 +47,8 @@ impl Command for EventCommand {
             "off".to_string(),
             "call".to_string(),
             "flag".to_string(),
+            "toggle".to_string(),
+            "schedule".to_string(),
         ]
     }
 
@@ -126,67 +130,17 @@ impl Command for EventCommand {
 
         let response = match (subcommand_id.as_str(), event) {
             ("call", Some(e)) => {
-                let subs = EventSubscription::belonging_to(&e)
-                    .load::<EventSubscription>(conn)
-                    .expect("Failed to load subscriptions for event");
-
-                let users = us::users
-                    .load::<User>(conn)
-                    .expect("Failed to load users for event");
-
-                let users = users
-                    .iter()
-                    .filter(|x| subs.iter().any(|y| y.user_id == x.id))
-                    .map(|x| format!("@{}", x.alias_name))
-                    .collect::<Vec<String>>();
-
-                let mut subs: HashSet<String> = HashSet::new();
-
-                subs.extend(users);
-
-                if e.flags.contains(&EventFlag::Massping) {
-                    let broadcaster_id = request.channel.alias_id.to_string();
-                    let moderator_id = instance_bundle.twitch_api_token.user_id.clone().take();
-
-                    if let Ok(chatters) = instance_bundle
-                        .twitch_api_client
-                        .req_get(
-                            GetChattersRequest::new(broadcaster_id.as_str(), moderator_id.as_str()),
-                            &*instance_bundle.twitch_api_token,
-                        )
-                        .await
-                    {
-                        let chatters = chatters
-                            .data
-                            .iter()
-                            .map(|x| format!("@{}", x.user_login))
-                            .collect::<HashSet<String>>();
-
-                        subs.extend(chatters);
-                    } else {
-                        return Err(ResponseError::InsufficientRights);
-                    }
-                }
-
-                if subs.is_empty() {
-                    return Ok(Response::Single(format!("⚡ {}", e.message)));
-                }
-
-                let formatted_subs = split_and_wrap_lines(
-                    subs.into_iter()
-                        .collect::<Vec<String>>()
-                        .join(", ")
-                        .as_str(),
-                    ", ",
-                    300 - e.message.len(),
-                );
-
-                let formatted_subs = formatted_subs
-                    .iter()
-                    .map(|x| format!("⚡ {} · {}", e.message, x))
-                    .collect::<Vec<String>>();
-
-                Response::Multiple(formatted_subs)
+                fire_event(
+                    conn,
+                    instance_bundle,
+                    &request.channel,
+                    e,
+                    Vec::new(),
+                    EventRunSource::Command,
+                )
+                .await?;
+
+                Response::Multiple(Vec::new())
             }
 
             ("on", Some(_)) => return Err(ResponseError::NamesakeCreation(name_and_type)),
@@ -262,18 +216,39 @@ impl Command for EventCommand {
             ("off", None) => return Err(ResponseError::NotFound(name_and_type)),
             ("flag", Some(e)) => {
                 let mut flags = e.flags.clone();
-                let flag_str = message_split.join(" ");
+                let mut announcement_color = e.announcement_color.clone();
+
+                let flag_str = match message_split.first() {
+                    Some(v) => v.to_string(),
+                    None => return Err(ResponseError::NotEnoughArguments(CommandArgument::Value)),
+                };
 
                 if let Ok(flag) = EventFlag::from_str(flag_str.as_str()) {
                     let r = if let Some(position) = e.flags.iter().position(|x| x == &flag) {
                         flags.remove(position);
 
+                        if flag == EventFlag::Announce {
+                            announcement_color = None;
+                        }
+
                         Response::Single(instance_bundle.localizator.formatted_text_by_request(
                             &request,
                             LineId::EventFlagOff,
                             vec![flag_str, target_name, event_type.to_string()],
                         ))
                     } else {
+                        if flag == EventFlag::Announce {
+                            if let Some(color) = message_split.get(1) {
+                                if !ANNOUNCEMENT_COLORS.contains(color) {
+                                    return Err(ResponseError::IncorrectArgument(
+                                        color.to_string(),
+                                    ));
+                                }
+
+                                announcement_color = Some(color.to_string());
+                            }
+                        }
+
                         flags.push(flag);
 
                         Response::Single(instance_bundle.localizator.formatted_text_by_request(
@@ -284,7 +259,10 @@ impl Command for EventCommand {
                     };
 
                     update(ev::events.find(&e.id))
-                        .set(ev::flags.eq(&flags))
+                        .set((
+                            ev::flags.eq(&flags),
+                            ev::announcement_color.eq(&announcement_color),
+                        ))
                         .execute(conn)
                         .expect("Failed to update event flags");
 
@@ -293,6 +271,90 @@ impl Command for EventCommand {
                     return Err(ResponseError::IncorrectArgument(flag_str));
                 }
             }
+            ("toggle", Some(e)) => {
+                update(ev::events.find(&e.id))
+                    .set(ev::is_enabled.eq(!e.is_enabled))
+                    .execute(conn)
+                    .expect("Failed to toggle the event");
+
+                Response::Single(instance_bundle.localizator.formatted_text_by_request(
+                    &request,
+                    if !e.is_enabled {
+                        LineId::EventEnabled
+                    } else {
+                        LineId::EventDisabled
+                    },
+                    vec![target_name, event_type.to_string()],
+                ))
+            }
+            ("schedule", Some(e)) => {
+                if e.event_type != EventType::Custom {
+                    return Err(ResponseError::IncorrectArgument(name_and_type));
+                }
+
+                let mode = match message_split.first() {
+                    Some(v) if !v.is_empty() => v.to_string(),
+                    _ => return Err(ResponseError::NotEnoughArguments(CommandArgument::Value)),
+                };
+
+                let value = message_split[1..].join(" ");
+                let now = Utc::now().naive_utc();
+
+                let (scheduled_at, schedule_cron) = match mode.as_str() {
+                    "off" => (None, None),
+                    "in" => match parse_duration(&value)
+                        .filter(|x| *x > 0)
+                        .and_then(|x| Duration::from_std(std::time::Duration::from_secs(x)).ok())
+                        .and_then(|x| now.checked_add_signed(x))
+                    {
+                        Some(v) => (Some(v), None),
+                        None => return Err(ResponseError::IncorrectArgument(value)),
+                    },
+                    "at" => match NaiveDateTime::parse_from_str(&value, "%Y-%m-%d %H:%M") {
+                        Ok(v) if v > now => (Some(v), None),
+                        _ => return Err(ResponseError::IncorrectArgument(value)),
+                    },
+                    "cron" => match CronSchedule::from_str(&value)
+                        .ok()
+                        .and_then(|x| x.next_after(now))
+                    {
+                        Some(v) => (Some(v), Some(value)),
+                        None => return Err(ResponseError::IncorrectArgument(value)),
+                    },
+                    _ => return Err(ResponseError::IncorrectArgument(mode)),
+                };
+
+                update(ev::events.find(&e.id))
+                    .set((
+                        ev::scheduled_at.eq(&scheduled_at),
+                        ev::schedule_cron.eq(&schedule_cron),
+                        // the reminders that are already due are skipped
+                        ev::reminded_at.eq(scheduled_at.map(|_| now)),
+                    ))
+                    .execute(conn)
+                    .expect("Failed to update the event schedule");
+
+                match scheduled_at {
+                    Some(v) => {
+                        Response::Single(instance_bundle.localizator.formatted_text_by_request(
+                            &request,
+                            LineId::EventScheduled,
+                            vec![
+                                target_name,
+                                event_type.to_string(),
+                                format_timestamp((v - now).num_seconds() as u64),
+                            ],
+                        ))
+                    }
+                    None => {
+                        Response::Single(instance_bundle.localizator.formatted_text_by_request(
+                            &request,
+                            LineId::EventScheduleOff,
+                            vec![target_name, event_type.to_string()],
+                        ))
+                    }
+                }
+            }
             _ => {
                 return Err(ResponseError::SomethingWentWrong);
             }
@@ -0,0 +1,96 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use common::establish_connection;
+use eyre::Result;
+
+use crate::{
+    commands::{
+        request::Request,
+        response::{Response, ResponseError},
+        Command, CommandArgument,
+    },
+    emotes::{history::get_emote_addition, EmoteProvider},
+    instance_bundle::InstanceBundle,
+    localization::LineId,
+    utils::format_timestamp,
+};
+
+pub struct EmoteWhoCommand;
+
+#[async_trait]
+impl Command for EmoteWhoCommand {
+    fn get_name(&self) -> String {
+        "ewho".to_string()
+    }
+
+    fn get_options(&self) -> Vec<String> {
+        vec!["provider".to_string()]
+    }
+
+    async fn execute(
+        &self,
+        instance_bundle: &InstanceBundle,
+        request: Request,
+    ) -> Result<Response, ResponseError> {
+        let provider = EmoteProvider::from_request(&request)?;
+
+        let name = match request.message.clone() {
+            Some(v) => v.trim().to_string(),
+            None => return Err(ResponseError::NotEnoughArguments(CommandArgument::Name)),
+        };
+
+        // the emote is looked up in the current set, so a re-added emote is told apart from the old one
+        let emote = match provider
+            .get_channel_emotes(instance_bundle, request.channel.alias_id.to_string())
+            .await
+        {
+            Some(v) => match v.into_iter().find(|x| x.name.eq(&name)) {
+                Some(v) => v,
+                None => return Err(ResponseError::NotFound(name)),
+            },
+            None => return Err(ResponseError::NotFound(request.channel.alias_name.clone())),
+        };
+
+        let conn = &mut establish_connection();
+
+        let provider_name = instance_bundle
+            .localizator
+            .get_literal_text(
+                request.channel_preference.language.as_str(),
+                provider.line_id(),
+            )
+            .unwrap();
+
+        let event = match get_emote_addition(conn, request.channel.id, &provider, &emote.id) {
+            Some(v) => v,
+            None => {
+                return Ok(Response::Single(
+                    instance_bundle.localizator.formatted_text_by_request(
+                        &request,
+                        LineId::EmoteWhoUnknown,
+                        vec![provider_name, emote.name],
+                    ),
+                ))
+            }
+        };
+
+        let ago = format_timestamp(
+            (Utc::now().naive_utc() - event.created_at)
+                .num_seconds()
+                .max(0) as u64,
+        );
+
+        Ok(Response::Single(match event.actor_name {
+            Some(actor_name) => instance_bundle.localizator.formatted_text_by_request(
+                &request,
+                LineId::EmoteWhoResponse,
+                vec![provider_name, emote.name, actor_name, ago],
+            ),
+            None => instance_bundle.localizator.formatted_text_by_request(
+                &request,
+                LineId::EmoteWhoAnonymous,
+                vec![provider_name, emote.name, ago],
+            ),
+        }))
+    }
+}
@@ -0,0 +1,229 @@
+use std::str::FromStr;
+
+use async_trait::async_trait;
+use diesel::{
+    delete, insert_into, BelongingToDsl, ExpressionMethods, OptionalExtension, QueryDsl,
+    RunQueryDsl,
+};
+use eyre::Result;
+
+use crate::{
+    commands::{
+        request::Request,
+        response::{Response, ResponseError},
+        Command, CommandArgument,
+    },
+    instance_bundle::InstanceBundle,
+    localization::LineId,
+    shared_variables::{BANNED_PHRASE_DEFAULT_TIMEOUT_SEC, BANNED_PHRASE_MAX_TIMEOUT_SEC},
+    utils::{parse_duration, split_and_wrap_lines},
+};
+
+use common::{
+    establish_connection,
+    models::{BannedPhrase, BannedPhraseAction, BannedPhraseKind, LevelOfRights, NewBannedPhrase},
+    schema::banned_phrases::dsl as bp,
+};
+
+pub struct FilterCommand;
+
+#[async_trait]
+impl Command for FilterCommand {
+    fn get_name(&self) -> String {
+        "filter".to_string()
+    }
+
+    fn required_rights(&self) -> LevelOfRights {
+        LevelOfRights::Moderator
+    }
+
+    fn get_subcommands(&self) -> Vec<String> {
+        vec!["add".to_string(), "remove".to_string(), "list".to_string()]
+    }
+
+    fn get_options(&self) -> Vec<String> {
+        vec![
+            "type".to_string(),
+            "action".to_string(),
+            "duration".to_string(),
+        ]
+    }
+
+    async fn execute(
+        &self,
+        instance_bundle: &InstanceBundle,
+        request: Request,
+    ) -> Result<Response, ResponseError> {
+        let subcommand_id = match request.subcommand_id.clone() {
+            Some(v) => v,
+            None => {
+                return Err(ResponseError::NotEnoughArguments(
+                    CommandArgument::Subcommand,
+                ))
+            }
+        };
+
+        let conn = &mut establish_connection();
+
+        if subcommand_id == "list" {
+            let phrases = BannedPhrase::belonging_to(&request.channel)
+                .order(bp::id.asc())
+                .load::<BannedPhrase>(conn)
+                .expect("Failed to get banned phrases");
+
+            if phrases.is_empty() {
+                return Ok(Response::Single(
+                    instance_bundle.localizator.formatted_text_by_request(
+                        &request,
+                        LineId::CommandFilterListEmpty,
+                        Vec::<String>::new(),
+                    ),
+                ));
+            }
+
+            // the patterns are not shown, so the bot does not repeat the banned phrases itself
+            let phrases = phrases
+                .iter()
+                .map(|x| match x.timeout_sec {
+                    Some(v) => format!("{} ({}, {} {}s)", x.id, x.kind, x.action, v),
+                    None => format!("{} ({}, {})", x.id, x.kind, x.action),
+                })
+                .collect::<Vec<String>>();
+
+            // every line must fit into a single message along with the response text
+            let empty_line = instance_bundle.localizator.formatted_text_by_request(
+                &request,
+                LineId::CommandFilterList,
+                vec!["".to_string()],
+            );
+
+            return Ok(Response::Multiple(
+                split_and_wrap_lines(
+                    phrases.join(", ").as_str(),
+                    ", ",
+                    500usize.saturating_sub(empty_line.len()),
+                )
+                .into_iter()
+                .map(|x| {
+                    instance_bundle.localizator.formatted_text_by_request(
+                        &request,
+                        LineId::CommandFilterList,
+                        vec![x],
+                    )
+                })
+                .collect::<Vec<String>>(),
+            ));
+        }
+
+        let message = match request.message.clone() {
+            Some(v) if !v.trim().is_empty() => v.trim().to_string(),
+            _ => return Err(ResponseError::NotEnoughArguments(CommandArgument::Value)),
+        };
+
+        let response = match subcommand_id.as_str() {
+            "add" => {
+                let kind = match request.options.get("type") {
+                    Some(v) => match BannedPhraseKind::from_str(v) {
+                        Ok(v) => v,
+                        Err(_) => return Err(ResponseError::IncorrectArgument(v.clone())),
+                    },
+                    None => BannedPhraseKind::Text,
+                };
+
+                let timeout_sec = match request.options.get("duration") {
+                    Some(v) => match parse_duration(v) {
+                        Some(x) if x > 0 && x <= BANNED_PHRASE_MAX_TIMEOUT_SEC as u64 => {
+                            Some(x as i32)
+                        }
+                        _ => return Err(ResponseError::IncorrectArgument(v.clone())),
+                    },
+                    None => None,
+                };
+
+                // the duration alone is enough to make the phrase a timeout one
+                let action = match request.options.get("action") {
+                    Some(v) => match BannedPhraseAction::from_str(v) {
+                        Ok(v) => v,
+                        Err(_) => return Err(ResponseError::IncorrectArgument(v.clone())),
+                    },
+                    None if timeout_sec.is_some() => BannedPhraseAction::Timeout,
+                    None => BannedPhraseAction::Delete,
+                };
+
+                let timeout_sec = match action {
+                    BannedPhraseAction::Timeout => {
+                        Some(timeout_sec.unwrap_or(BANNED_PHRASE_DEFAULT_TIMEOUT_SEC))
+                    }
+                    _ => None,
+                };
+
+                // the errors name the type and the ID instead of the pattern,
+                // so the bot does not repeat the banned phrase itself
+                if kind.compile(&message).is_err() {
+                    return Err(ResponseError::IncorrectArgument(kind.to_string()));
+                }
+
+                if let Some(id) = BannedPhrase::belonging_to(&request.channel)
+                    .filter(bp::kind.eq(&kind))
+                    .filter(bp::pattern.eq(&message))
+                    .select(bp::id)
+                    .first::<i32>(conn)
+                    .optional()
+                    .expect("Failed to get a banned phrase")
+                {
+                    return Err(ResponseError::NamesakeCreation(format!("ID {}", id)));
+                }
+
+                let phrase_id = insert_into(bp::banned_phrases)
+                    .values([NewBannedPhrase {
+                        channel_id: request.channel.id,
+                        pattern: message.clone(),
+                        kind,
+                        action,
+                        timeout_sec,
+                    }])
+                    .returning(bp::id)
+                    .get_result::<i32>(conn)
+                    .expect("Failed to create a banned phrase");
+
+                instance_bundle.localizator.formatted_text_by_request(
+                    &request,
+                    LineId::CommandFilterAdded,
+                    vec![phrase_id.to_string()],
+                )
+            }
+            "remove" => {
+                let phrase_id = match message.parse::<i32>() {
+                    Ok(v) => v,
+                    Err(_) => return Err(ResponseError::IncorrectArgument(message)),
+                };
+
+                let deleted = delete(
+                    bp::banned_phrases
+                        .filter(bp::id.eq(&phrase_id))
+                        .filter(bp::channel_id.eq(&request.channel.id)),
+                )
+                .execute(conn)
+                .expect("Failed to delete a banned phrase");
+
+                if deleted == 0 {
+                    return Err(ResponseError::NotFound(phrase_id.to_string()));
+                }
+
+                instance_bundle.localizator.formatted_text_by_request(
+                    &request,
+                    LineId::CommandFilterRemoved,
+                    vec![phrase_id.to_string()],
+                )
+            }
+            _ => return Err(ResponseError::SomethingWentWrong),
+        };
+
+        instance_bundle
+            .phrase_filter
+            .invalidate(request.channel.alias_id)
+            .await;
+
+        Ok(Response::Single(response))
+    }
+}
@@ -1,8 +1,9 @@
+use std::time::Duration as StdDuration;
+
 use async_trait::async_trait;
 use chrono::{Datelike, Duration, Utc};
 use eyre::Result;
 use rand::seq::SliceRandom;
-use reqwest::StatusCode;
 use substring::Substring;
 
 use crate::{
@@ -11,9 +12,10 @@ use crate::{
         response::{Response, ResponseError},
         Command,
     },
+    http::{HttpError, RequestOptions},
     instance_bundle::InstanceBundle,
     localization::LineId,
-    shared_variables::HOLIDAY_V1_API_URL,
+    shared_variables::{HOLIDAY_CACHE_TTL_SEC, HOLIDAY_V1_API_URL},
 };
 
 pub struct HolidayCommand;
@@ -72,42 +74,49 @@ impl Command for HolidayCommand {
 
         let url = format!("{}/{}/{}", HOLIDAY_V1_API_URL, month, day);
 
-        match reqwest::get(url).await {
-            Ok(response) if response.status() == StatusCode::NOT_FOUND => Err(
-                ResponseError::IncorrectArgument(format!("{}.{}", day, month)),
-            ),
-            Ok(response) => match response.json::<Vec<String>>().await {
-                Ok(value) => {
-                    let mut rng = rand::thread_rng();
-                    let holiday = value.choose(&mut rng);
-
-                    Ok(Response::Single(
-                        instance_bundle.localizator.formatted_text_by_request(
-                            &request,
-                            if holiday.is_none() {
-                                LineId::CommandHolidayEmpty
-                            } else {
-                                LineId::CommandHolidayResponse
-                            },
-                            if let Some(holiday) = holiday {
-                                let position = value.iter().position(|x| x.eq(holiday)).unwrap();
-
-                                vec![
-                                    day.to_string(),
-                                    month.to_string(),
-                                    position.to_string(),
-                                    value.len().to_string(),
-                                    holiday.clone(),
-                                ]
-                            } else {
-                                vec![day.to_string(), month.to_string()]
-                            },
-                        ),
-                    ))
-                }
-                Err(_) => Err(ResponseError::SomethingWentWrong),
-            },
-            Err(e) => Err(ResponseError::ExternalAPIError(0, Some(e.to_string()))),
+        let options = RequestOptions {
+            cache_ttl: Some(StdDuration::from_secs(HOLIDAY_CACHE_TTL_SEC)),
+            ..Default::default()
+        };
+
+        match instance_bundle
+            .http_client
+            .get_json::<Vec<String>>(url.as_str(), &options)
+            .await
+        {
+            Err(HttpError::Status(404, _)) => Err(ResponseError::IncorrectArgument(format!(
+                "{}.{}",
+                day, month
+            ))),
+            Ok(value) => {
+                let mut rng = rand::thread_rng();
+                let holiday = value.choose(&mut rng);
+
+                Ok(Response::Single(
+                    instance_bundle.localizator.formatted_text_by_request(
+                        &request,
+                        if holiday.is_none() {
+                            LineId::CommandHolidayEmpty
+                        } else {
+                            LineId::CommandHolidayResponse
+                        },
+                        if let Some(holiday) = holiday {
+                            let position = value.iter().position(|x| x.eq(holiday)).unwrap();
+
+                            vec![
+                                day.to_string(),
+                                month.to_string(),
+                                position.to_string(),
+                                value.len().to_string(),
+                                holiday.clone(),
+                            ]
+                        } else {
+                            vec![day.to_string(), month.to_string()]
+                        },
+                    ),
+                ))
+            }
+            Err(e) => Err(e.into()),
         }
     }
 }
@@ -1,9 +1,14 @@
 use std::env;
 
 use async_trait::async_trait;
-use diesel::{insert_into, ExpressionMethods, QueryDsl, RunQueryDsl};
+use chrono::{Duration, NaiveDateTime, Utc};
+use diesel::{
+    insert_into, update, ExpressionMethods, PgArrayExpressionMethods, PgConnection, QueryDsl,
+    RunQueryDsl,
+};
 use log::warn;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use twitch_api::types::NicknameRef;
 
 use crate::{
     commands::{
@@ -11,15 +16,18 @@ use crate::{
         response::{Response, ResponseError},
         Command,
     },
+    handlers::join_channel,
     instance_bundle::InstanceBundle,
     localization::LineId,
-    shared_variables::{DEFAULT_LANGUAGE, DEFAULT_PREFIX},
+    shared_variables::{
+        DEFAULT_LANGUAGE, DEFAULT_PREFIX, TWITCH_HELIX_API_URL, TWITCH_OAUTH2_API_URL,
+    },
 };
 
 use common::{
     establish_connection,
-    models::{Channel, NewChannel, NewChannelPreference},
-    schema::{channel_preferences::dsl as chp, channels::dsl as ch},
+    models::{Channel, NewChannel, NewChannelPreference, Session},
+    schema::{channel_preferences::dsl as chp, channels::dsl as ch, sessions::dsl as se},
 };
 
 pub struct JoinCommand;
@@ -29,6 +37,36 @@ struct StatsAPIJoinBody {
     pub twitch_id: u32,
 }
 
+#[derive(Deserialize)]
+struct ModeratedChannelResponse {
+    pub data: Vec<ModeratedChannel>,
+    #[serde(default)]
+    pub pagination: ModeratedChannelPagination,
+}
+
+#[derive(Deserialize, Default)]
+struct ModeratedChannelPagination {
+    pub cursor: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ModeratedChannel {
+    pub broadcaster_id: String,
+}
+
+#[derive(Deserialize)]
+struct RefreshedSessionResponse {
+    pub access_token: String,
+    pub refresh_token: String,
+    pub expires_in: i64,
+}
+
+enum ModeratorStatus {
+    Moderator,
+    NotModerator,
+    SessionExpired,
+}
+
 #[async_trait]
 impl Command for JoinCommand {
     fn get_name(&self) -> String {
@@ -40,63 +78,140 @@ impl Command for JoinCommand {
         instance_bundle: &InstanceBundle,
         request: Request,
     ) -> Result<Response, ResponseError> {
-        if request.message.is_some() {
-            return Ok(Response::Single(
-                instance_bundle.localizator.formatted_text_by_request(
-                    &request,
-                    LineId::JoinOtherchatroom,
-                    Vec::<String>::new(),
-                ),
-            ));
-        }
-
         let conn = &mut establish_connection();
 
+        let (alias_id, alias_name) = match request.message.clone() {
+            Some(message) => {
+                let login = message
+                    .split(' ')
+                    .next()
+                    .unwrap_or_default()
+                    .trim_start_matches('@')
+                    .to_lowercase();
+
+                let user = match instance_bundle
+                    .twitch_api_client
+                    .get_user_from_login(
+                        NicknameRef::from_str(login.as_str()),
+                        &*instance_bundle.twitch_api_token,
+                    )
+                    .await
+                {
+                    Ok(Some(v)) => v,
+                    _ => return Err(ResponseError::NotFound(login)),
+                };
+
+                let alias_id = user.id.take().parse::<i32>().unwrap();
+
+                if alias_id != request.sender.alias_id && !request.sender.is_admin {
+                    let line_id = match self
+                        .is_moderator_of(conn, instance_bundle, &request, alias_id)
+                        .await?
+                    {
+                        ModeratorStatus::Moderator => None,
+                        ModeratorStatus::NotModerator => Some(LineId::JoinOtherchatroom),
+                        ModeratorStatus::SessionExpired => Some(LineId::JoinSessionExpired),
+                    };
+
+                    if let Some(line_id) = line_id {
+                        return Ok(Response::Single(
+                            instance_bundle.localizator.formatted_text_by_request(
+                                &request,
+                                line_id,
+                                Vec::<String>::new(),
+                            ),
+                        ));
+                    }
+                }
+
+                (alias_id, user.login.take())
+            }
+            None => (request.sender.alias_id, request.sender.alias_name.clone()),
+        };
+
+        let is_other_channel = alias_id != request.sender.alias_id;
+
         let channel_query = ch::channels
-            .filter(ch::alias_id.eq(request.sender.alias_id))
+            .filter(ch::alias_id.eq(alias_id))
             .get_result::<Channel>(conn);
 
-        if channel_query.is_ok() {
-            return Ok(Response::Single(
+        if let Ok(channel) = channel_query {
+            if channel.opt_outed_at.is_some() {
+                // re-joining clears the opt-out and cancels the scheduled purge
+                update(ch::channels.find(&channel.id))
+                    .set((
+                        ch::opt_outed_at.eq(None::<NaiveDateTime>),
+                        ch::purge_at.eq(None::<NaiveDateTime>),
+                    ))
+                    .execute(conn)
+                    .expect("Failed to opt in the channel");
+
+                join_channel(conn, instance_bundle, &channel).await;
+
+                return Ok(Response::Single(if is_other_channel {
+                    instance_bundle.localizator.formatted_text_by_request(
+                        &request,
+                        LineId::CommandJoinResponseOther,
+                        vec![alias_name],
+                    )
+                } else {
+                    instance_bundle.localizator.formatted_text_by_request(
+                        &request,
+                        LineId::CommandJoinResponse,
+                        Vec::<String>::new(),
+                    )
+                }));
+            }
+
+            return Ok(Response::Single(if is_other_channel {
+                instance_bundle.localizator.formatted_text_by_request(
+                    &request,
+                    LineId::CommandJoinAlreadyJoinedOther,
+                    vec![alias_name],
+                )
+            } else {
                 instance_bundle.localizator.formatted_text_by_request(
                     &request,
                     LineId::CommandJoinAlreadyJoined,
                     Vec::<String>::new(),
-                ),
-            ));
+                )
+            }));
         }
 
         insert_into(ch::channels)
             .values([NewChannel {
-                alias_id: request.sender.alias_id,
-                alias_name: request.sender.alias_name.clone(),
+                alias_id,
+                alias_name: alias_name.clone(),
             }])
             .execute(conn)
             .expect("Failed to insert a new channel");
 
         let new_channel = ch::channels
-            .filter(ch::alias_id.eq(request.sender.alias_id))
+            .filter(ch::alias_id.eq(alias_id))
             .first::<Channel>(conn)
             .expect("Failed to get users");
 
         insert_into(chp::channel_preferences)
             .values([NewChannelPreference {
                 channel_id: new_channel.id,
-                prefix: DEFAULT_PREFIX.to_string(),
-                language: DEFAULT_LANGUAGE.to_string(),
+                prefix: match env::var("BOT_DEFAULT_PREFIX") {
+                    Ok(v) => v,
+                    Err(_) => DEFAULT_PREFIX.to_string(),
+                },
+                language: match env::var("BOT_DEFAULT_LANGUAGE") {
+                    Ok(v) => v,
+                    Err(_) => DEFAULT_LANGUAGE.to_string(),
+                },
             }])
             .execute(conn)
             .expect("Failed to insert preferences for a new channel");
 
-        instance_bundle
-            .twitch_irc_client
-            .join(request.sender.alias_name.clone())
-            .expect("Failed to join chat room");
+        join_channel(conn, instance_bundle, &new_channel).await;
 
         instance_bundle
             .twitch_irc_client
             .say(
-                request.sender.alias_name.clone(),
+                alias_name.clone(),
                 instance_bundle.localizator.formatted_text_by_request(
                     &request,
                     LineId::CommandJoinResponseInChat,
@@ -111,7 +226,7 @@ impl Command for JoinCommand {
 
             let client = reqwest::Client::new();
             let mut req = client.post(url).json(&StatsAPIJoinBody {
-                twitch_id: request.sender.alias_id as u32,
+                twitch_id: alias_id as u32,
             });
 
             if let Ok(credentials) = env::var("STATS_API_PASSWORD") {
@@ -136,20 +251,162 @@ impl Command for JoinCommand {
 
             if let Ok(res) = req.send().await {
                 if res.status() != reqwest::StatusCode::OK {
-                    warn!(
-                        "Failed to channel alias ID {} to join Stats API!",
-                        request.sender.alias_id
-                    );
+                    warn!("Failed to channel alias ID {} to join Stats API!", alias_id);
                 }
             }
         }
 
-        Ok(Response::Single(
+        Ok(Response::Single(if is_other_channel {
+            instance_bundle.localizator.formatted_text_by_request(
+                &request,
+                LineId::CommandJoinResponseOther,
+                vec![alias_name],
+            )
+        } else {
             instance_bundle.localizator.formatted_text_by_request(
                 &request,
                 LineId::CommandJoinResponse,
                 Vec::<String>::new(),
-            ),
-        ))
+            )
+        }))
+    }
+}
+
+impl JoinCommand {
+    /// Checks with the sender's Twitch session whether they moderate the channel.
+    /// The session is refreshed first if it has expired.
+    async fn is_moderator_of(
+        &self,
+        conn: &mut PgConnection,
+        instance_bundle: &InstanceBundle,
+        request: &Request,
+        broadcaster_id: i32,
+    ) -> Result<ModeratorStatus, ResponseError> {
+        let session = match se::sessions
+            .filter(se::user_id.eq(&request.sender.id))
+            .filter(se::scopes.contains(vec!["user:read:moderated_channels"]))
+            .order(se::expires_at.desc())
+            .first::<Session>(conn)
+        {
+            Ok(v) => v,
+            Err(_) => return Ok(ModeratorStatus::NotModerator),
+        };
+
+        let client_id = match env::var("BOT_CLIENT_ID") {
+            Ok(v) => v,
+            Err(_) => return Err(ResponseError::SomethingWentWrong),
+        };
+
+        let client = instance_bundle.twitch_api_client.get_client();
+
+        let access_token = if session.expires_at <= Utc::now().naive_utc() {
+            match self
+                .refresh_session(conn, client, &session, client_id.as_str())
+                .await
+            {
+                Some(v) => v,
+                None => return Ok(ModeratorStatus::SessionExpired),
+            }
+        } else {
+            session.access_token
+        };
+
+        let broadcaster_id = broadcaster_id.to_string();
+        let mut cursor: Option<String> = None;
+
+        // since HelixClient doesn't support the GetModeratedChannels endpoint,
+        // the request is made manually with the client the Helix requests are sent with
+        loop {
+            let mut url = format!(
+                "{}/moderation/channels?user_id={}&first=100",
+                TWITCH_HELIX_API_URL, request.sender.alias_id
+            );
+
+            if let Some(cursor) = &cursor {
+                url.push_str(format!("&after={}", cursor).as_str());
+            }
+
+            let response = match client
+                .get(url)
+                .bearer_auth(&access_token)
+                .header("Client-Id", client_id.as_str())
+                .send()
+                .await
+            {
+                Ok(v) => v,
+                Err(e) => return Err(ResponseError::ExternalAPIError(0, Some(e.to_string()))),
+            };
+
+            // the user may have revoked the access of the bot
+            if response.status() == reqwest::StatusCode::UNAUTHORIZED {
+                return Ok(ModeratorStatus::SessionExpired);
+            }
+
+            if response.status() != reqwest::StatusCode::OK {
+                return Err(ResponseError::ExternalAPIError(
+                    response.status().as_u16() as u32,
+                    None,
+                ));
+            }
+
+            let json = match response.json::<ModeratedChannelResponse>().await {
+                Ok(v) => v,
+                Err(e) => return Err(ResponseError::ExternalAPIError(0, Some(e.to_string()))),
+            };
+
+            if json
+                .data
+                .iter()
+                .any(|x| x.broadcaster_id.eq(&broadcaster_id))
+            {
+                return Ok(ModeratorStatus::Moderator);
+            }
+
+            match json.pagination.cursor {
+                Some(v) => cursor = Some(v),
+                None => return Ok(ModeratorStatus::NotModerator),
+            }
+        }
+    }
+
+    /// Gets a new access token for the session and saves it.
+    /// Returns nothing if the session can't be refreshed.
+    async fn refresh_session(
+        &self,
+        conn: &mut PgConnection,
+        client: &reqwest::Client,
+        session: &Session,
+        client_id: &str,
+    ) -> Option<String> {
+        let client_secret = env::var("BOT_CLIENT_SECRET").ok()?;
+
+        let response = client
+            .post(format!("{}/token", TWITCH_OAUTH2_API_URL))
+            .form(&[
+                ("client_id", client_id),
+                ("client_secret", client_secret.as_str()),
+                ("grant_type", "refresh_token"),
+                ("refresh_token", session.refresh_token.as_str()),
+            ])
+            .send()
+            .await
+            .ok()?;
+
+        if response.status() != reqwest::StatusCode::OK {
+            return None;
+        }
+
+        let json = response.json::<RefreshedSessionResponse>().await.ok()?;
+
+        update(se::sessions.find(&session.id))
+            .set((
+                se::access_token.eq(&json.access_token),
+                se::refresh_token.eq(&json.refresh_token),
+                se::expires_at.eq(Utc::now().naive_utc() + Duration::seconds(json.expires_in)),
+            ))
+            .execute(conn)
+            .ok()?;
+
+        Some(json.access_token)
     }
 }
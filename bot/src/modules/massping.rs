@@ -1,8 +1,8 @@
 use async_trait::async_trait;
 use eyre::Result;
-use twitch_api::helix::chat::GetChattersRequest;
 
 use crate::{
+    chatters::get_massping_chatters,
     commands::{
         request::Request,
         response::{Response, ResponseError},
@@ -10,9 +10,11 @@ use crate::{
     },
     instance_bundle::InstanceBundle,
     localization::LineId,
+    shared_variables::{ACTIVE_CHATTERS_DEFAULT_WINDOW_SEC, ACTIVE_CHATTERS_MAX_WINDOW_SEC},
+    utils::parse_duration,
 };
 
-use common::models::LevelOfRights;
+use common::{establish_connection, models::LevelOfRights};
 
 pub struct MasspingCommand;
 
@@ -26,27 +28,29 @@ impl Command for MasspingCommand {
         LevelOfRights::Moderator
     }
 
+    fn get_options(&self) -> Vec<String> {
+        vec!["active".to_string()]
+    }
+
     async fn execute(
         &self,
         instance_bundle: &InstanceBundle,
         request: Request,
     ) -> Result<Response, ResponseError> {
-        let twitch_request = GetChattersRequest::new(
-            request.channel.alias_id.to_string(),
-            instance_bundle.twitch_api_token.user_id.clone(),
-        );
-
-        let chatters = match instance_bundle
-            .twitch_api_client
-            .req_get(twitch_request, &*instance_bundle.twitch_api_token.clone())
-            .await
-        {
-            Ok(response) => response.data,
-            Err(_) => {
-                return Err(ResponseError::InsufficientRights);
-            }
+        let active_window_sec = match request.options.get("active") {
+            Some(v) if v.is_empty() => Some(ACTIVE_CHATTERS_DEFAULT_WINDOW_SEC),
+            Some(v) => match parse_duration(v) {
+                Some(sec) if sec > 0 && sec <= ACTIVE_CHATTERS_MAX_WINDOW_SEC => Some(sec),
+                _ => return Err(ResponseError::IncorrectArgument(v.clone())),
+            },
+            None => None,
         };
 
+        let conn = &mut establish_connection();
+        let chatters =
+            get_massping_chatters(conn, instance_bundle, &request.channel, active_window_sec)
+                .await?;
+
         let message = request.message.clone().unwrap_or_default();
         let mut lines: Vec<String> = vec!["".to_string()];
         let mut index = 0;
@@ -58,9 +62,7 @@ impl Command for MasspingCommand {
         );
 
         for chatter in chatters {
-            if line.len() + format!("{}@{} ", lines.get(index).unwrap(), chatter.user_login).len()
-                >= 500
-            {
+            if line.len() + format!("{}@{} ", lines.get(index).unwrap(), chatter).len() >= 500 {
                 index += 1;
             }
 
@@ -73,7 +75,7 @@ impl Command for MasspingCommand {
                 None => "".to_string(),
             };
 
-            lines.insert(index, format!("{}@{} ", line, chatter.user_login));
+            lines.insert(index, format!("{}@{} ", line, chatter));
         }
 
         Ok(Response::Multiple(
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use async_trait::async_trait;
 use eyre::Result;
 
@@ -7,9 +9,10 @@ use crate::{
         response::{Response, ResponseError},
         Command, CommandArgument,
     },
+    http::{HttpError, RequestOptions},
     instance_bundle::InstanceBundle,
     models::mcsrv::ServerData,
-    shared_variables::MCSRV_API_URL,
+    shared_variables::{MCSRV_API_URL, MCSRV_CACHE_TTL_SEC},
 };
 
 pub struct MinecraftServerCommand;
@@ -22,14 +25,23 @@ impl Command for MinecraftServerCommand {
 
     async fn execute(
         &self,
-        _instance_bundle: &InstanceBundle,
+        instance_bundle: &InstanceBundle,
         request: Request,
     ) -> Result<Response, ResponseError> {
         if let Some(message) = request.message.clone() {
             let url = format!("{}/{}", MCSRV_API_URL, message);
 
-            if let Ok(response) = reqwest::get(url).await {
-                if let Ok(data) = response.json::<ServerData>().await {
+            let options = RequestOptions {
+                cache_ttl: Some(Duration::from_secs(MCSRV_CACHE_TTL_SEC)),
+                ..Default::default()
+            };
+
+            match instance_bundle
+                .http_client
+                .get_json::<ServerData>(url.as_str(), &options)
+                .await
+            {
+                Ok(data) => {
                     let ban_emoji = "⛔";
                     let ok_emoji = "✅";
                     let mut response = format!(
@@ -59,11 +71,10 @@ impl Command for MinecraftServerCommand {
                     }
 
                     return Ok(Response::Single(response));
-                } else {
-                    return Err(ResponseError::SomethingWentWrong);
                 }
-            } else {
-                return Err(ResponseError::NotFound(message));
+                // an invalid address makes the request itself fail
+                Err(HttpError::Request(_)) => return Err(ResponseError::NotFound(message)),
+                Err(e) => return Err(e.into()),
             }
         }
         Err(ResponseError::NotEnoughArguments(CommandArgument::Target))
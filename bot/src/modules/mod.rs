@@ -1,16 +1,25 @@
+pub mod admin;
 pub mod custom_command;
 pub mod ecount;
+pub mod ediff;
+pub mod ehistory;
+pub mod emote;
 pub mod esim;
 pub mod etop;
 pub mod event;
+pub mod ewho;
+pub mod filter;
 pub mod help;
 pub mod holiday;
 pub mod join;
 pub mod massping;
 pub mod mcsrv;
 pub mod notify;
+pub mod part;
 pub mod ping;
+pub mod rights;
 pub mod settings;
+pub mod seventv;
 pub mod spam;
 pub mod timer;
 pub mod userid;
@@ -1,7 +1,11 @@
-use std::str::FromStr;
+use std::{collections::HashMap, str::FromStr};
 
 use async_trait::async_trait;
-use diesel::{delete, insert_into, BelongingToDsl, ExpressionMethods, QueryDsl, RunQueryDsl};
+use chrono_tz::Tz;
+use diesel::{
+    delete, insert_into, update, BelongingToDsl, ExpressionMethods, PgConnection, QueryDsl,
+    RunQueryDsl,
+};
 use eyre::Result;
 use twitch_api::{
     helix::users::GetUsersRequest,
@@ -16,12 +20,19 @@ use crate::{
     },
     instance_bundle::InstanceBundle,
     localization::LineId,
+    shared_variables::NOTIFY_LIST_PAGE_SIZE,
 };
 
 use common::{
     establish_connection,
-    models::{Event, EventSubscription, EventType, NewEventSubscription},
-    schema::{event_subscriptions::dsl as evs, events::dsl as ev},
+    models::{
+        Channel, Event, EventSubscription, EventType, LevelOfRights, NewEventSubscription,
+        NewUserPreference, NotificationDelivery, UserPreference,
+    },
+    schema::{
+        channels::dsl as ch, event_subscriptions::dsl as evs, events::dsl as ev, rights::dsl as ri,
+        user_preferences::dsl as up,
+    },
 };
 
 pub struct NotifyCommand;
@@ -32,12 +43,21 @@ impl Command for NotifyCommand {
         "notify".to_string()
     }
 
+    fn get_options(&self) -> Vec<String> {
+        vec!["from".to_string(), "to".to_string()]
+    }
+
     fn get_subcommands(&self) -> Vec<String> {
         vec![
             "sub".to_string(),
             "unsub".to_string(),
             "subs".to_string(),
             "list".to_string(),
+            "mode".to_string(),
+            "tz".to_string(),
+            "quiet".to_string(),
+            "mute".to_string(),
+            "massping".to_string(),
         ]
     }
 
@@ -57,21 +77,130 @@ impl Command for NotifyCommand {
 
         let conn = &mut establish_connection();
         match subcommand_id.as_str() {
+            "mode" | "tz" | "quiet" | "mute" | "massping" => {
+                let preference = self.get_user_preference(conn, &request);
+
+                let (line_id, parameters) = match (subcommand_id.as_str(), request.message.clone())
+                {
+                    ("mute", _) => {
+                        update(up::user_preferences.find(&preference.id))
+                            .set(up::is_muted.eq(!preference.is_muted))
+                            .execute(conn)
+                            .expect("Failed to update the user preference");
+
+                        (
+                            if preference.is_muted {
+                                LineId::NotifyUnmuted
+                            } else {
+                                LineId::NotifyMuted
+                            },
+                            Vec::new(),
+                        )
+                    }
+                    ("massping", _) => {
+                        update(up::user_preferences.find(&preference.id))
+                            .set(up::is_massping_opted_out.eq(!preference.is_massping_opted_out))
+                            .execute(conn)
+                            .expect("Failed to update the user preference");
+
+                        (
+                            if preference.is_massping_opted_out {
+                                LineId::NotifyMasspingOptIn
+                            } else {
+                                LineId::NotifyMasspingOptOut
+                            },
+                            Vec::new(),
+                        )
+                    }
+                    (_, None) => {
+                        return Err(ResponseError::NotEnoughArguments(CommandArgument::Value))
+                    }
+                    ("mode", Some(message)) => {
+                        let delivery = match NotificationDelivery::from_str(message.as_str()) {
+                            Ok(v) => v,
+                            Err(_) => return Err(ResponseError::IncorrectArgument(message)),
+                        };
+
+                        update(up::user_preferences.find(&preference.id))
+                            .set(up::notification_delivery.eq(&delivery))
+                            .execute(conn)
+                            .expect("Failed to update the user preference");
+
+                        (LineId::NotifyMode, vec![delivery.to_string()])
+                    }
+                    ("tz", Some(message)) => {
+                        let time_zone = match Tz::from_str_insensitive(message.as_str()) {
+                            Ok(v) => v,
+                            Err(_) => return Err(ResponseError::IncorrectArgument(message)),
+                        };
+
+                        update(up::user_preferences.find(&preference.id))
+                            .set(up::time_zone.eq(time_zone.name()))
+                            .execute(conn)
+                            .expect("Failed to update the user preference");
+
+                        (LineId::NotifyTimezone, vec![time_zone.name().to_string()])
+                    }
+                    ("quiet", Some(message)) if message.eq("off") => {
+                        update(up::user_preferences.find(&preference.id))
+                            .set((
+                                up::quiet_hours_start.eq(None::<i32>),
+                                up::quiet_hours_end.eq(None::<i32>),
+                            ))
+                            .execute(conn)
+                            .expect("Failed to update the user preference");
+
+                        (LineId::NotifyQuietHoursOff, Vec::new())
+                    }
+                    ("quiet", Some(message)) => {
+                        let hours = message
+                            .split('-')
+                            .map(|x| x.parse::<i32>())
+                            .collect::<Vec<Result<i32, _>>>();
+
+                        let (start, end) = match (hours.first(), hours.get(1), hours.len()) {
+                            (Some(Ok(start)), Some(Ok(end)), 2)
+                                if (0..24).contains(start) && (0..24).contains(end) =>
+                            {
+                                (*start, *end)
+                            }
+                            _ => return Err(ResponseError::IncorrectArgument(message)),
+                        };
+
+                        update(up::user_preferences.find(&preference.id))
+                            .set((up::quiet_hours_start.eq(start), up::quiet_hours_end.eq(end)))
+                            .execute(conn)
+                            .expect("Failed to update the user preference");
+
+                        (
+                            LineId::NotifyQuietHours,
+                            vec![
+                                start.to_string(),
+                                end.to_string(),
+                                preference.time_zone.clone(),
+                            ],
+                        )
+                    }
+                    _ => return Err(ResponseError::SomethingWentWrong),
+                };
+
+                return Ok(Response::Single(
+                    instance_bundle
+                        .localizator
+                        .formatted_text_by_request(&request, line_id, parameters),
+                ));
+            }
             "subs" => {
                 let subs: Vec<EventSubscription> = EventSubscription::belonging_to(&request.sender)
                     .get_results::<EventSubscription>(conn)
                     .expect("Failed to get event subscriptions");
 
                 let events: Vec<Event> = ev::events
-                    .filter(ev::channel_id.eq(&request.channel.id))
+                    .filter(ev::id.eq_any(subs.iter().map(|x| x.event_id).collect::<Vec<i32>>()))
+                    .order(ev::id.asc())
                     .get_results::<Event>(conn)
                     .expect("Failed to get events");
 
-                let events: Vec<&Event> = events
-                    .iter()
-                    .filter(|x| subs.iter().any(|y| x.id == y.event_id))
-                    .collect::<Vec<&Event>>();
-
                 if events.is_empty() {
                     return Ok(Response::Single(
                         instance_bundle.localizator.formatted_text_by_request(
@@ -82,51 +211,9 @@ impl Command for NotifyCommand {
                     ));
                 }
 
-                let target_ids: Vec<UserId> = events
-                    .iter()
-                    .flat_map(|x| x.target_alias_id)
-                    .map(|x| UserId::new(x.to_string()))
-                    .collect::<Vec<UserId>>();
-
-                let target_ids = target_ids
-                    .iter()
-                    .map(|x| x.as_ref())
-                    .collect::<Vec<&UserIdRef>>();
-
-                let helix_request = GetUsersRequest::ids(target_ids.as_slice());
-
-                let mut t_subs: Vec<String> = Vec::new();
-
-                if let Ok(helix_response) = instance_bundle
-                    .twitch_api_client
-                    .req_get(helix_request, &*instance_bundle.twitch_api_token)
-                    .await
-                {
-                    let users = helix_response.data;
-
-                    for user in users {
-                        let id = user.id.take().parse::<i32>().unwrap();
-                        if let Some(e) = events
-                            .iter()
-                            .filter(|x| x.target_alias_id.is_some())
-                            .find(|x| x.target_alias_id.unwrap() == id)
-                        {
-                            t_subs.push(format!(
-                                "{}:{}",
-                                user.login.take(),
-                                e.event_type.to_string()
-                            ));
-                        }
-                    }
-                }
-
-                for event in events.iter().filter(|x| x.custom_alias_id.is_some()) {
-                    t_subs.push(format!(
-                        "{}:{} *",
-                        event.custom_alias_id.clone().unwrap(),
-                        event.event_type.to_string(),
-                    ));
-                }
+                let t_subs = self
+                    .format_event_names(instance_bundle, conn, &request, &events)
+                    .await;
 
                 return Ok(Response::Single(
                     instance_bundle.localizator.formatted_text_by_request(
@@ -136,10 +223,24 @@ impl Command for NotifyCommand {
                     ),
                 ));
             }
-            // duplicated code bruh
             "list" => {
-                let events: Vec<Event> = ev::events
-                    .filter(ev::channel_id.eq(&request.channel.id))
+                let page = match request.message.clone() {
+                    Some(v) => match v.parse::<usize>() {
+                        Ok(v) if v > 0 => v,
+                        _ => return Err(ResponseError::IncorrectArgument(v)),
+                    },
+                    None => 1,
+                };
+
+                let channel_ids: Vec<i32> = ch::channels
+                    .filter(ch::opt_outed_at.is_null())
+                    .select(ch::id)
+                    .get_results::<i32>(conn)
+                    .expect("Failed to get channels");
+
+                let mut events: Vec<Event> = ev::events
+                    .filter(ev::channel_id.eq_any(channel_ids))
+                    .order((ev::channel_id.asc(), ev::id.asc()))
                     .get_results::<Event>(conn)
                     .expect("Failed to get events");
 
@@ -153,57 +254,30 @@ impl Command for NotifyCommand {
                     ));
                 }
 
-                let target_ids: Vec<UserId> = events
-                    .iter()
-                    .flat_map(|x| x.target_alias_id)
-                    .map(|x| UserId::new(x.to_string()))
-                    .collect::<Vec<UserId>>();
-
-                let target_ids = target_ids
-                    .iter()
-                    .map(|x| x.as_ref())
-                    .collect::<Vec<&UserIdRef>>();
-
-                let helix_request = GetUsersRequest::ids(target_ids.as_slice());
+                // events of the current chat room go first
+                events.sort_by_key(|x| x.channel_id != request.channel.id);
 
-                let mut t_subs: Vec<String> = Vec::new();
+                let page_count = events.len().div_ceil(NOTIFY_LIST_PAGE_SIZE);
 
-                if let Ok(helix_response) = instance_bundle
-                    .twitch_api_client
-                    .req_get(helix_request, &*instance_bundle.twitch_api_token)
-                    .await
-                {
-                    let users = helix_response.data;
-
-                    for user in users {
-                        let id = user.id.take().parse::<i32>().unwrap();
-                        if let Some(e) = events
-                            .iter()
-                            .filter(|x| x.target_alias_id.is_some())
-                            .find(|x| x.target_alias_id.unwrap() == id)
-                        {
-                            t_subs.push(format!(
-                                "{}:{}",
-                                user.login.take(),
-                                e.event_type.to_string()
-                            ));
-                        }
-                    }
+                if page > page_count {
+                    return Err(ResponseError::NotFound(page.to_string()));
                 }
 
-                for event in events.iter().filter(|x| x.custom_alias_id.is_some()) {
-                    t_subs.push(format!(
-                        "{}:{} *",
-                        event.custom_alias_id.clone().unwrap(),
-                        event.event_type.to_string(),
-                    ));
-                }
+                let events = events
+                    .into_iter()
+                    .skip((page - 1) * NOTIFY_LIST_PAGE_SIZE)
+                    .take(NOTIFY_LIST_PAGE_SIZE)
+                    .collect::<Vec<Event>>();
+
+                let t_subs = self
+                    .format_event_names(instance_bundle, conn, &request, &events)
+                    .await;
 
                 return Ok(Response::Single(
                     instance_bundle.localizator.formatted_text_by_request(
                         &request,
                         LineId::NotifyList,
-                        vec![t_subs.join(", ")],
+                        vec![t_subs.join(", "), page.to_string(), page_count.to_string()],
                     ),
                 ));
             }
@@ -255,7 +329,25 @@ impl Command for NotifyCommand {
             return Err(ResponseError::NotFound(target_name));
         }
 
-        let events = Event::belonging_to(&request.channel)
+        let source_channel = match request.options.get("from") {
+            Some(name) => self.find_channel(conn, name)?,
+            None => request.channel.clone(),
+        };
+
+        let delivery_channel_id = match request.options.get("to") {
+            Some(name) => {
+                let channel = self.find_channel(conn, name)?;
+
+                if !self.can_deliver_to(conn, &request, &channel) {
+                    return Err(ResponseError::InsufficientRights);
+                }
+
+                Some(channel.id)
+            }
+            None => None,
+        };
+
+        let events = Event::belonging_to(&source_channel)
             .filter(ev::event_type.eq(&event_type))
             .load::<Event>(conn)
             .expect("Failed to load events");
@@ -279,6 +371,7 @@ impl Command for NotifyCommand {
                     .values([NewEventSubscription {
                         event_id: e.id,
                         user_id: request.sender.id,
+                        channel_id: delivery_channel_id,
                     }])
                     .execute(conn)
                     .expect("Failed to create a new event subscription");
@@ -330,3 +423,116 @@ impl Command for NotifyCommand {
         Ok(Response::Single(response))
     }
 }
+
+impl NotifyCommand {
+    fn find_channel(&self, conn: &mut PgConnection, name: &str) -> Result<Channel, ResponseError> {
+        let name = name.trim_start_matches(['#', '@']).to_lowercase();
+
+        if name.is_empty() {
+            return Err(ResponseError::NotEnoughArguments(CommandArgument::Target));
+        }
+
+        ch::channels
+            .filter(ch::alias_name.eq(&name))
+            .filter(ch::opt_outed_at.is_null())
+            .first::<Channel>(conn)
+            .map_err(|_| ResponseError::NotFound(name))
+    }
+
+    /// Checks whether the sender may have their mentions delivered to the chat room.
+    /// Other chat rooms have to be moderated by the sender, so nobody gets pinged there without consent.
+    fn can_deliver_to(
+        &self,
+        conn: &mut PgConnection,
+        request: &Request,
+        channel: &Channel,
+    ) -> bool {
+        if channel.id == request.channel.id
+            || channel.alias_id == request.sender.alias_id
+            || request.sender.is_admin
+        {
+            return true;
+        }
+
+        ri::rights
+            .filter(ri::user_id.eq(&request.sender.id))
+            .filter(ri::channel_id.eq(&channel.id))
+            .select(ri::level)
+            .first::<LevelOfRights>(conn)
+            .is_ok_and(|x| x >= LevelOfRights::Moderator)
+    }
+
+    /// Formats events as "name:type", marking custom events with "*"
+    /// and events from other chat rooms with the chat room name.
+    async fn format_event_names(
+        &self,
+        instance_bundle: &InstanceBundle,
+        conn: &mut PgConnection,
+        request: &Request,
+        events: &[Event],
+    ) -> Vec<String> {
+        let channels: Vec<Channel> = ch::channels
+            .filter(ch::id.eq_any(events.iter().map(|x| x.channel_id).collect::<Vec<i32>>()))
+            .get_results::<Channel>(conn)
+            .expect("Failed to get channels");
+
+        let target_ids: Vec<UserId> = events
+            .iter()
+            .flat_map(|x| x.target_alias_id)
+            .map(|x| UserId::new(x.to_string()))
+            .collect::<Vec<UserId>>();
+
+        let mut logins: HashMap<i32, String> = HashMap::new();
+
+        for chunk in target_ids.chunks(100) {
+            let chunk = chunk
+                .iter()
+                .map(|x| x.as_ref())
+                .collect::<Vec<&UserIdRef>>();
+
+            let helix_request = GetUsersRequest::ids(chunk.as_slice());
+
+            if let Ok(helix_response) = instance_bundle
+                .twitch_api_client
+                .req_get(helix_request, &*instance_bundle.twitch_api_token)
+                .await
+            {
+                for user in helix_response.data {
+                    logins.insert(user.id.take().parse::<i32>().unwrap(), user.login.take());
+                }
+            }
+        }
+
+        events
+            .iter()
+            .filter_map(|x| {
+                let name = match (x.target_alias_id, x.custom_alias_id.clone()) {
+                    (Some(id), _) => format!("{}:{}", logins.get(&id)?, x.event_type.to_string()),
+                    (None, Some(name)) => format!("{}:{} *", name, x.event_type.to_string()),
+                    _ => return None,
+                };
+
+                match channels.iter().find(|y| y.id == x.channel_id) {
+                    Some(c) if c.id != request.channel.id => {
+                        Some(format!("{} (#{})", name, c.alias_name))
+                    }
+                    _ => Some(name),
+                }
+            })
+            .collect::<Vec<String>>()
+    }
+
+    fn get_user_preference(&self, conn: &mut PgConnection, request: &Request) -> UserPreference {
+        UserPreference::belonging_to(&request.sender)
+            .first::<UserPreference>(conn)
+            .unwrap_or_else(|_| {
+                insert_into(up::user_preferences)
+                    .values([NewUserPreference {
+                        user_id: request.sender.id,
+                        notification_delivery: NotificationDelivery::Chat,
+                    }])
+                    .get_result::<UserPreference>(conn)
+                    .expect("Failed to create preferences for user")
+            })
+    }
+}
@@ -0,0 +1,76 @@
+use async_trait::async_trait;
+use chrono::{Duration, NaiveDateTime, Utc};
+use diesel::{update, ExpressionMethods, QueryDsl, RunQueryDsl};
+
+use crate::{
+    commands::{
+        request::Request,
+        response::{Response, ResponseError},
+        Command,
+    },
+    handlers::part_channel,
+    instance_bundle::InstanceBundle,
+    localization::LineId,
+    utils::format_timestamp,
+};
+
+use common::{
+    establish_connection, models::LevelOfRights, schema::channels::dsl as ch,
+    CHANNEL_PURGE_GRACE_PERIOD_SEC,
+};
+
+pub struct PartCommand;
+
+#[async_trait]
+impl Command for PartCommand {
+    fn get_name(&self) -> String {
+        "part".to_string()
+    }
+
+    fn required_rights(&self) -> LevelOfRights {
+        LevelOfRights::Broadcaster
+    }
+
+    fn get_options(&self) -> Vec<String> {
+        vec!["purge".to_string()]
+    }
+
+    async fn execute(
+        &self,
+        instance_bundle: &InstanceBundle,
+        request: Request,
+    ) -> Result<Response, ResponseError> {
+        let conn = &mut establish_connection();
+        let now = Utc::now().naive_utc();
+        let is_purged = request.options.contains_key("purge");
+
+        update(ch::channels.find(&request.channel.id))
+            .set((
+                ch::opt_outed_at.eq(Some(now)),
+                ch::purge_at.eq(if is_purged {
+                    Some(now + Duration::seconds(CHANNEL_PURGE_GRACE_PERIOD_SEC))
+                } else {
+                    None::<NaiveDateTime>
+                }),
+            ))
+            .execute(conn)
+            .expect("Failed to opt out the channel");
+
+        // the response can still be sent after the chat room is parted
+        part_channel(conn, instance_bundle, &request.channel).await;
+
+        Ok(Response::Single(if is_purged {
+            instance_bundle.localizator.formatted_text_by_request(
+                &request,
+                LineId::CommandPartResponsePurge,
+                vec![format_timestamp(CHANNEL_PURGE_GRACE_PERIOD_SEC as u64)],
+            )
+        } else {
+            instance_bundle.localizator.formatted_text_by_request(
+                &request,
+                LineId::CommandPartResponse,
+                Vec::<String>::new(),
+            )
+        }))
+    }
+}
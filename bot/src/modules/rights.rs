@@ -0,0 +1,217 @@
+use std::str::FromStr;
+
+use async_trait::async_trait;
+use diesel::{
+    insert_into, update, ExpressionMethods, OptionalExtension, PgConnection, QueryDsl, RunQueryDsl,
+};
+use twitch_api::types::NicknameRef;
+
+use crate::{
+    commands::{
+        request::Request,
+        response::{Response, ResponseError},
+        Command, CommandArgument,
+    },
+    instance_bundle::InstanceBundle,
+    localization::LineId,
+};
+
+use common::{
+    establish_connection,
+    models::{LevelOfRights, NewRight, NewUser, Right, User},
+    schema::{rights::dsl as ri, users::dsl as us},
+};
+
+pub struct RightsCommand;
+
+#[async_trait]
+impl Command for RightsCommand {
+    fn get_name(&self) -> String {
+        "rights".to_string()
+    }
+
+    fn required_rights(&self) -> LevelOfRights {
+        LevelOfRights::Moderator
+    }
+
+    fn get_subcommands(&self) -> Vec<String> {
+        vec!["set".to_string(), "reset".to_string(), "get".to_string()]
+    }
+
+    async fn execute(
+        &self,
+        instance_bundle: &InstanceBundle,
+        request: Request,
+    ) -> Result<Response, ResponseError> {
+        let subcommand_id = match request.subcommand_id.clone() {
+            Some(v) => v,
+            None => {
+                return Err(ResponseError::NotEnoughArguments(
+                    CommandArgument::Subcommand,
+                ))
+            }
+        };
+
+        let message = match request.message.clone() {
+            Some(v) => v,
+            None => return Err(ResponseError::NotEnoughArguments(CommandArgument::Target)),
+        };
+
+        let mut message_split = message.split(' ');
+
+        let login = message_split
+            .next()
+            .unwrap_or_default()
+            .trim_start_matches('@')
+            .to_lowercase();
+
+        let conn = &mut establish_connection();
+
+        let user = self.get_user(conn, instance_bundle, &login).await?;
+
+        let rights = ri::rights
+            .filter(ri::user_id.eq(&user.id))
+            .filter(ri::channel_id.eq(&request.channel.id))
+            .first::<Right>(conn)
+            .optional()
+            .expect("Failed to get rights");
+
+        let level = match &rights {
+            Some(v) => v.level.clone(),
+            None => LevelOfRights::User,
+        };
+
+        if subcommand_id.eq("get") {
+            return Ok(Response::Single(
+                instance_bundle.localizator.formatted_text_by_request(
+                    &request,
+                    if rights.map(|x| x.is_fixed).unwrap_or(false) {
+                        LineId::CommandRightsInfoFixed
+                    } else {
+                        LineId::CommandRightsInfo
+                    },
+                    vec![user.alias_name, level.to_string()],
+                ),
+            ));
+        }
+
+        // nobody can manage the broadcaster, admins and users of the same or higher level
+        if user.alias_id == request.channel.alias_id
+            || user.is_admin
+            || level >= request.rights.level
+        {
+            return Err(ResponseError::InsufficientRights);
+        }
+
+        let response = match subcommand_id.as_str() {
+            "set" => {
+                let new_level = match message_split.next() {
+                    Some(v) => match LevelOfRights::from_str(v) {
+                        Ok(v) => v,
+                        Err(_) => return Err(ResponseError::IncorrectArgument(v.to_string())),
+                    },
+                    None => return Err(ResponseError::NotEnoughArguments(CommandArgument::Value)),
+                };
+
+                if new_level >= request.rights.level || new_level >= LevelOfRights::Broadcaster {
+                    return Err(ResponseError::InsufficientRights);
+                }
+
+                match rights {
+                    Some(v) => {
+                        update(ri::rights.find(&v.id))
+                            .set((ri::level.eq(&new_level), ri::is_fixed.eq(true)))
+                            .execute(conn)
+                            .expect("Failed to update rights");
+                    }
+                    None => {
+                        let v = insert_into(ri::rights)
+                            .values([NewRight {
+                                user_id: user.id,
+                                channel_id: request.channel.id,
+                                level: new_level.clone(),
+                            }])
+                            .get_result::<Right>(conn)
+                            .expect("Failed to insert new rights");
+
+                        update(ri::rights.find(&v.id))
+                            .set(ri::is_fixed.eq(true))
+                            .execute(conn)
+                            .expect("Failed to update rights");
+                    }
+                }
+
+                instance_bundle.localizator.formatted_text_by_request(
+                    &request,
+                    LineId::CommandRightsSet,
+                    vec![user.alias_name, new_level.to_string()],
+                )
+            }
+            "reset" => {
+                // the level is recalculated from badges on the user's next message
+                if let Some(v) = rights {
+                    update(ri::rights.find(&v.id))
+                        .set((ri::level.eq(LevelOfRights::User), ri::is_fixed.eq(false)))
+                        .execute(conn)
+                        .expect("Failed to update rights");
+                }
+
+                instance_bundle.localizator.formatted_text_by_request(
+                    &request,
+                    LineId::CommandRightsReset,
+                    vec![user.alias_name],
+                )
+            }
+            _ => return Err(ResponseError::SomethingWentWrong),
+        };
+
+        Ok(Response::Single(response))
+    }
+}
+
+impl RightsCommand {
+    /// Gets the user by login, creating them if they have never talked to the bot.
+    async fn get_user(
+        &self,
+        conn: &mut PgConnection,
+        instance_bundle: &InstanceBundle,
+        login: &str,
+    ) -> Result<User, ResponseError> {
+        if let Ok(user) = us::users
+            .filter(us::alias_name.eq(login))
+            .first::<User>(conn)
+        {
+            return Ok(user);
+        }
+
+        let twitch_user = match instance_bundle
+            .twitch_api_client
+            .get_user_from_login(
+                NicknameRef::from_str(login),
+                &*instance_bundle.twitch_api_token,
+            )
+            .await
+        {
+            Ok(Some(v)) => v,
+            _ => return Err(ResponseError::NotFound(login.to_string())),
+        };
+
+        let alias_id = twitch_user.id.take().parse::<i32>().unwrap();
+
+        // the user may have been renamed since they were stored
+        if let Ok(user) = us::users
+            .filter(us::alias_id.eq(alias_id))
+            .first::<User>(conn)
+        {
+            return Ok(user);
+        }
+
+        Ok(insert_into(us::users)
+            .values([NewUser {
+                alias_id,
+                alias_name: twitch_user.login.take(),
+            }])
+            .get_result::<User>(conn)
+            .expect("Failed to insert a new user"))
+    }
+}
@@ -37,6 +37,7 @@ impl Command for SettingsCommand {
             "locale".to_string(),
             "prefix".to_string(),
             "feature".to_string(),
+            "exclude".to_string(),
         ]
     }
 
@@ -66,7 +67,7 @@ impl Command for SettingsCommand {
             "locale" => {
                 let locales = instance_bundle.localizator.localization_names();
 
-                if !locales.contains(&&message) {
+                if !locales.contains(&message) {
                     return Err(ResponseError::NotFound(message));
                 }
 
@@ -135,6 +136,36 @@ impl Command for SettingsCommand {
                 }
                 Err(_) => return Err(ResponseError::IncorrectArgument(message)),
             },
+            "exclude" => {
+                let login = message.trim_start_matches('@').to_lowercase();
+                let mut exclusions = request.channel_preference.massping_exclusions.clone();
+
+                let is_removed = match exclusions.iter().position(|x| x == &login) {
+                    Some(i) => {
+                        exclusions.remove(i);
+                        true
+                    }
+                    None => {
+                        exclusions.push(login.clone());
+                        false
+                    }
+                };
+
+                update(chp::channel_preferences.find(&request.channel_preference.id))
+                    .set(chp::massping_exclusions.eq(&exclusions))
+                    .execute(conn)
+                    .expect("Failed to update the channel preference");
+
+                instance_bundle.localizator.formatted_text_by_request(
+                    &request,
+                    if is_removed {
+                        LineId::SettingsExcludeOff
+                    } else {
+                        LineId::SettingsExcludeOn
+                    },
+                    vec![login],
+                )
+            }
             _ => return Err(ResponseError::SomethingWentWrong),
         };
 
@@ -0,0 +1,235 @@
+use std::env;
+
+use async_trait::async_trait;
+use common::{establish_connection, models::LevelOfRights};
+use eyre::Result;
+use log::error;
+
+use crate::{
+    commands::{
+        request::Request,
+        response::{Response, ResponseError},
+        Command, CommandArgument,
+    },
+    emotes::{history::record_emote_changes, EmoteChange, EmoteProvider, ProviderEmote},
+    instance_bundle::InstanceBundle,
+    localization::LineId,
+    seventv::api::schema::ListItemAction,
+};
+
+pub struct SevenTVCommand;
+
+#[async_trait]
+impl Command for SevenTVCommand {
+    fn get_name(&self) -> String {
+        "7tv".to_string()
+    }
+
+    fn required_rights(&self) -> LevelOfRights {
+        LevelOfRights::Moderator
+    }
+
+    fn get_subcommands(&self) -> Vec<String> {
+        vec![
+            "add".to_string(),
+            "remove".to_string(),
+            "rename".to_string(),
+        ]
+    }
+
+    async fn execute(
+        &self,
+        instance_bundle: &InstanceBundle,
+        request: Request,
+    ) -> Result<Response, ResponseError> {
+        let token = match env::var("SEVENTV_TOKEN") {
+            Ok(v) => v,
+            Err(_) => {
+                error!(
+                    "Tried to run the !7tv command, but SEVENTV_TOKEN is not set in the .env file"
+                );
+                return Err(ResponseError::SomethingWentWrong);
+            }
+        };
+
+        let subcommand_id = match request.subcommand_id.clone() {
+            Some(v) => v,
+            None => {
+                return Err(ResponseError::NotEnoughArguments(
+                    CommandArgument::Subcommand,
+                ))
+            }
+        };
+
+        let message = request.message.clone().unwrap_or_default();
+        let mut message_split = message.split_ascii_whitespace();
+
+        let first_argument = match message_split.next() {
+            Some(v) => v.to_string(),
+            None => {
+                return Err(ResponseError::NotEnoughArguments(
+                    if subcommand_id.eq("add") {
+                        CommandArgument::Value
+                    } else {
+                        CommandArgument::Name
+                    },
+                ))
+            }
+        };
+
+        let api = &instance_bundle.seventv_api_client;
+
+        let emote_set = match api
+            .get_user_by_twitch_id(request.channel.alias_id.to_string())
+            .await
+        {
+            Some(v) => v.emote_set,
+            None => return Err(ResponseError::NotFound(request.channel.alias_name.clone())),
+        };
+
+        let provider_name = instance_bundle
+            .localizator
+            .get_literal_text(
+                request.channel_preference.language.as_str(),
+                LineId::Provider7TV,
+            )
+            .unwrap();
+
+        let (change, response) = match subcommand_id.as_str() {
+            "add" => {
+                // both the emote ID and the link to the emote page are accepted
+                let emote_id = first_argument
+                    .split('?')
+                    .next()
+                    .unwrap_or_default()
+                    .trim_end_matches('/')
+                    .rsplit('/')
+                    .next()
+                    .unwrap_or_default()
+                    .to_string();
+
+                let alias = message_split.next().map(|x| x.to_string());
+
+                let emotes = api
+                    .change_emote_in_set(
+                        token.as_str(),
+                        emote_set.id,
+                        ListItemAction::Add,
+                        emote_id.clone(),
+                        alias.clone(),
+                    )
+                    .await
+                    .map_err(|(code, message)| ResponseError::ExternalAPIError(code, message))?;
+
+                let name = match emotes.into_iter().find(|x| x.id.eq(&emote_id)) {
+                    Some(v) => v.name,
+                    None => alias.unwrap_or(emote_id.clone()),
+                };
+
+                (
+                    EmoteChange::Pushed(ProviderEmote {
+                        id: emote_id,
+                        name: name.clone(),
+                    }),
+                    instance_bundle.localizator.formatted_text_by_request(
+                        &request,
+                        LineId::EmotesPushed,
+                        vec![provider_name, request.sender.alias_name.clone(), name],
+                    ),
+                )
+            }
+            "remove" => {
+                let emote = match emote_set.emotes.iter().find(|x| x.name.eq(&first_argument)) {
+                    Some(v) => v,
+                    None => return Err(ResponseError::NotFound(first_argument)),
+                };
+
+                api.change_emote_in_set(
+                    token.as_str(),
+                    emote_set.id.clone(),
+                    ListItemAction::Remove,
+                    emote.id.clone(),
+                    None,
+                )
+                .await
+                .map_err(|(code, message)| ResponseError::ExternalAPIError(code, message))?;
+
+                (
+                    EmoteChange::Pulled(ProviderEmote {
+                        id: emote.id.clone(),
+                        name: emote.name.clone(),
+                    }),
+                    instance_bundle.localizator.formatted_text_by_request(
+                        &request,
+                        LineId::EmotesPulled,
+                        vec![
+                            provider_name,
+                            request.sender.alias_name.clone(),
+                            emote.name.clone(),
+                        ],
+                    ),
+                )
+            }
+            "rename" => {
+                let new_name = match message_split.next() {
+                    Some(v) => v.to_string(),
+                    None => return Err(ResponseError::NotEnoughArguments(CommandArgument::Name)),
+                };
+
+                let emote = match emote_set.emotes.iter().find(|x| x.name.eq(&first_argument)) {
+                    Some(v) => v,
+                    None => return Err(ResponseError::NotFound(first_argument)),
+                };
+
+                api.change_emote_in_set(
+                    token.as_str(),
+                    emote_set.id.clone(),
+                    ListItemAction::Update,
+                    emote.id.clone(),
+                    Some(new_name.clone()),
+                )
+                .await
+                .map_err(|(code, message)| ResponseError::ExternalAPIError(code, message))?;
+
+                (
+                    EmoteChange::Updated(
+                        emote.name.clone(),
+                        ProviderEmote {
+                            id: emote.id.clone(),
+                            name: new_name.clone(),
+                        },
+                    ),
+                    instance_bundle.localizator.formatted_text_by_request(
+                        &request,
+                        LineId::EmotesUpdated,
+                        vec![
+                            provider_name,
+                            request.sender.alias_name.clone(),
+                            emote.name.clone(),
+                            new_name,
+                        ],
+                    ),
+                )
+            }
+            _ => return Err(ResponseError::SomethingWentWrong),
+        };
+
+        // EventAPI dispatches of the bot's own changes are skipped, so the moderator is recorded here
+        let conn = &mut establish_connection();
+
+        record_emote_changes(
+            conn,
+            request.channel.alias_id,
+            &EmoteProvider::SevenTV,
+            Some(request.sender.alias_name.clone()),
+            &[change],
+        );
+
+        instance_bundle
+            .emote_details_cache
+            .invalidate(&EmoteProvider::SevenTV, request.channel.alias_id)
+            .await;
+
+        Ok(Response::Single(response))
+    }
+}
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use async_trait::async_trait;
 use eyre::Result;
 
@@ -8,6 +10,9 @@ use crate::{
         Command, CommandArgument,
     },
     instance_bundle::InstanceBundle,
+    localization::LineId,
+    shared_variables::SPAM_MAX_DELAY_SEC,
+    utils::parse_duration,
 };
 
 use common::models::LevelOfRights;
@@ -24,45 +29,143 @@ impl Command for SpamCommand {
         LevelOfRights::Moderator
     }
 
+    fn get_options(&self) -> Vec<String> {
+        vec![
+            "delay".to_string(),
+            "pyramid".to_string(),
+            "counter".to_string(),
+        ]
+    }
+
+    fn get_subcommands(&self) -> Vec<String> {
+        vec!["cancel".to_string()]
+    }
+
+    fn get_delay_free_subcommands(&self) -> Vec<String> {
+        vec!["cancel".to_string()]
+    }
+
     async fn execute(
         &self,
-        _instance_bundle: &InstanceBundle,
+        instance_bundle: &InstanceBundle,
         request: Request,
     ) -> Result<Response, ResponseError> {
-        if let Some(msg) = request.message {
-            let mut s = msg.split(' ').collect::<Vec<&str>>();
+        let mut spam_tasks = instance_bundle.spam_tasks.lock().await;
+
+        if let Some("cancel") = request.subcommand_id.as_deref() {
+            let line_id = match spam_tasks.remove(&request.channel.id) {
+                Some(task) if !task.is_finished() => {
+                    task.abort();
+                    LineId::CommandSpamCancelled
+                }
+                _ => LineId::CommandSpamNotRunning,
+            };
 
-            let count = if let Some(c) = s.first() {
-                if let Ok(c) = c.parse::<u32>() {
-                    s.remove(0);
+            return Ok(Response::Single(
+                instance_bundle.localizator.formatted_text_by_request(
+                    &request,
+                    line_id,
+                    Vec::<String>::new(),
+                ),
+            ));
+        }
 
-                    if c > 100 {
-                        100
-                    } else {
-                        c
-                    }
+        let msg = match request.message.clone() {
+            Some(v) => v,
+            None => return Err(ResponseError::NotEnoughArguments(CommandArgument::Message)),
+        };
+
+        let mut s = msg.split(' ').collect::<Vec<&str>>();
+
+        let count = if let Some(c) = s.first() {
+            if let Ok(c) = c.parse::<u32>() {
+                s.remove(0);
+
+                if c > 100 {
+                    100
                 } else {
-                    10
+                    c
                 }
             } else {
-                return Err(ResponseError::NotEnoughArguments(CommandArgument::Amount));
-            };
+                10
+            }
+        } else {
+            return Err(ResponseError::NotEnoughArguments(CommandArgument::Amount));
+        };
 
-            let msg = s.join(" ");
+        let msg = s.join(" ");
 
-            if msg.is_empty() {
-                return Err(ResponseError::NotEnoughArguments(CommandArgument::Message));
+        if msg.is_empty() {
+            return Err(ResponseError::NotEnoughArguments(CommandArgument::Message));
+        }
+
+        let delay = match request.options.get("delay") {
+            Some(v) => match parse_duration(v) {
+                Some(sec) if sec <= SPAM_MAX_DELAY_SEC => sec,
+                _ => return Err(ResponseError::IncorrectArgument(v.clone())),
+            },
+            None => 0,
+        };
+
+        let mut msgs = Vec::<String>::new();
+
+        if request.options.contains_key("pyramid") {
+            // the widest line must fit into a single message along with the counter
+            let max_len = if request.options.contains_key("counter") {
+                500 - format!(" ({}/{})", count * 2, count * 2).len()
+            } else {
+                500
+            };
+            let max_height = ((max_len + 1) / (msg.len() + 1)) as u32;
+
+            if max_height == 0 {
+                return Err(ResponseError::IncorrectArgument(msg));
             }
 
-            let mut msgs = Vec::<String>::new();
+            let height = count.min(max_height);
 
+            for i in (1..=height).chain((1..height).rev()) {
+                msgs.push(vec![msg.as_str(); i as usize].join(" "));
+            }
+        } else {
             for _ in 0..count {
                 msgs.push(msg.clone());
             }
+        }
+
+        // a counter makes every line unique so Twitch does not drop duplicates
+        if request.options.contains_key("counter") {
+            let total = msgs.len();
 
-            return Ok(Response::Multiple(msgs));
+            msgs = msgs
+                .iter()
+                .enumerate()
+                .map(|(i, x)| format!("{} ({}/{})", x, i + 1, total))
+                .collect();
         }
 
-        Err(ResponseError::NotEnoughArguments(CommandArgument::Message))
+        if let Some(task) = spam_tasks.remove(&request.channel.id) {
+            task.abort();
+        }
+
+        let irc_client = instance_bundle.twitch_irc_client.clone();
+        let channel_name = request.channel.alias_name.clone();
+
+        spam_tasks.insert(
+            request.channel.id,
+            tokio::spawn(async move {
+                for (i, msg) in msgs.into_iter().enumerate() {
+                    if i > 0 && delay > 0 {
+                        tokio::time::sleep(Duration::from_secs(delay)).await;
+                    }
+
+                    if irc_client.say(channel_name.clone(), msg).await.is_err() {
+                        break;
+                    }
+                }
+            }),
+        );
+
+        Ok(Response::Multiple(Vec::new()))
     }
 }
@@ -1,56 +1,106 @@
 pub(crate) mod schema;
 
-use reqwest::Client;
+use std::{sync::Arc, time::Duration};
 
-use self::schema::{ConnectionUser, EmoteSet, User};
+use crate::{
+    http::{HttpClient, RequestOptions},
+    shared_variables::HTTP_DEFAULT_TIMEOUT_SEC,
+};
+
+use self::schema::{
+    ChangeEmoteInSetData, ChangeEmoteInSetVariables, ConnectionUser, Emote, EmoteSet, GQLRequest,
+    GQLResponse, ListItemAction, User,
+};
 
 pub struct SevenTVAPIClient {
-    client: Client,
+    http_client: Arc<HttpClient>,
 }
 
 const SEVENTV_URL: &str = "https://7tv.io/v3";
 
+const CHANGE_EMOTE_IN_SET_MUTATION: &str = "mutation ChangeEmoteInSet($id: ObjectID!, $action: ListItemAction!, $emote_id: ObjectID!, $name: String) { emoteSet(id: $id) { id emotes(id: $emote_id, action: $action, name: $name) { id name } } }";
+
 impl SevenTVAPIClient {
-    pub fn new(client: Client) -> Self {
-        Self { client }
+    pub fn new(http_client: Arc<HttpClient>) -> Self {
+        Self { http_client }
     }
 
     pub async fn get_user_by_twitch_id(&self, user_id: String) -> Option<ConnectionUser> {
         let url = format!("{SEVENTV_URL}/users/twitch/{user_id}");
-        let request = self.client.get(url).send().await;
-
-        if let Ok(response) = request {
-            if let Ok(data) = response.json::<ConnectionUser>().await {
-                return Some(data);
-            }
-        }
 
-        None
+        self.http_client
+            .get_json::<ConnectionUser>(url.as_str(), &RequestOptions::default())
+            .await
+            .ok()
     }
 
     pub async fn get_user(&self, id: String) -> Option<User> {
         let url = format!("{SEVENTV_URL}/users/{id}");
-        let request = self.client.get(url).send().await;
-
-        if let Ok(response) = request {
-            if let Ok(data) = response.json::<User>().await {
-                return Some(data);
-            }
-        }
 
-        None
+        self.http_client
+            .get_json::<User>(url.as_str(), &RequestOptions::default())
+            .await
+            .ok()
     }
 
     pub async fn get_emote_set(&self, id: String) -> Option<EmoteSet> {
         let url = format!("{SEVENTV_URL}/emote-sets/{id}");
-        let request = self.client.get(url).send().await;
 
-        if let Ok(response) = request {
-            if let Ok(data) = response.json::<EmoteSet>().await {
-                return Some(data);
-            }
-        }
+        self.http_client
+            .get_json::<EmoteSet>(url.as_str(), &RequestOptions::default())
+            .await
+            .ok()
+    }
+
+    /// Adds, removes or renames the emote in the emote set on behalf of the token's owner.
+    /// Returns the emotes of the set after the change,
+    /// or the status code and the message of the error.
+    pub async fn change_emote_in_set(
+        &self,
+        token: &str,
+        emote_set_id: String,
+        action: ListItemAction,
+        emote_id: String,
+        name: Option<String>,
+    ) -> Result<Vec<Emote>, (u32, Option<String>)> {
+        let url = format!("{SEVENTV_URL}/gql");
+        let body = GQLRequest {
+            query: CHANGE_EMOTE_IN_SET_MUTATION.to_string(),
+            variables: ChangeEmoteInSetVariables {
+                id: emote_set_id,
+                action,
+                emote_id,
+                name,
+            },
+        };
 
-        None
+        // the mutation is not retried, so the emote is not changed twice
+        let response = match self
+            .http_client
+            .client()
+            .post(url)
+            .timeout(Duration::from_secs(HTTP_DEFAULT_TIMEOUT_SEC))
+            .bearer_auth(token)
+            .json(&body)
+            .send()
+            .await
+        {
+            Ok(v) => v,
+            Err(e) => return Err((e.status().map(|x| x.as_u16() as u32).unwrap_or(0), None)),
+        };
+
+        let status_code = response.status().as_u16() as u32;
+
+        match response.json::<GQLResponse<ChangeEmoteInSetData>>().await {
+            Ok(GQLResponse {
+                data: Some(data),
+                errors: None,
+            }) => Ok(data.emote_set.emotes),
+            Ok(GQLResponse {
+                errors: Some(errors),
+                ..
+            }) => Err((status_code, errors.into_iter().next().map(|x| x.message))),
+            _ => Err((status_code, None)),
+        }
     }
 }
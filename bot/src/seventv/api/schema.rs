@@ -1,4 +1,4 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, Debug)]
 pub struct ConnectionUser {
@@ -42,4 +42,61 @@ pub struct Emote {
     pub id: String,
     pub name: String,
     pub actor_id: Option<String>,
+    /// The time the emote was added to the set, in milliseconds.
+    pub timestamp: Option<i64>,
+    pub data: Option<EmoteData>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct EmoteData {
+    pub owner: Option<EmoteOwner>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct EmoteOwner {
+    pub username: String,
+}
+
+#[derive(Serialize)]
+pub struct GQLRequest<T> {
+    pub query: String,
+    pub variables: T,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct GQLResponse<T> {
+    pub data: Option<T>,
+    pub errors: Option<Vec<GQLError>>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct GQLError {
+    pub message: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ListItemAction {
+    Add,
+    Remove,
+    Update,
+}
+
+#[derive(Serialize)]
+pub struct ChangeEmoteInSetVariables {
+    pub id: String,
+    pub action: ListItemAction,
+    pub emote_id: String,
+    pub name: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeEmoteInSetData {
+    pub emote_set: ChangedEmoteSet,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ChangedEmoteSet {
+    pub emotes: Vec<Emote>,
 }
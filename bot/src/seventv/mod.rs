@@ -1,20 +1,27 @@
 pub mod api;
 pub(super) mod schema;
+#[cfg(test)]
+mod tests;
 
 use common::{
     establish_connection,
     models::ChannelFeature,
     schema::{channel_preferences::dsl as chp, channels::dsl as ch},
 };
-use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
-use futures::SinkExt;
-use log::info;
+use diesel::{ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl};
+use futures::{SinkExt, StreamExt};
+use log::{error, info};
 use serde_json::Value;
-use std::{collections::HashSet, str::FromStr, sync::Arc, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    env,
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use eyre::Context;
 use reqwest::Url;
-use tokio::net::TcpStream;
+use tokio::{net::TcpStream, time::Interval};
 use tokio_tungstenite::{
     connect_async_with_config,
     tungstenite::{protocol::WebSocketConfig, Message},
@@ -23,8 +30,14 @@ use tokio_tungstenite::{
 use twitch_api::types::UserId;
 
 use crate::{
-    instance_bundle::InstanceBundle, localization::LineId, seventv::schema::Payload,
-    shared_variables::SEVENTV_WEBSOCKET_URL,
+    emotes::{history::record_emote_changes, EmoteChange, EmoteProvider, ProviderEmote},
+    instance_bundle::InstanceBundle,
+    localization::LineId,
+    seventv::schema::Payload,
+    shared_variables::{
+        SEVENTV_DEFAULT_HEARTBEAT_INTERVAL_SEC, SEVENTV_MAX_RECONNECT_DELAY_SEC,
+        SEVENTV_RESOLVE_RETRY_DELAY, SEVENTV_WEBSOCKET_URL,
+    },
 };
 
 use self::schema::*;
@@ -37,159 +50,546 @@ async fn connect(url: Url) -> Result<WebSocketStream<MaybeTlsStream<TcpStream>>,
     Ok(socket)
 }
 
-pub struct SevenTVWebsocketClient {
-    socket: WebSocketStream<MaybeTlsStream<TcpStream>>,
-    session_id: Option<String>,
-    instance_bundle: Arc<InstanceBundle>,
-    reconnect_url: Url,
+/// Exponential backoff: the first reconnect is immediate, then 1, 2, 4... seconds.
+fn reconnect_delay(attempts: u32) -> Duration {
+    if attempts == 0 {
+        return Duration::ZERO;
+    }
 
-    listening_channel_ids: HashSet<UserId>,
+    Duration::from_secs(
+        2u64.saturating_pow(attempts - 1)
+            .min(SEVENTV_MAX_RECONNECT_DELAY_SEC),
+    )
 }
 
-impl SevenTVWebsocketClient {
-    pub async fn new(instance_bundle: Arc<InstanceBundle>) -> Result<Self, eyre::Error> {
-        let reconnect_url = Url::parse(SEVENTV_WEBSOCKET_URL).unwrap();
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Subscription {
+    event_type: String,
+    object_id: String,
+}
 
-        Ok(Self {
-            instance_bundle,
-            socket: connect(reconnect_url.clone()).await?,
+/// One of the connections to 7TV EventAPI.
+/// A new one is opened when the others have reached their subscription limit.
+struct Shard {
+    socket: Option<WebSocketStream<MaybeTlsStream<TcpStream>>>,
+    session_id: Option<String>,
+    is_ready: bool,
+    subscription_limit: Option<usize>,
+    heartbeat_interval: Duration,
+    last_message_at: Instant,
+
+    /// Subscriptions that the shard must have.
+    subscriptions: HashSet<Subscription>,
+    /// Subscriptions that 7TV has acknowledged in the current or the last session.
+    confirmed_subscriptions: HashSet<Subscription>,
+
+    attempts: u32,
+    reconnect_at: Instant,
+}
+
+impl Shard {
+    fn new() -> Self {
+        Self {
+            socket: None,
             session_id: None,
-            reconnect_url,
-            listening_channel_ids: HashSet::new(),
-        })
+            is_ready: false,
+            subscription_limit: None,
+            heartbeat_interval: Duration::from_secs(SEVENTV_DEFAULT_HEARTBEAT_INTERVAL_SEC),
+            last_message_at: Instant::now(),
+            subscriptions: HashSet::new(),
+            confirmed_subscriptions: HashSet::new(),
+            attempts: 0,
+            reconnect_at: Instant::now(),
+        }
     }
 
-    pub async fn run(&mut self) -> Result<(), eyre::Error> {
-        loop {
-            self.process_awaiting_channels().await?;
-            tokio::select!(
-                Some(msg) = futures::StreamExt::next(&mut self.socket) => {
-                    let msg = match msg {
-                        Err(tungstenite::Error::Protocol(tungstenite::error::ProtocolError::ResetWithoutClosingHandshake)) => {
-                            self.socket = connect(self.reconnect_url.clone()).await.context("when reestablishing connection")?;
-                            continue
-                        }
-                        _ => msg.context("when getting message")?,
-                    };
-                    self.process_message(msg).await?
-                }
-            )
+    fn is_full(&self) -> bool {
+        self.subscription_limit
+            .is_some_and(|x| self.subscriptions.len() >= x)
+    }
+}
+
+/// What the supervisor has received from the connections.
+enum SupervisorEvent {
+    Dispatch(Dispatch),
+    /// The interval has ticked, so it's time to check the connections.
+    Tick,
+}
+
+/// Supervises the connections to 7TV EventAPI: reconnects them with backoff,
+/// resumes the sessions or subscribes again, and shards the subscriptions.
+struct EventAPISupervisor {
+    url: Url,
+    shards: Vec<Shard>,
+}
+
+impl EventAPISupervisor {
+    fn new(url: Url) -> Self {
+        Self {
+            url,
+            shards: vec![Shard::new()],
         }
     }
 
-    async fn flip_channels(&mut self) {
-        let mut data = self.instance_bundle.seventv_eventapi_data.lock().await;
+    /// Waits for a dispatch from any of the connections or for the next tick of the interval.
+    /// The other messages are handled by the supervisor itself.
+    async fn next_event(&mut self, interval: &mut Interval) -> Option<SupervisorEvent> {
+        let message = {
+            let sockets = self
+                .shards
+                .iter_mut()
+                .enumerate()
+                .filter_map(|(i, x)| x.socket.as_mut().map(|y| (i, y)))
+                .map(|(i, x)| Box::pin(async move { (i, x.next().await) }))
+                .collect::<Vec<_>>();
+
+            if sockets.is_empty() {
+                interval.tick().await;
+                None
+            } else {
+                tokio::select!(
+                    (v, _, _) = futures::future::select_all(sockets) => Some(v),
+                    _ = interval.tick() => None,
+                )
+            }
+        };
+
+        match message {
+            Some((index, Some(Ok(msg)))) => self
+                .process_message(index, msg)
+                .await
+                .map(SupervisorEvent::Dispatch),
+            Some((index, Some(Err(e)))) => {
+                error!("[7TV EventAPI] Connection #{} error: {}", index, e);
+                self.disconnect(index).await;
+                None
+            }
+            Some((index, None)) => {
+                self.disconnect(index).await;
+                None
+            }
+            None => Some(SupervisorEvent::Tick),
+        }
+    }
 
-        let mut set_a: HashSet<UserId> = HashSet::from_iter(data.iter().cloned());
-        let set_b: HashSet<UserId> = HashSet::from_iter(self.listening_channel_ids.iter().cloned());
+    /// Reconnects the closed shards and closes the ones that stopped sending heartbeats.
+    async fn process_shards(&mut self) {
+        for index in 0..self.shards.len() {
+            let shard = &mut self.shards[index];
 
-        set_a.extend(set_b);
+            if shard.socket.is_some() {
+                if shard.last_message_at.elapsed() > shard.heartbeat_interval * 3 {
+                    info!("[7TV EventAPI] Connection #{} missed heartbeats", index);
+                    self.disconnect(index).await;
+                }
 
-        *data = HashSet::from_iter(set_a.into_iter());
-        self.listening_channel_ids.clear();
+                continue;
+            }
+
+            if shard.reconnect_at > Instant::now() {
+                continue;
+            }
+
+            match connect(self.url.clone()).await {
+                Ok(v) => {
+                    info!("[7TV EventAPI] Connection #{} is established", index);
+                    shard.socket = Some(v);
+                    shard.last_message_at = Instant::now();
+                }
+                Err(e) => {
+                    let delay = reconnect_delay(shard.attempts + 1);
+                    shard.attempts += 1;
+                    shard.reconnect_at = Instant::now() + delay;
+
+                    error!(
+                        "[7TV EventAPI] Failed to connect #{}: {}. Retrying in {} seconds",
+                        index,
+                        e,
+                        delay.as_secs()
+                    );
+                }
+            }
+        }
+    }
+
+    /// Closes the shard's connection. Its subscriptions are restored after reconnecting.
+    async fn disconnect(&mut self, index: usize) {
+        let shard = &mut self.shards[index];
+
+        if let Some(mut socket) = shard.socket.take() {
+            let _ = socket.close(None).await;
+        }
+
+        shard.is_ready = false;
+        shard.reconnect_at = Instant::now() + reconnect_delay(shard.attempts);
+        shard.attempts += 1;
     }
 
-    pub async fn process_message(&mut self, msg: Message) -> Result<(), eyre::Report> {
+    /// Handles the message of the shard's connection. Dispatches are returned to the caller.
+    async fn process_message(&mut self, index: usize, msg: Message) -> Option<Dispatch> {
+        self.shards[index].last_message_at = Instant::now();
+
         match msg {
             Message::Text(s) => {
-                println!("received 7tv text message: {s}");
-
-                if let Ok(e) = serde_json::from_str::<Payload<Value>>(s.as_str()) {
-                    let d = e.d.to_string();
-                    let d = d.as_str();
+                let e = match serde_json::from_str::<Payload<Value>>(s.as_str()) {
+                    Ok(v) => v,
+                    Err(_) => return None,
+                };
 
-                    match e.op {
-                        // Dispatch
-                        0 => {
-                            if let Ok(d) = serde_json::from_str::<Dispatch>(d) {
-                                self.handle_dispatch(d).await?;
-                            }
+                match e.op {
+                    // Dispatch
+                    0 => return serde_json::from_value::<Dispatch>(e.d).ok(),
+                    // Hello
+                    1 => {
+                        if let Ok(d) = serde_json::from_value::<Hello>(e.d) {
+                            self.handle_hello(index, d).await;
                         }
-                        // Hello
-                        1 => {
-                            if let Ok(d) = serde_json::from_str::<Hello>(d) {
-                                if self.session_id.is_none() {
-                                    self.session_id = Some(d.session_id);
-                                    self.process_awaiting_channels().await?;
-                                } else {
-                                    self.resume_session().await?;
-                                }
-                            }
-                        }
-                        // Heartbeat
-                        2 => println!("[7TV EventAPI] Heartbeat!"),
-                        // Reconnect
-                        4 => {
-                            println!("[7TV EventAPI] Reconnect!");
-
-                            self.flip_channels().await;
-                            self.socket.close(None).await?;
-                            self.socket = connect(self.reconnect_url.clone()).await?;
-                        }
-                        // Error
-                        6 => println!("[7TV EventAPI] Error: {}", e.d),
-                        // End of Stream
-                        7 => {
-                            println!("[7TV EventAPI] The host has closed the connection! Reason: ")
+                    }
+                    // Heartbeat
+                    2 => {}
+                    // Reconnect
+                    4 => {
+                        info!("[7TV EventAPI] Connection #{} is asked to reconnect", index);
+                        self.disconnect(index).await;
+                    }
+                    // Ack
+                    5 => {
+                        if let Ok(d) = serde_json::from_value::<Ack>(e.d) {
+                            self.handle_ack(index, d).await;
                         }
-                        _ => println!(
-                            "[7TV EventAPI] Unhandled opcode: {}. Payload: {}",
-                            e.op, e.d
-                        ),
                     }
+                    // Error
+                    6 => error!("[7TV EventAPI] Connection #{} error: {}", index, e.d),
+                    // End of Stream
+                    7 => {
+                        info!(
+                            "[7TV EventAPI] The host has closed connection #{}: {}",
+                            index, e.d
+                        );
+                        self.disconnect(index).await;
+                    }
+                    _ => info!(
+                        "[7TV EventAPI] Unhandled opcode: {}. Payload: {}",
+                        e.op, e.d
+                    ),
                 }
             }
             Message::Close(e) => {
-                let e = if e.is_some() {
-                    let unwrapped_e = e.unwrap();
+                let e = match e {
+                    Some(v) => format!("{} {}", v.code, v.reason),
+                    None => "No reason".to_string(),
+                };
 
-                    format!("{} {}", unwrapped_e.code, unwrapped_e.reason)
-                } else {
-                    "No reason".to_string()
+                info!(
+                    "[7TV EventAPI] Connection #{} has been closed. Reason: {}",
+                    index, e
+                );
+
+                self.disconnect(index).await;
+            }
+            _ => {}
+        }
+
+        None
+    }
+
+    async fn handle_hello(&mut self, index: usize, body: Hello) {
+        let shard = &mut self.shards[index];
+
+        shard.attempts = 0;
+        // a negative limit means there is no limit
+        shard.subscription_limit = usize::try_from(body.subscription_limit).ok();
+
+        if let Some(v) = body.heartbeat_interval {
+            shard.heartbeat_interval = Duration::from_millis(v);
+        }
+
+        if let Some(session_id) = shard.session_id.replace(body.session_id) {
+            if !shard.confirmed_subscriptions.is_empty() {
+                let data = Payload {
+                    op: 34,
+                    d: Resume { session_id },
                 };
 
+                self.send(index, data).await;
+                return;
+            }
+        }
+
+        self.resubscribe(index).await;
+    }
+
+    async fn handle_ack(&mut self, index: usize, body: Ack) {
+        match body.command.as_str() {
+            "RESUME" => {
+                let data = match serde_json::from_value::<ResumeAck>(body.data) {
+                    Ok(v) => v,
+                    Err(_) => return,
+                };
+
+                let shard = &mut self.shards[index];
+
+                if !data.success {
+                    info!("[7TV EventAPI] Failed to resume connection #{}", index);
+                    self.resubscribe(index).await;
+                    return;
+                }
+
+                // it's unknown which subscriptions are gone, so it's safer to start over
+                if data.subscriptions_restored as usize != shard.confirmed_subscriptions.len() {
+                    info!(
+                        "[7TV EventAPI] Connection #{} has restored {} of {} subscriptions",
+                        index,
+                        data.subscriptions_restored,
+                        shard.confirmed_subscriptions.len()
+                    );
+                    shard.session_id = None;
+                    shard.confirmed_subscriptions.clear();
+                    self.disconnect(index).await;
+                    return;
+                }
+
                 info!(
-                    "The connection to 7TV EventAPI has been disconnected. Reason: {}",
-                    e
+                    "[7TV EventAPI] Connection #{} has been resumed, {} dispatches replayed",
+                    index, data.dispatches_replayed
                 );
 
-                self.flip_channels().await;
+                shard.is_ready = true;
+
+                let subscribed = shard
+                    .subscriptions
+                    .difference(&shard.confirmed_subscriptions)
+                    .cloned()
+                    .collect::<Vec<Subscription>>();
+                let unsubscribed = shard
+                    .confirmed_subscriptions
+                    .difference(&shard.subscriptions)
+                    .cloned()
+                    .collect::<Vec<Subscription>>();
+
+                for subscription in subscribed {
+                    self.send_subscription(index, 35, &subscription).await;
+                }
 
-                let mut attempts = 0;
+                for subscription in unsubscribed {
+                    self.send_subscription(index, 36, &subscription).await;
+                }
+            }
+            "SUBSCRIBE" | "UNSUBSCRIBE" => {
+                let data = match serde_json::from_value::<Subscribe>(body.data) {
+                    Ok(v) => v,
+                    Err(_) => return,
+                };
 
-                loop {
-                    if attempts > 2 {
-                        break;
-                    }
+                let subscription = Subscription {
+                    event_type: data.event_type,
+                    object_id: data.condition.object_id,
+                };
 
-                    tokio::time::sleep(Duration::from_secs(60)).await;
+                let shard = &mut self.shards[index];
 
-                    match connect(self.reconnect_url.clone()).await {
-                        Ok(v) => self.socket = v,
-                        Err(_) => {
-                            attempts += 1;
-                            info!("Failed to reconnect to 7TV EventAPI! {} attempts left.", {
-                                3 - attempts
-                            });
-                        }
-                    }
+                if body.command.eq("SUBSCRIBE") {
+                    shard.confirmed_subscriptions.insert(subscription);
+                } else {
+                    shard.confirmed_subscriptions.remove(&subscription);
                 }
             }
             _ => {}
         }
+    }
 
-        Ok(())
+    /// Sends all the shard's subscriptions in a new session.
+    /// The ones over the subscription limit are moved to other shards.
+    async fn resubscribe(&mut self, index: usize) {
+        let shard = &mut self.shards[index];
+
+        shard.is_ready = true;
+        shard.confirmed_subscriptions.clear();
+
+        let mut subscriptions = shard.subscriptions.iter().cloned().collect::<Vec<_>>();
+        let overflow = match shard.subscription_limit {
+            Some(v) if subscriptions.len() > v => subscriptions.split_off(v),
+            _ => Vec::new(),
+        };
+
+        for subscription in &overflow {
+            shard.subscriptions.remove(subscription);
+        }
+
+        for subscription in subscriptions {
+            self.send_subscription(index, 35, &subscription).await;
+        }
+
+        for subscription in overflow {
+            self.subscribe(subscription.event_type.as_str(), subscription.object_id)
+                .await;
+        }
+    }
+
+    /// Subscribes to the event on a shard that has not reached its limit yet.
+    async fn subscribe(&mut self, event_type: &str, object_id: String) {
+        let subscription = Subscription {
+            event_type: event_type.to_string(),
+            object_id,
+        };
+
+        let index = match self.shards.iter().position(|x| !x.is_full()) {
+            Some(v) => v,
+            None => {
+                info!(
+                    "[7TV EventAPI] All connections have reached their subscription limit. Opening a new one"
+                );
+                self.shards.push(Shard::new());
+                self.shards.len() - 1
+            }
+        };
+
+        self.shards[index]
+            .subscriptions
+            .insert(subscription.clone());
+
+        // otherwise, it is sent after the connection is ready
+        if self.shards[index].is_ready {
+            self.send_subscription(index, 35, &subscription).await;
+        }
+    }
+
+    async fn unsubscribe(&mut self, event_type: &str, object_id: String) {
+        let subscription = Subscription {
+            event_type: event_type.to_string(),
+            object_id,
+        };
+
+        if let Some(index) = self
+            .shards
+            .iter()
+            .position(|x| x.subscriptions.contains(&subscription))
+        {
+            self.shards[index].subscriptions.remove(&subscription);
+
+            if self.shards[index].is_ready {
+                self.send_subscription(index, 36, &subscription).await;
+            }
+        }
+    }
+
+    async fn send_subscription(&mut self, index: usize, op: u32, subscription: &Subscription) {
+        let data = Payload {
+            op,
+            d: Subscribe {
+                event_type: subscription.event_type.clone(),
+                condition: SubscribeCondition {
+                    object_id: subscription.object_id.clone(),
+                },
+            },
+        };
+
+        self.send(index, data).await;
+    }
+
+    async fn send<T: serde::Serialize>(&mut self, index: usize, data: Payload<T>) {
+        let message = Message::Text(serde_json::to_string(&data).unwrap());
+
+        let result = match self.shards[index].socket.as_mut() {
+            Some(socket) => socket.send(message).await,
+            None => return,
+        };
+
+        if let Err(e) = result {
+            error!(
+                "[7TV EventAPI] Failed to send a message to connection #{}: {}",
+                index, e
+            );
+            self.disconnect(index).await;
+        }
+    }
+}
+
+/// Listens to the 7TV events of the joined channels and announces the emote changes.
+pub struct SevenTVWebsocketClient {
+    instance_bundle: Arc<InstanceBundle>,
+    supervisor: EventAPISupervisor,
+    resolved_at: Instant,
+
+    listening_channel_ids: HashSet<UserId>,
+    unresolved_channel_ids: HashSet<UserId>,
+    listening_emote_set_ids: HashMap<UserId, String>,
+    listening_user_ids: HashMap<UserId, String>,
+}
+
+impl SevenTVWebsocketClient {
+    pub fn new(instance_bundle: Arc<InstanceBundle>) -> Self {
+        // the URL can be overridden, e.g. to use a local EventAPI server
+        let url = match env::var("SEVENTV_WEBSOCKET_URL") {
+            Ok(v) => Url::parse(v.as_str()).expect("SEVENTV_WEBSOCKET_URL must be a valid URL"),
+            Err(_) => Url::parse(SEVENTV_WEBSOCKET_URL).unwrap(),
+        };
+
+        Self {
+            instance_bundle,
+            supervisor: EventAPISupervisor::new(url),
+            resolved_at: Instant::now(),
+            listening_channel_ids: HashSet::new(),
+            unresolved_channel_ids: HashSet::new(),
+            listening_emote_set_ids: HashMap::new(),
+            listening_user_ids: HashMap::new(),
+        }
+    }
+
+    /// Never returns: the failures are logged and the connections are restored.
+    pub async fn run(&mut self) {
+        let mut interval = tokio::time::interval(Duration::from_secs(1));
+
+        loop {
+            match self.supervisor.next_event(&mut interval).await {
+                Some(SupervisorEvent::Dispatch(d)) => {
+                    if let Err(e) = self.handle_dispatch(d).await {
+                        error!("[7TV EventAPI] Failed to handle a dispatch: {}", e);
+                    }
+                }
+                Some(SupervisorEvent::Tick) => {
+                    self.process_awaiting_channels().await;
+                    self.supervisor.process_shards().await;
+                }
+                None => {}
+            }
+        }
     }
 
     async fn handle_dispatch(&mut self, body: Dispatch) -> Result<(), eyre::Error> {
-        if body.event_type != *"emote_set.update" {
-            println!("[7TV EventAPI] Unhandled body type: {}", body.event_type);
+        match body.event_type.as_str() {
+            "emote_set.update" => {
+                if let Ok(body) = serde_json::from_value::<DispatchBody>(body.body) {
+                    self.handle_emote_set_update(body).await?;
+                }
+            }
+            "user.update" => {
+                if let Ok(body) = serde_json::from_value::<UserDispatchBody>(body.body) {
+                    self.handle_user_update(body).await?;
+                }
+            }
+            _ => info!("[7TV EventAPI] Unhandled body type: {}", body.event_type),
+        }
+
+        Ok(())
+    }
+
+    async fn handle_emote_set_update(&mut self, body: DispatchBody) -> Result<(), eyre::Error> {
+        // the changes made by the !7tv command are recorded and announced by the command itself
+        let bot_id = self.instance_bundle.twitch_api_token.user_id.as_str();
+
+        if body
+            .actor
+            .connections
+            .iter()
+            .any(|x| x.platform.eq("TWITCH") && x.id.eq(bot_id))
+        {
             return Ok(());
         }
 
         let api = self.instance_bundle.seventv_api_client.clone();
 
-        if let Some(emote_set) = api.get_emote_set(body.body.id).await {
+        if let Some(emote_set) = api.get_emote_set(body.id).await {
             if let Some(emote_set_owner) = emote_set.owner {
                 if let Some(emote_set_owner) = api.get_user(emote_set_owner.id).await {
                     if let Some(owner) = emote_set_owner
@@ -198,7 +598,6 @@ impl SevenTVWebsocketClient {
                         .find(|x| x.platform.eq("TWITCH"))
                     {
                         let actor_name = if let Some(connection) = body
-                            .body
                             .actor
                             .connections
                             .iter()
@@ -206,24 +605,73 @@ impl SevenTVWebsocketClient {
                         {
                             connection.username.clone()
                         } else {
-                            body.body.actor.username
+                            body.actor.username
                         };
 
                         let conn = &mut establish_connection();
 
-                        let owner_id = owner.id.parse::<i32>().unwrap();
+                        let owner_id = owner.id.parse::<i32>()?;
 
-                        let channel = ch::channels
+                        let channel = match ch::channels
                             .filter(ch::alias_id.eq(&owner_id))
                             .select((ch::id, ch::alias_name))
                             .get_result::<(i32, String)>(conn)
-                            .expect("Failed to get channel");
+                            .optional()?
+                        {
+                            Some(v) => v,
+                            None => return Ok(()),
+                        };
+
+                        let mut changes: Vec<EmoteChange> = Vec::new();
+
+                        for e in body.pushed.unwrap_or_default() {
+                            if let Some(emote) = e.value {
+                                changes.push(EmoteChange::Pushed(ProviderEmote {
+                                    id: emote.id,
+                                    name: emote.name,
+                                }));
+                            }
+                        }
+
+                        for e in body.pulled.unwrap_or_default() {
+                            if let Some(emote) = e.old_value {
+                                changes.push(EmoteChange::Pulled(ProviderEmote {
+                                    id: emote.id,
+                                    name: emote.name,
+                                }));
+                            }
+                        }
+
+                        for e in body.updated.unwrap_or_default() {
+                            if let (Some(old_emote), Some(emote)) = (e.old_value, e.value) {
+                                changes.push(EmoteChange::Updated(
+                                    old_emote.name,
+                                    ProviderEmote {
+                                        id: emote.id,
+                                        name: emote.name,
+                                    },
+                                ));
+                            }
+                        }
+
+                        // the changes are stored even if the chat room doesn't want to hear about them
+                        record_emote_changes(
+                            conn,
+                            owner_id,
+                            &EmoteProvider::SevenTV,
+                            Some(actor_name.clone()),
+                            &changes,
+                        );
+
+                        self.instance_bundle
+                            .emote_details_cache
+                            .invalidate(&EmoteProvider::SevenTV, owner_id)
+                            .await;
 
                         let (channel_language, channel_features) = chp::channel_preferences
                             .filter(chp::channel_id.eq(&channel.0))
                             .select((chp::language, chp::features))
-                            .get_result::<(String, Vec<Option<String>>)>(conn)
-                            .expect("Failed to get channel preference");
+                            .get_result::<(String, Vec<Option<String>>)>(conn)?;
 
                         if !channel_features.iter().flatten().any(|x| {
                             if let Ok(f) = ChannelFeature::from_str(x.as_str()) {
@@ -235,88 +683,41 @@ impl SevenTVWebsocketClient {
                             return Ok(());
                         }
 
-                        let mut messages: Vec<String> = Vec::new();
-
-                        if let Some(pushed) = body.body.pushed {
-                            for e in pushed {
-                                let emote_name = e.value.unwrap().name;
-
-                                messages.push(
-                                    self.instance_bundle
-                                        .localizator
-                                        .get_formatted_text(
-                                            channel_language.as_str(),
-                                            LineId::EmotesPushed,
-                                            vec![
-                                                self.instance_bundle
-                                                    .localizator
-                                                    .get_literal_text(
-                                                        channel_language.as_str(),
-                                                        LineId::Provider7TV,
-                                                    )
-                                                    .unwrap(),
-                                                actor_name.clone(),
-                                                emote_name,
-                                            ],
-                                        )
-                                        .unwrap(),
-                                );
-                            }
-                        }
+                        let provider_name = self
+                            .instance_bundle
+                            .localizator
+                            .get_literal_text(channel_language.as_str(), LineId::Provider7TV)
+                            .unwrap_or_default();
 
-                        if let Some(pulled) = body.body.pulled {
-                            for e in pulled {
-                                let emote_name = e.old_value.unwrap().name;
-
-                                messages.push(
-                                    self.instance_bundle
-                                        .localizator
-                                        .get_formatted_text(
-                                            channel_language.as_str(),
-                                            LineId::EmotesPulled,
-                                            vec![
-                                                self.instance_bundle
-                                                    .localizator
-                                                    .get_literal_text(
-                                                        channel_language.as_str(),
-                                                        LineId::Provider7TV,
-                                                    )
-                                                    .unwrap(),
-                                                actor_name.clone(),
-                                                emote_name,
-                                            ],
-                                        )
-                                        .unwrap(),
-                                )
-                            }
-                        }
+                        let mut messages: Vec<String> = Vec::new();
 
-                        if let Some(updated) = body.body.updated {
-                            for e in updated {
-                                let emote_name = e.value.unwrap().name;
-                                let old_emote_name = e.old_value.unwrap().name;
-
-                                messages.push(
-                                    self.instance_bundle
-                                        .localizator
-                                        .get_formatted_text(
-                                            channel_language.as_str(),
-                                            LineId::EmotesUpdated,
-                                            vec![
-                                                self.instance_bundle
-                                                    .localizator
-                                                    .get_literal_text(
-                                                        channel_language.as_str(),
-                                                        LineId::Provider7TV,
-                                                    )
-                                                    .unwrap(),
-                                                actor_name.clone(),
-                                                old_emote_name,
-                                                emote_name,
-                                            ],
-                                        )
-                                        .unwrap(),
-                                )
+                        for change in changes {
+                            let (line_id, parameters) = match change {
+                                EmoteChange::Pushed(emote) => (
+                                    LineId::EmotesPushed,
+                                    vec![provider_name.clone(), actor_name.clone(), emote.name],
+                                ),
+                                EmoteChange::Pulled(emote) => (
+                                    LineId::EmotesPulled,
+                                    vec![provider_name.clone(), actor_name.clone(), emote.name],
+                                ),
+                                EmoteChange::Updated(old_name, emote) => (
+                                    LineId::EmotesUpdated,
+                                    vec![
+                                        provider_name.clone(),
+                                        actor_name.clone(),
+                                        old_name,
+                                        emote.name,
+                                    ],
+                                ),
+                            };
+
+                            if let Some(message) = self
+                                .instance_bundle
+                                .localizator
+                                .get_formatted_text(channel_language.as_str(), line_id, parameters)
+                            {
+                                messages.push(message);
                             }
                         }
 
@@ -324,8 +725,7 @@ impl SevenTVWebsocketClient {
                             self.instance_bundle
                                 .twitch_irc_client
                                 .say(channel.1.clone(), m)
-                                .await
-                                .expect("Failed to send a message");
+                                .await?;
                         }
                     }
                 }
@@ -335,78 +735,223 @@ impl SevenTVWebsocketClient {
         Ok(())
     }
 
-    async fn process_awaiting_channels(&mut self) -> Result<(), eyre::Error> {
-        let mut data = self.instance_bundle.seventv_eventapi_data.lock().await;
-
-        let ids = data
+    /// Follows the switches of the active emote set of the channel's Twitch connection.
+    async fn handle_user_update(&mut self, body: UserDispatchBody) -> Result<(), eyre::Error> {
+        let channel_id = match self
+            .listening_user_ids
             .iter()
-            .filter(|x| !self.listening_channel_ids.iter().any(|y| y.eq(*x)))
-            .cloned()
-            .collect::<HashSet<UserId>>();
+            .find(|(_, v)| v.eq(&&body.id))
+        {
+            Some((k, _)) => k.clone(),
+            None => return Ok(()),
+        };
 
-        *data = ids.clone();
+        let api = self.instance_bundle.seventv_api_client.clone();
+        let mut change: Option<(usize, EmoteSetChangeField)> = None;
 
-        drop(data);
+        for field in body.updated.unwrap_or_default() {
+            if field.key != *"connections" {
+                continue;
+            }
 
-        if !ids.is_empty() {
-            for id in ids {
-                self.listen_channel(id).await?;
+            if let (Some(index), Some(values)) = (field.index, field.value.as_array()) {
+                if let Some(value) = values
+                    .iter()
+                    .filter_map(|x| serde_json::from_value::<EmoteSetChangeField>(x.clone()).ok())
+                    .find(|x| x.key.eq("emote_set"))
+                {
+                    change = Some((index, value));
+                }
             }
         }
 
+        let (index, change) = match change {
+            Some(v) => v,
+            None => return Ok(()),
+        };
+
+        // the user may have connections to other platforms with their own emote sets
+        match api.get_user(body.id).await {
+            Some(user)
+                if user
+                    .connections
+                    .get(index)
+                    .is_some_and(|x| x.platform.eq("TWITCH") && x.id.eq(channel_id.as_str())) => {}
+            _ => return Ok(()),
+        }
+
+        let new_emote_set_id = change.value.as_ref().map(|x| x.id.clone());
+
+        if self.listening_emote_set_ids.get(&channel_id) == new_emote_set_id.as_ref() {
+            return Ok(());
+        }
+
+        if let Some(emote_set_id) = self.listening_emote_set_ids.remove(&channel_id) {
+            self.supervisor
+                .unsubscribe("emote_set.update", emote_set_id)
+                .await;
+        }
+
+        if let Some(emote_set_id) = new_emote_set_id {
+            self.listening_emote_set_ids
+                .insert(channel_id.clone(), emote_set_id.clone());
+            self.supervisor
+                .subscribe("emote_set.update", emote_set_id)
+                .await;
+        }
+
+        // all the emotes have changed along with the emote set
+        self.instance_bundle
+            .emote_details_cache
+            .invalidate(&EmoteProvider::SevenTV, channel_id.as_str().parse::<i32>()?)
+            .await;
+
+        let (old_emote_set, new_emote_set) = match (change.old_value, change.value) {
+            (Some(a), Some(b)) => (a, b),
+            _ => return Ok(()),
+        };
+
+        let actor_name = match body
+            .actor
+            .connections
+            .iter()
+            .find(|x| x.platform.eq("TWITCH"))
+        {
+            Some(connection) => connection.username.clone(),
+            None => body.actor.username,
+        };
+
+        let conn = &mut establish_connection();
+
+        let preference = ch::channels
+            .inner_join(chp::channel_preferences)
+            .filter(ch::alias_id.eq(channel_id.as_str().parse::<i32>()?))
+            .filter(ch::opt_outed_at.is_null())
+            .select((ch::alias_name, chp::language, chp::features))
+            .first::<(String, String, Vec<Option<String>>)>(conn)
+            .optional()?;
+
+        let (channel_name, channel_language, channel_features) = match preference {
+            Some(v) => v,
+            None => return Ok(()),
+        };
+
+        if !channel_features.iter().flatten().any(|x| {
+            ChannelFeature::from_str(x.as_str())
+                .is_ok_and(|f| f == ChannelFeature::Notify7TVUpdates)
+        }) {
+            return Ok(());
+        }
+
+        let localizator = &self.instance_bundle.localizator;
+
+        if let Some(message) = localizator.get_formatted_text(
+            channel_language.as_str(),
+            LineId::EmoteSetSwitched,
+            vec![
+                localizator
+                    .get_literal_text(channel_language.as_str(), LineId::Provider7TV)
+                    .unwrap_or_default(),
+                actor_name,
+                old_emote_set.name,
+                new_emote_set.name,
+            ],
+        ) {
+            self.instance_bundle
+                .twitch_irc_client
+                .say(channel_name, message)
+                .await?;
+        }
+
         Ok(())
     }
 
-    async fn listen_channel(&mut self, channel_id: UserId) -> Result<(), eyre::Error> {
-        if let Some(user) = self
+    async fn process_awaiting_channels(&mut self) {
+        // the channels without 7TV accounts are checked again from time to time
+        if self.resolved_at.elapsed() >= Duration::from_secs(SEVENTV_RESOLVE_RETRY_DELAY) {
+            self.resolved_at = Instant::now();
+            self.instance_bundle
+                .seventv_eventapi_data
+                .lock()
+                .await
+                .extend(self.unresolved_channel_ids.drain());
+        }
+
+        let ids = self
             .instance_bundle
-            .seventv_api_client
-            .get_user_by_twitch_id(channel_id.clone().take())
+            .seventv_eventapi_data
+            .lock()
             .await
-        {
-            let emote_set_id = user.emote_set.id;
-
-            let data = Payload {
-                op: 35,
-                d: Subscribe {
-                    event_type: "emote_set.update".to_string(),
-                    condition: SubscribeCondition {
-                        object_id: emote_set_id,
-                    },
-                },
-            };
+            .drain()
+            .collect::<Vec<UserId>>();
 
-            println!("{:?}", serde_json::to_string(&data).unwrap());
-            self.socket
-                .send(Message::Text(serde_json::to_string(&data).unwrap()))
-                .await?;
+        for id in ids {
+            if !self.listening_channel_ids.contains(&id) {
+                self.listen_channel(id).await;
+            }
+        }
 
-            println!("Listening 7TV events for {}'s emote set", user.username);
+        let removed_ids = self
+            .instance_bundle
+            .seventv_eventapi_removed_data
+            .lock()
+            .await
+            .drain()
+            .collect::<Vec<UserId>>();
 
-            self.listening_channel_ids.insert(channel_id);
+        for id in removed_ids {
+            self.unresolved_channel_ids.remove(&id);
+            self.unlisten_channel(id).await;
         }
-
-        Ok(())
     }
 
-    async fn resume_session(&mut self) -> Result<(), eyre::Error> {
-        if self.session_id.is_none() {
-            println!("[7TV EventAPI] Failed to resume a session because session_id is none!");
+    async fn unlisten_channel(&mut self, channel_id: UserId) {
+        self.listening_channel_ids.remove(&channel_id);
 
-            return Ok(());
+        if let Some(user_id) = self.listening_user_ids.remove(&channel_id) {
+            self.supervisor.unsubscribe("user.update", user_id).await;
         }
 
-        let data = Payload {
-            op: 34,
-            d: Resume {
-                session_id: self.session_id.clone().unwrap(),
-            },
+        if let Some(emote_set_id) = self.listening_emote_set_ids.remove(&channel_id) {
+            self.supervisor
+                .unsubscribe("emote_set.update", emote_set_id)
+                .await;
+
+            info!("Stopped listening 7TV events for channel ID {}", channel_id);
+        }
+    }
+
+    async fn listen_channel(&mut self, channel_id: UserId) {
+        let user = match self
+            .instance_bundle
+            .seventv_api_client
+            .get_user_by_twitch_id(channel_id.clone().take())
+            .await
+        {
+            Some(v) => v,
+            None => {
+                self.unresolved_channel_ids.insert(channel_id);
+                return;
+            }
         };
 
-        self.socket
-            .send(Message::Text(serde_json::to_string(&data)?))
-            .await?;
+        let emote_set_id = user.emote_set.id;
 
-        Ok(())
+        self.listening_emote_set_ids
+            .insert(channel_id.clone(), emote_set_id.clone());
+
+        self.supervisor
+            .subscribe("emote_set.update", emote_set_id)
+            .await;
+
+        // the active emote set can be switched later
+        self.listening_user_ids
+            .insert(channel_id.clone(), user.user.id.clone());
+
+        self.supervisor.subscribe("user.update", user.user.id).await;
+
+        info!("Listening 7TV events for {}'s emote set", user.username);
+
+        self.listening_channel_ids.insert(channel_id);
     }
 }
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 use super::api::schema::{Emote, User};
 
@@ -12,7 +13,7 @@ pub struct Payload<T> {
 pub struct Dispatch {
     #[serde(rename(deserialize = "type"))]
     pub event_type: String,
-    pub body: DispatchBody,
+    pub body: Value,
 }
 
 #[derive(Deserialize, Debug)]
@@ -33,26 +34,63 @@ pub struct ChangeField {
     pub value: Option<Emote>,
 }
 
+#[derive(Deserialize, Debug)]
+pub struct UserDispatchBody {
+    pub id: String,
+    pub actor: User,
+    pub updated: Option<Vec<NestedChangeField>>,
+}
+
+/// A change of the user's object, e.g. of one of their connections.
+/// Nested changes keep the list of the changed fields in `value`.
+#[derive(Deserialize, Debug)]
+pub struct NestedChangeField {
+    pub key: String,
+    pub index: Option<usize>,
+    pub value: Value,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct EmoteSetChangeField {
+    pub key: String,
+    pub old_value: Option<PartialEmoteSet>,
+    pub value: Option<PartialEmoteSet>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PartialEmoteSet {
+    pub id: String,
+    pub name: String,
+}
+
 #[derive(Deserialize, Debug)]
 pub struct Hello {
-    pub subscription_limit: u32,
+    pub heartbeat_interval: Option<u64>,
+    pub subscription_limit: i32,
     pub session_id: String,
 }
 
 #[derive(Deserialize, Debug)]
 pub struct Ack {
     pub command: String,
-    pub data: String,
+    pub data: Value,
 }
 
-#[derive(Serialize, Debug)]
+#[derive(Deserialize, Debug)]
+pub struct ResumeAck {
+    pub success: bool,
+    pub dispatches_replayed: u32,
+    pub subscriptions_restored: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
 pub struct Subscribe {
-    #[serde(rename(serialize = "type"))]
+    #[serde(rename = "type")]
     pub event_type: String,
     pub condition: SubscribeCondition,
 }
 
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct SubscribeCondition {
     pub object_id: String,
 }
@@ -0,0 +1,417 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use futures::{SinkExt, StreamExt};
+use reqwest::Url;
+use serde_json::{json, Value};
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::broadcast,
+};
+use tokio_tungstenite::{accept_async, tungstenite::Message, WebSocketStream};
+
+use crate::shared_variables::SEVENTV_MAX_RECONNECT_DELAY_SEC;
+
+use super::{reconnect_delay, schema::Payload, EventAPISupervisor, Subscription, SupervisorEvent};
+
+#[derive(Default)]
+struct MockState {
+    subscription_limit: i32,
+    is_resumable: bool,
+    /// The payloads received by every connection in the order the connections were opened.
+    received: Vec<Vec<Payload<Value>>>,
+    /// The acknowledged subscriptions of every session.
+    sessions: HashMap<String, Vec<Value>>,
+}
+
+/// A local EventAPI server that acknowledges every command it receives.
+struct MockEventAPI {
+    url: Url,
+    state: Arc<Mutex<MockState>>,
+    close_sender: broadcast::Sender<()>,
+}
+
+impl MockEventAPI {
+    async fn start(subscription_limit: i32, is_resumable: bool) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(format!("ws://{}", listener.local_addr().unwrap()).as_str()).unwrap();
+
+        let state = Arc::new(Mutex::new(MockState {
+            subscription_limit,
+            is_resumable,
+            ..Default::default()
+        }));
+        let (close_sender, _) = broadcast::channel(1);
+
+        tokio::spawn({
+            let state = state.clone();
+            let close_sender = close_sender.clone();
+
+            async move {
+                while let Ok((stream, _)) = listener.accept().await {
+                    let index = {
+                        let mut state = state.lock().unwrap();
+                        state.received.push(Vec::new());
+                        state.received.len() - 1
+                    };
+
+                    tokio::spawn(handle_connection(
+                        stream,
+                        index,
+                        state.clone(),
+                        close_sender.subscribe(),
+                    ));
+                }
+            }
+        });
+
+        Self {
+            url,
+            state,
+            close_sender,
+        }
+    }
+
+    /// Closes all the open connections, as the host does when it restarts.
+    fn close_connections(&self) {
+        let _ = self.close_sender.send(());
+    }
+
+    fn connection_count(&self) -> usize {
+        self.state.lock().unwrap().received.len()
+    }
+
+    fn received(&self, connection: usize) -> Vec<(u32, Value)> {
+        self.state.lock().unwrap().received[connection]
+            .iter()
+            .map(|x| (x.op, x.d.clone()))
+            .collect()
+    }
+
+    fn received_ops(&self, connection: usize) -> Vec<u32> {
+        self.received(connection).into_iter().map(|x| x.0).collect()
+    }
+}
+
+async fn handle_connection(
+    stream: TcpStream,
+    index: usize,
+    state: Arc<Mutex<MockState>>,
+    mut close_receiver: broadcast::Receiver<()>,
+) {
+    let mut socket = accept_async(stream).await.unwrap();
+    let session_id = format!("session-{}", index);
+
+    let subscription_limit = state.lock().unwrap().subscription_limit;
+
+    send(
+        &mut socket,
+        1,
+        json!({
+            "heartbeat_interval": 45000,
+            "subscription_limit": subscription_limit,
+            "session_id": session_id,
+        }),
+    )
+    .await;
+
+    loop {
+        let message = tokio::select! {
+            v = socket.next() => v,
+            _ = close_receiver.recv() => {
+                let _ = socket.close(None).await;
+                return;
+            }
+        };
+
+        let payload = match message {
+            Some(Ok(Message::Text(s))) => {
+                serde_json::from_str::<Payload<Value>>(s.as_str()).unwrap()
+            }
+            Some(Ok(_)) => continue,
+            _ => return,
+        };
+
+        let reply = {
+            let mut state = state.lock().unwrap();
+
+            state.received[index].push(Payload {
+                op: payload.op,
+                d: payload.d.clone(),
+            });
+
+            match payload.op {
+                // Resume
+                34 => {
+                    let old_session_id = payload.d["session_id"].as_str().unwrap_or_default();
+
+                    let restored = match state.sessions.get(old_session_id) {
+                        Some(v) if state.is_resumable => Some(v.clone()),
+                        _ => None,
+                    };
+
+                    let reply = json!({
+                        "command": "RESUME",
+                        "data": {
+                            "success": restored.is_some(),
+                            "dispatches_replayed": 0,
+                            "subscriptions_restored": restored.as_ref().map_or(0, |x| x.len()),
+                        },
+                    });
+
+                    if let Some(v) = restored {
+                        state.sessions.insert(session_id.clone(), v);
+                    }
+
+                    Some(reply)
+                }
+                // Subscribe
+                35 => {
+                    state
+                        .sessions
+                        .entry(session_id.clone())
+                        .or_default()
+                        .push(payload.d.clone());
+
+                    Some(json!({ "command": "SUBSCRIBE", "data": payload.d }))
+                }
+                // Unsubscribe
+                36 => {
+                    if let Some(v) = state.sessions.get_mut(&session_id) {
+                        v.retain(|x| x.ne(&payload.d));
+                    }
+
+                    Some(json!({ "command": "UNSUBSCRIBE", "data": payload.d }))
+                }
+                _ => None,
+            }
+        };
+
+        if let Some(d) = reply {
+            send(&mut socket, 5, d).await;
+        }
+    }
+}
+
+async fn send(socket: &mut WebSocketStream<TcpStream>, op: u32, d: Value) {
+    let message = serde_json::to_string(&Payload { op, d }).unwrap();
+    socket.send(Message::Text(message)).await.unwrap();
+}
+
+/// Lets the supervisor handle its connections until the condition is met.
+async fn run_until(
+    supervisor: &mut EventAPISupervisor,
+    condition: impl Fn(&EventAPISupervisor) -> bool,
+) {
+    let mut interval = tokio::time::interval(Duration::from_millis(10));
+
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while !condition(supervisor) {
+            if let Some(SupervisorEvent::Tick) = supervisor.next_event(&mut interval).await {
+                supervisor.process_shards().await;
+            }
+        }
+    })
+    .await
+    .expect("The supervisor has not reached the expected state in time");
+}
+
+fn subscription(object_id: &str) -> Subscription {
+    Subscription {
+        event_type: "emote_set.update".to_string(),
+        object_id: object_id.to_string(),
+    }
+}
+
+fn subscription_value(object_id: &str) -> Value {
+    json!({
+        "type": "emote_set.update",
+        "condition": { "object_id": object_id },
+    })
+}
+
+#[tokio::test]
+async fn subscriptions_over_the_limit_are_moved_to_a_new_connection() {
+    let server = MockEventAPI::start(1, true).await;
+    let mut supervisor = EventAPISupervisor::new(server.url.clone());
+
+    supervisor
+        .subscribe("emote_set.update", "a".to_string())
+        .await;
+    supervisor
+        .subscribe("emote_set.update", "b".to_string())
+        .await;
+
+    run_until(&mut supervisor, |x| {
+        x.shards.len() == 2
+            && x.shards
+                .iter()
+                .all(|y| y.is_ready && y.confirmed_subscriptions.len() == 1)
+    })
+    .await;
+
+    assert_eq!(server.connection_count(), 2);
+    assert!(supervisor
+        .shards
+        .iter()
+        .all(|x| x.subscription_limit == Some(1)));
+
+    let mut object_ids = Vec::new();
+
+    for connection in 0..2 {
+        let received = server.received(connection);
+
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].0, 35);
+
+        object_ids.push(received[0].1["condition"]["object_id"].clone());
+    }
+
+    object_ids.sort_by_key(|x| x.to_string());
+    assert_eq!(object_ids, vec![json!("a"), json!("b")]);
+}
+
+#[tokio::test]
+async fn acks_confirm_and_remove_subscriptions() {
+    let server = MockEventAPI::start(-1, true).await;
+    let mut supervisor = EventAPISupervisor::new(server.url.clone());
+
+    supervisor
+        .subscribe("emote_set.update", "a".to_string())
+        .await;
+
+    run_until(&mut supervisor, |x| {
+        x.shards[0]
+            .confirmed_subscriptions
+            .contains(&subscription("a"))
+    })
+    .await;
+
+    // a negative limit means there is no limit
+    assert_eq!(supervisor.shards[0].subscription_limit, None);
+    assert!(!supervisor.shards[0].is_full());
+
+    supervisor
+        .unsubscribe("emote_set.update", "a".to_string())
+        .await;
+
+    run_until(&mut supervisor, |x| {
+        x.shards[0].confirmed_subscriptions.is_empty()
+    })
+    .await;
+
+    assert!(supervisor.shards[0].subscriptions.is_empty());
+    assert_eq!(
+        server.received(0),
+        vec![(35, subscription_value("a")), (36, subscription_value("a"))]
+    );
+}
+
+#[tokio::test]
+async fn session_is_resumed_after_reconnecting() {
+    let server = MockEventAPI::start(-1, true).await;
+    let mut supervisor = EventAPISupervisor::new(server.url.clone());
+
+    supervisor
+        .subscribe("emote_set.update", "a".to_string())
+        .await;
+
+    run_until(&mut supervisor, |x| {
+        x.shards[0].confirmed_subscriptions.len() == 1
+    })
+    .await;
+
+    server.close_connections();
+
+    run_until(&mut supervisor, |x| {
+        server.connection_count() == 2 && x.shards[0].is_ready
+    })
+    .await;
+
+    let shard = &supervisor.shards[0];
+
+    assert_eq!(shard.session_id.as_deref(), Some("session-1"));
+    assert_eq!(shard.attempts, 0);
+    assert!(shard.confirmed_subscriptions.contains(&subscription("a")));
+
+    // the restored subscriptions are not sent again
+    assert_eq!(
+        server.received(1),
+        vec![(34, json!({ "session_id": "session-0" }))]
+    );
+}
+
+#[tokio::test]
+async fn subscriptions_are_sent_again_if_resume_fails() {
+    let server = MockEventAPI::start(-1, false).await;
+    let mut supervisor = EventAPISupervisor::new(server.url.clone());
+
+    supervisor
+        .subscribe("emote_set.update", "a".to_string())
+        .await;
+
+    run_until(&mut supervisor, |x| {
+        x.shards[0].confirmed_subscriptions.len() == 1
+    })
+    .await;
+
+    server.close_connections();
+
+    run_until(&mut supervisor, |x| {
+        server.connection_count() == 2
+            && x.shards[0].is_ready
+            && x.shards[0].confirmed_subscriptions.len() == 1
+    })
+    .await;
+
+    assert_eq!(server.received_ops(1), vec![34, 35]);
+    assert_eq!(server.received(1)[1].1, subscription_value("a"));
+}
+
+#[test]
+fn reconnect_delay_grows_exponentially() {
+    assert_eq!(reconnect_delay(0), Duration::ZERO);
+    assert_eq!(reconnect_delay(1), Duration::from_secs(1));
+    assert_eq!(reconnect_delay(2), Duration::from_secs(2));
+    assert_eq!(reconnect_delay(3), Duration::from_secs(4));
+    assert_eq!(reconnect_delay(6), Duration::from_secs(32));
+    assert_eq!(
+        reconnect_delay(64),
+        Duration::from_secs(SEVENTV_MAX_RECONNECT_DELAY_SEC)
+    );
+}
+
+#[tokio::test]
+async fn failed_connections_are_retried_with_backoff() {
+    // the port is free again once the listener is dropped
+    let url = {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        Url::parse(format!("ws://{}", listener.local_addr().unwrap()).as_str()).unwrap()
+    };
+
+    let mut supervisor = EventAPISupervisor::new(url);
+
+    supervisor.process_shards().await;
+
+    let shard = &supervisor.shards[0];
+    let reconnect_at = shard.reconnect_at;
+
+    assert!(shard.socket.is_none());
+    assert_eq!(shard.attempts, 1);
+    assert!(reconnect_at >= Instant::now() + Duration::from_millis(900));
+
+    // the next attempt waits for the delay to pass
+    supervisor.process_shards().await;
+
+    assert_eq!(supervisor.shards[0].attempts, 1);
+    assert_eq!(supervisor.shards[0].reconnect_at, reconnect_at);
+
+    supervisor.shards[0].reconnect_at = Instant::now();
+    supervisor.process_shards().await;
+
+    assert_eq!(supervisor.shards[0].attempts, 2);
+    assert!(supervisor.shards[0].reconnect_at >= Instant::now() + Duration::from_millis(1900));
+}
@@ -17,11 +17,54 @@ pub const DEFAULT_PREFIX: &str = "~";
 pub const DEFAULT_LANGUAGE: &str = "english";
 
 pub const HOLIDAY_V1_API_URL: &str = "https://hol.ilotterytea.kz/api/v1";
+pub const TWITCH_HELIX_API_URL: &str = "https://api.twitch.tv/helix";
+pub const TWITCH_OAUTH2_API_URL: &str = "https://id.twitch.tv/oauth2";
 pub const SEVENTV_WEBSOCKET_URL: &str = "wss://events.7tv.io/v3";
+pub const BTTV_WEBSOCKET_URL: &str = "wss://sockets.betterttv.net/ws";
 pub const IVR_API_V2_URL: &str = "https://api.ivr.fi/v2";
 pub const MCSRV_API_URL: &str = "https://api.mcsrvstat.us/3";
 
+pub const HTTP_DEFAULT_TIMEOUT_SEC: u64 = 5;
+pub const HTTP_MAX_RETRIES: u32 = 3;
+pub const HTTP_RETRY_BASE_DELAY_MS: u64 = 500;
+pub const MCSRV_TIMEOUT_SEC: u64 = 10;
+pub const MCSRV_CACHE_TTL_SEC: u64 = 60;
+pub const HOLIDAY_CACHE_TTL_SEC: u64 = 60 * 60;
+pub const STATS_CACHE_TTL_SEC: u64 = 60;
+
 pub const TIMER_CHECK_DELAY: u64 = 1;
+pub const CHANNEL_UPDATE_CHECK_DELAY: u64 = 1;
+pub const CHANNEL_SYNC_DELAY: u64 = 60;
+pub const EVENT_TRIGGER_CHECK_DELAY: u64 = 1;
+pub const EVENT_REMINDER_OFFSETS_SEC: [i64; 4] = [60 * 60, 10 * 60, 5 * 60, 60];
+
+pub const WHISPER_LIMIT_PER_SECOND: usize = 3;
+pub const WHISPER_LIMIT_PER_MINUTE: usize = 100;
+pub const DIGEST_CHECK_DELAY: u64 = 60;
+pub const FFZ_UPDATE_CHECK_DELAY: u64 = 60;
+pub const EMOTE_USAGE_FLUSH_DELAY: u64 = 30;
+pub const EMOTE_CACHE_TTL_SEC: u64 = 10 * 60;
+pub const EMOTE_CACHE_REFRESH_DELAY: u64 = 5;
+pub const SEVENTV_RESOLVE_RETRY_DELAY: u64 = 10 * 60;
+pub const SEVENTV_DEFAULT_HEARTBEAT_INTERVAL_SEC: u64 = 45;
+pub const SEVENTV_MAX_RECONNECT_DELAY_SEC: u64 = 5 * 60;
+pub const DIGEST_INTERVAL_SEC: i64 = 60 * 60;
+
+pub const NOTIFY_LIST_PAGE_SIZE: usize = 10;
+
+pub const ACTIVE_CHATTERS_DEFAULT_WINDOW_SEC: u64 = 30 * 60;
+pub const ACTIVE_CHATTERS_MAX_WINDOW_SEC: u64 = 24 * 60 * 60;
+
+pub const SPAM_MAX_DELAY_SEC: u64 = 60;
+
+pub const EMOTE_HISTORY_DEFAULT_AMOUNT: i64 = 5;
+pub const EMOTE_HISTORY_MAX_AMOUNT: i64 = 15;
+
+pub const BANNED_PHRASE_CACHE_TTL_SEC: u64 = 60;
+pub const BANNED_PHRASE_OFFENSE_WINDOW_SEC: i64 = 7 * 24 * 60 * 60;
+pub const BANNED_PHRASE_DEFAULT_TIMEOUT_SEC: i32 = 60;
+pub const BANNED_PHRASE_ESCALATION_FACTOR: i64 = 5;
+pub const BANNED_PHRASE_MAX_TIMEOUT_SEC: i64 = 14 * 24 * 60 * 60;
 
 pub const COMPILE_TIMESTAMP: i32 = compile_time::unix!();
 pub const COMPILE_VERSION: &str = compile_time::rustc_version_str!();
@@ -0,0 +1,150 @@
+use std::str::FromStr;
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, Timelike};
+
+#[cfg(test)]
+mod tests;
+
+/// A five-field cron expression: minute, hour, day of month, month and day of week.
+/// Every field supports `*`, numbers, lists (`1,2`), ranges (`1-5`) and steps (`*/15`).
+pub struct CronSchedule {
+    minutes: Vec<u32>,
+    hours: Vec<u32>,
+    days_of_month: Vec<u32>,
+    months: Vec<u32>,
+    days_of_week: Vec<u32>,
+
+    is_day_of_month_restricted: bool,
+    is_day_of_week_restricted: bool,
+}
+
+impl FromStr for CronSchedule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields = s.split_ascii_whitespace().collect::<Vec<&str>>();
+
+        if fields.len() != 5 {
+            return Err("Cron expression must have 5 fields".to_string());
+        }
+
+        let parse = |field: &str, min: u32, max: u32| {
+            parse_field(field, min, max).ok_or(format!("Failed to parse cron field \"{}\"", field))
+        };
+
+        let mut days_of_week = parse(fields[4], 0, 7)?;
+
+        // both 0 and 7 stand for Sunday
+        if days_of_week.contains(&7) {
+            days_of_week.retain(|x| *x != 7);
+
+            if !days_of_week.contains(&0) {
+                days_of_week.insert(0, 0);
+            }
+        }
+
+        Ok(Self {
+            minutes: parse(fields[0], 0, 59)?,
+            hours: parse(fields[1], 0, 23)?,
+            days_of_month: parse(fields[2], 1, 31)?,
+            months: parse(fields[3], 1, 12)?,
+            days_of_week,
+            is_day_of_month_restricted: fields[2] != "*",
+            is_day_of_week_restricted: fields[4] != "*",
+        })
+    }
+}
+
+impl CronSchedule {
+    /// Returns the first matching minute strictly after the timestamp,
+    /// looking no further than a year ahead.
+    pub fn next_after(&self, timestamp: NaiveDateTime) -> Option<NaiveDateTime> {
+        let mut t = timestamp.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let limit = timestamp + Duration::days(366);
+
+        while t <= limit {
+            if !self.months.contains(&t.month()) {
+                let (year, month) = if t.month() == 12 {
+                    (t.year() + 1, 1)
+                } else {
+                    (t.year(), t.month() + 1)
+                };
+
+                t = NaiveDate::from_ymd_opt(year, month, 1)?.and_hms_opt(0, 0, 0)?;
+                continue;
+            }
+
+            if !self.matches_day(&t) {
+                t = (t.date() + Duration::days(1)).and_hms_opt(0, 0, 0)?;
+                continue;
+            }
+
+            if !self.hours.contains(&t.hour()) {
+                t = t.with_minute(0)? + Duration::hours(1);
+                continue;
+            }
+
+            if !self.minutes.contains(&t.minute()) {
+                t += Duration::minutes(1);
+                continue;
+            }
+
+            return Some(t);
+        }
+
+        None
+    }
+
+    fn matches_day(&self, t: &NaiveDateTime) -> bool {
+        let dom = self.days_of_month.contains(&t.day());
+        let dow = self
+            .days_of_week
+            .contains(&t.weekday().num_days_from_sunday());
+
+        // cron matches either day field if both of them are restricted
+        match (
+            self.is_day_of_month_restricted,
+            self.is_day_of_week_restricted,
+        ) {
+            (true, true) => dom || dow,
+            (true, false) => dom,
+            (false, true) => dow,
+            (false, false) => true,
+        }
+    }
+}
+
+fn parse_field(field: &str, min: u32, max: u32) -> Option<Vec<u32>> {
+    let mut values: Vec<u32> = Vec::new();
+
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().ok()?),
+            None => (part, 1),
+        };
+
+        if step == 0 {
+            return None;
+        }
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (start.parse::<u32>().ok()?, end.parse::<u32>().ok()?)
+        } else {
+            let value = range.parse::<u32>().ok()?;
+            (value, if part.contains('/') { max } else { value })
+        };
+
+        if start < min || end > max || start > end {
+            return None;
+        }
+
+        values.extend((start..=end).step_by(step as usize));
+    }
+
+    values.sort();
+    values.dedup();
+
+    Some(values)
+}
@@ -0,0 +1,116 @@
+use std::str::FromStr;
+
+use chrono::{NaiveDate, NaiveDateTime};
+
+use super::{parse_field, CronSchedule};
+
+fn timestamp(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(year, month, day)
+        .unwrap()
+        .and_hms_opt(hour, minute, 0)
+        .unwrap()
+}
+
+fn next_after(expression: &str, timestamp: NaiveDateTime) -> Option<NaiveDateTime> {
+    CronSchedule::from_str(expression)
+        .unwrap()
+        .next_after(timestamp)
+}
+
+#[test]
+fn fields_are_parsed() {
+    assert_eq!(parse_field("*", 0, 5), Some(vec![0, 1, 2, 3, 4, 5]));
+    assert_eq!(parse_field("7", 0, 59), Some(vec![7]));
+    assert_eq!(parse_field("3,1,3", 0, 59), Some(vec![1, 3]));
+    assert_eq!(parse_field("1-5", 0, 59), Some(vec![1, 2, 3, 4, 5]));
+    assert_eq!(parse_field("*/15", 0, 59), Some(vec![0, 15, 30, 45]));
+    assert_eq!(parse_field("10-20/5", 0, 59), Some(vec![10, 15, 20]));
+    assert_eq!(parse_field("1-2,50/5", 0, 59), Some(vec![1, 2, 50, 55]));
+}
+
+#[test]
+fn invalid_fields_are_rejected() {
+    assert_eq!(parse_field("", 0, 59), None);
+    assert_eq!(parse_field("a", 0, 59), None);
+    assert_eq!(parse_field("60", 0, 59), None);
+    assert_eq!(parse_field("0", 1, 31), None);
+    assert_eq!(parse_field("5-1", 0, 59), None);
+    assert_eq!(parse_field("*/0", 0, 59), None);
+    assert_eq!(parse_field("1,", 0, 59), None);
+}
+
+#[test]
+fn expressions_must_have_five_valid_fields() {
+    assert!(CronSchedule::from_str("0 18 * *").is_err());
+    assert!(CronSchedule::from_str("0 18 * * 5 1").is_err());
+    assert!(CronSchedule::from_str("0 24 * * *").is_err());
+    assert!(CronSchedule::from_str("0 18 * * 5").is_ok());
+}
+
+#[test]
+fn next_occurrence_is_strictly_after_the_timestamp() {
+    let t = timestamp(2024, 4, 1, 12, 15);
+
+    assert_eq!(
+        next_after("*/15 * * * *", t),
+        Some(timestamp(2024, 4, 1, 12, 30))
+    );
+    assert_eq!(
+        next_after("*/15 * * * *", t + chrono::Duration::seconds(30)),
+        Some(timestamp(2024, 4, 1, 12, 30))
+    );
+    assert_eq!(
+        next_after("0 * * * *", timestamp(2024, 4, 1, 23, 59)),
+        Some(timestamp(2024, 4, 2, 0, 0))
+    );
+}
+
+#[test]
+fn next_occurrence_skips_to_matching_days_and_months() {
+    // 2024-04-01 is a Monday
+    let t = timestamp(2024, 4, 1, 12, 0);
+
+    assert_eq!(
+        next_after("0 18 * * 5", t),
+        Some(timestamp(2024, 4, 5, 18, 0))
+    );
+    assert_eq!(
+        next_after("0 0 1 1 *", t),
+        Some(timestamp(2025, 1, 1, 0, 0))
+    );
+    assert_eq!(next_after("30 9 29 2 *", t), None);
+    assert_eq!(
+        next_after("30 23 31 12 *", timestamp(2024, 12, 31, 23, 30)),
+        Some(timestamp(2025, 12, 31, 23, 30))
+    );
+}
+
+#[test]
+fn sunday_is_both_zero_and_seven() {
+    let t = timestamp(2024, 4, 1, 12, 0);
+
+    assert_eq!(
+        next_after("0 0 * * 0", t),
+        Some(timestamp(2024, 4, 7, 0, 0))
+    );
+    assert_eq!(
+        next_after("0 0 * * 7", t),
+        Some(timestamp(2024, 4, 7, 0, 0))
+    );
+}
+
+#[test]
+fn restricted_day_fields_match_either_day() {
+    let t = timestamp(2024, 4, 1, 12, 0);
+
+    // the Friday comes before the 13th
+    assert_eq!(
+        next_after("0 0 13 * 5", t),
+        Some(timestamp(2024, 4, 5, 0, 0))
+    );
+    // only the day of month is restricted
+    assert_eq!(
+        next_after("0 0 13 * *", t),
+        Some(timestamp(2024, 4, 13, 0, 0))
+    );
+}
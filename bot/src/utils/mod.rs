@@ -1,3 +1,8 @@
+pub mod cron;
+
+#[cfg(test)]
+mod tests;
+
 pub fn format_timestamp(timestamp_in_seconds: u64) -> String {
     let timestamp_as_f64 = timestamp_in_seconds as f64;
     let days = (timestamp_as_f64 / (60.0 * 60.0 * 24.0)).trunc();
@@ -16,6 +21,45 @@ pub fn format_timestamp(timestamp_in_seconds: u64) -> String {
     }
 }
 
+/// Parses a duration like `1d2h30m` or `45s` into seconds.
+/// A number without a unit is treated as seconds.
+/// Returns nothing if the duration doesn't fit into `u64`.
+pub fn parse_duration(value: &str) -> Option<u64> {
+    if value.is_empty() {
+        return None;
+    }
+
+    let mut total: u64 = 0;
+    let mut buffer = String::new();
+
+    for c in value.chars() {
+        if c.is_ascii_digit() {
+            buffer.push(c);
+            continue;
+        }
+
+        let amount = buffer.parse::<u64>().ok()?;
+        buffer.clear();
+
+        let multiplier = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 60 * 60,
+            'd' => 60 * 60 * 24,
+            'w' => 60 * 60 * 24 * 7,
+            _ => return None,
+        };
+
+        total = total.checked_add(amount.checked_mul(multiplier)?)?;
+    }
+
+    if !buffer.is_empty() {
+        total = total.checked_add(buffer.parse::<u64>().ok()?)?;
+    }
+
+    Some(total)
+}
+
 pub fn split_and_wrap_lines(
     init_string: &str,
     separator: &str,
@@ -0,0 +1,38 @@
+use super::parse_duration;
+
+#[test]
+fn durations_are_parsed_into_seconds() {
+    assert_eq!(parse_duration("45s"), Some(45));
+    assert_eq!(parse_duration("30m"), Some(30 * 60));
+    assert_eq!(parse_duration("2h"), Some(2 * 60 * 60));
+    assert_eq!(parse_duration("1w"), Some(7 * 24 * 60 * 60));
+    assert_eq!(
+        parse_duration("1d2h30m"),
+        Some(24 * 60 * 60 + 2 * 60 * 60 + 30 * 60)
+    );
+    assert_eq!(parse_duration("2m2m"), Some(4 * 60));
+}
+
+#[test]
+fn numbers_without_a_unit_are_seconds() {
+    assert_eq!(parse_duration("90"), Some(90));
+    assert_eq!(parse_duration("1h30"), Some(60 * 60 + 30));
+    assert_eq!(parse_duration("0"), Some(0));
+}
+
+#[test]
+fn invalid_durations_are_rejected() {
+    assert_eq!(parse_duration(""), None);
+    assert_eq!(parse_duration("h"), None);
+    assert_eq!(parse_duration("1x"), None);
+    assert_eq!(parse_duration("1h 30m"), None);
+    assert_eq!(parse_duration("-5s"), None);
+}
+
+#[test]
+fn overflowing_durations_are_rejected() {
+    assert_eq!(parse_duration("18446744073709551615"), Some(u64::MAX));
+    assert_eq!(parse_duration("18446744073709551616"), None);
+    assert_eq!(parse_duration("40000000000000w"), None);
+    assert_eq!(parse_duration("18446744073709551615s1s"), None);
+}
@@ -0,0 +1,88 @@
+use std::{
+    collections::VecDeque,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use log::{error, info};
+use twitch_api::helix::whispers::{SendWhisperBody, SendWhisperRequest};
+
+use crate::{
+    instance_bundle::InstanceBundle,
+    shared_variables::{WHISPER_LIMIT_PER_MINUTE, WHISPER_LIMIT_PER_SECOND},
+};
+
+pub struct TwitchWhisperHelper {
+    bundle: Arc<InstanceBundle>,
+
+    sent_timestamps: VecDeque<Instant>,
+}
+
+impl TwitchWhisperHelper {
+    pub fn new(bundle: Arc<InstanceBundle>) -> Self {
+        Self {
+            bundle,
+            sent_timestamps: VecDeque::new(),
+        }
+    }
+
+    pub async fn run(&mut self) {
+        info!("Starting to send whispers...");
+
+        loop {
+            self.process_whisper_queue().await;
+            tokio::time::sleep(Duration::from_millis(250)).await;
+        }
+    }
+
+    fn is_rate_limited(&mut self) -> bool {
+        let now = Instant::now();
+
+        while let Some(timestamp) = self.sent_timestamps.front() {
+            if now.duration_since(*timestamp) > Duration::from_secs(60) {
+                self.sent_timestamps.pop_front();
+            } else {
+                break;
+            }
+        }
+
+        let sent_last_second = self
+            .sent_timestamps
+            .iter()
+            .filter(|x| now.duration_since(**x) <= Duration::from_secs(1))
+            .count();
+
+        sent_last_second >= WHISPER_LIMIT_PER_SECOND
+            || self.sent_timestamps.len() >= WHISPER_LIMIT_PER_MINUTE
+    }
+
+    async fn process_whisper_queue(&mut self) {
+        while !self.is_rate_limited() {
+            let mut queue = self.bundle.twitch_whisper_queue.lock().await;
+
+            let (user_id, message) = match queue.pop_front() {
+                Some(v) => v,
+                None => return,
+            };
+
+            drop(queue);
+
+            let request = SendWhisperRequest::new(
+                self.bundle.twitch_api_token.user_id.clone(),
+                user_id.clone(),
+            );
+            let body = SendWhisperBody::new(message);
+
+            self.sent_timestamps.push_back(Instant::now());
+
+            if let Err(e) = self
+                .bundle
+                .twitch_api_client
+                .req_post(request, body, &*self.bundle.twitch_api_token)
+                .await
+            {
+                error!("Failed to send a whisper to user ID {}: {:?}", user_id, e);
+            }
+        }
+    }
+}
@@ -0,0 +1,13 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE "events"
+DROP COLUMN "announcement_color",
+DROP COLUMN "is_enabled";
+
+UPDATE "events" SET "flags" = ARRAY(SELECT f FROM unnest("flags") AS f WHERE f = 'massping');
+
+ALTER TABLE "events" ALTER COLUMN "flags" DROP DEFAULT;
+ALTER TYPE "event_flag" RENAME TO "event_flag_old";
+CREATE TYPE "event_flag" AS ENUM ('massping');
+ALTER TABLE "events" ALTER COLUMN "flags" TYPE event_flag[] USING "flags"::text[]::event_flag[];
+ALTER TABLE "events" ALTER COLUMN "flags" SET DEFAULT ARRAY[]::event_flag[];
+DROP TYPE "event_flag_old";
@@ -0,0 +1,10 @@
+-- Your SQL goes here
+ALTER TYPE "event_flag" ADD VALUE IF NOT EXISTS 'silent';
+ALTER TYPE "event_flag" ADD VALUE IF NOT EXISTS 'announce';
+ALTER TYPE "event_flag" ADD VALUE IF NOT EXISTS 'me';
+ALTER TYPE "event_flag" ADD VALUE IF NOT EXISTS 'no_mentions';
+ALTER TYPE "event_flag" ADD VALUE IF NOT EXISTS 'once';
+
+ALTER TABLE "events"
+ADD COLUMN "announcement_color" VARCHAR,
+ADD COLUMN "is_enabled" BOOLEAN NOT NULL DEFAULT TRUE;
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE "digest_notifications";
+DROP TABLE "user_preferences";
+DROP TYPE "notification_delivery";
@@ -0,0 +1,15 @@
+-- Your SQL goes here
+CREATE TYPE "notification_delivery" AS ENUM ('chat', 'whisper', 'digest');
+
+CREATE TABLE IF NOT EXISTS "user_preferences" (
+  "id" SERIAL NOT NULL PRIMARY KEY,
+  "user_id" INTEGER NOT NULL UNIQUE REFERENCES "users"("id"),
+  "notification_delivery" notification_delivery NOT NULL DEFAULT 'chat'
+);
+
+CREATE TABLE IF NOT EXISTS "digest_notifications" (
+  "id" SERIAL NOT NULL PRIMARY KEY,
+  "user_id" INTEGER NOT NULL REFERENCES "users"("id"),
+  "message" VARCHAR NOT NULL,
+  "created_at" TIMESTAMP NOT NULL DEFAULT timezone('utc', now())
+);
@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE "user_preferences"
+DROP COLUMN "time_zone",
+DROP COLUMN "quiet_hours_start",
+DROP COLUMN "quiet_hours_end",
+DROP COLUMN "is_muted";
@@ -0,0 +1,6 @@
+-- Your SQL goes here
+ALTER TABLE "user_preferences"
+ADD COLUMN "time_zone" VARCHAR NOT NULL DEFAULT 'UTC',
+ADD COLUMN "quiet_hours_start" INTEGER,
+ADD COLUMN "quiet_hours_end" INTEGER,
+ADD COLUMN "is_muted" BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE "event_subscriptions"
+DROP COLUMN "channel_id";
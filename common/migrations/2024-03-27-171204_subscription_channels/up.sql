@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE "event_subscriptions"
+ADD COLUMN "channel_id" INTEGER REFERENCES "channels"("id");
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE "events"
+DROP COLUMN "scheduled_at",
+DROP COLUMN "schedule_cron",
+DROP COLUMN "reminded_at";
@@ -0,0 +1,5 @@
+-- Your SQL goes here
+ALTER TABLE "events"
+ADD COLUMN "scheduled_at" TIMESTAMP,
+ADD COLUMN "schedule_cron" VARCHAR,
+ADD COLUMN "reminded_at" TIMESTAMP;
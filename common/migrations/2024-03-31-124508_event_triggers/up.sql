@@ -0,0 +1,8 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS "event_triggers" (
+  "id" SERIAL NOT NULL PRIMARY KEY,
+  "event_id" INTEGER NOT NULL REFERENCES "events"("id") ON DELETE CASCADE,
+  "user_id" INTEGER NOT NULL REFERENCES "users"("id"),
+  "parameters" TEXT[] NOT NULL DEFAULT ARRAY[]::VARCHAR[],
+  "created_at" TIMESTAMP NOT NULL DEFAULT timezone('utc', now())
+);
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE IF EXISTS "event_runs";
+DROP TYPE IF EXISTS "event_run_source";
@@ -0,0 +1,12 @@
+-- Your SQL goes here
+CREATE TYPE "event_run_source" AS ENUM ('stream', 'command', 'schedule', 'api');
+
+CREATE TABLE IF NOT EXISTS "event_runs" (
+  "id" SERIAL NOT NULL PRIMARY KEY,
+  "event_id" INTEGER NOT NULL REFERENCES "events"("id") ON DELETE CASCADE,
+  "source" event_run_source NOT NULL,
+  "parameters" TEXT[] NOT NULL DEFAULT ARRAY[]::VARCHAR[],
+  "notified_count" INTEGER NOT NULL DEFAULT 0,
+  "message_ids" TEXT[] NOT NULL DEFAULT ARRAY[]::VARCHAR[],
+  "created_at" TIMESTAMP NOT NULL DEFAULT timezone('utc', now())
+);
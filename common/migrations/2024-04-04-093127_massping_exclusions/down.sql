@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE "user_preferences"
+DROP COLUMN "is_massping_opted_out";
+
+ALTER TABLE "channel_preferences"
+DROP COLUMN "massping_exclusions";
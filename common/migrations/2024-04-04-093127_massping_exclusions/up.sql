@@ -0,0 +1,9 @@
+-- Your SQL goes here
+ALTER TABLE "user_preferences"
+ADD COLUMN "is_massping_opted_out" BOOLEAN NOT NULL DEFAULT FALSE;
+
+ALTER TABLE "channel_preferences"
+ADD COLUMN "massping_exclusions" TEXT[] NOT NULL DEFAULT ARRAY[
+  'nightbot', 'streamelements', 'streamlabs', 'moobot', 'fossabot',
+  'wizebot', 'soundalerts', 'sery_bot', 'commanderroot', 'supibot'
+]::VARCHAR[];
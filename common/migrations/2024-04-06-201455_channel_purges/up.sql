@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE "channels"
+ADD COLUMN "purge_at" TIMESTAMP;
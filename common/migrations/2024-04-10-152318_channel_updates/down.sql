@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE IF EXISTS "channel_updates";
+DROP TYPE IF EXISTS "channel_update_action";
@@ -0,0 +1,9 @@
+-- Your SQL goes here
+CREATE TYPE "channel_update_action" AS ENUM ('join', 'part');
+
+CREATE TABLE IF NOT EXISTS "channel_updates" (
+  "id" SERIAL NOT NULL PRIMARY KEY,
+  "channel_id" INTEGER NOT NULL REFERENCES "channels"("id") ON DELETE CASCADE,
+  "action" channel_update_action NOT NULL,
+  "created_at" TIMESTAMP NOT NULL DEFAULT timezone('utc', now())
+);
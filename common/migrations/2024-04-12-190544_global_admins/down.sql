@@ -0,0 +1,15 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE "users" DROP COLUMN IF EXISTS "is_suspended";
+ALTER TABLE "users" DROP COLUMN IF EXISTS "is_admin";
+
+-- enum values cannot be dropped, so the type is recreated without 'admin'
+UPDATE "rights" SET "level" = 'broadcaster' WHERE "level" = 'admin';
+
+ALTER TYPE "level_of_rights" RENAME TO "level_of_rights_old";
+CREATE TYPE "level_of_rights" AS ENUM ('suspended', 'user', 'subscriber', 'vip', 'moderator', 'broadcaster');
+
+ALTER TABLE "rights" ALTER COLUMN "level" DROP DEFAULT;
+ALTER TABLE "rights" ALTER COLUMN "level" TYPE "level_of_rights" USING "level"::TEXT::"level_of_rights";
+ALTER TABLE "rights" ALTER COLUMN "level" SET DEFAULT 'user';
+
+DROP TYPE "level_of_rights_old";
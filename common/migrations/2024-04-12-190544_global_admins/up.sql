@@ -0,0 +1,5 @@
+-- Your SQL goes here
+ALTER TYPE "level_of_rights" ADD VALUE IF NOT EXISTS 'admin';
+
+ALTER TABLE "users" ADD COLUMN "is_admin" BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE "users" ADD COLUMN "is_suspended" BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,12 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS "emote_usages" (
+  "id" SERIAL NOT NULL PRIMARY KEY,
+  "channel_id" INTEGER NOT NULL REFERENCES "channels"("id") ON DELETE CASCADE,
+  "provider" VARCHAR NOT NULL,
+  "emote_id" VARCHAR NOT NULL,
+  "emote_name" VARCHAR NOT NULL,
+  "usage_count" INTEGER NOT NULL DEFAULT 0,
+  "updated_at" TIMESTAMP NOT NULL DEFAULT timezone('utc', now()),
+
+  CONSTRAINT "unique_emote_usage" UNIQUE ("channel_id", "provider", "emote_id")
+);
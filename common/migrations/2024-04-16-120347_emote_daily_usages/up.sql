@@ -0,0 +1,13 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS "emote_daily_usages" (
+  "id" SERIAL NOT NULL PRIMARY KEY,
+  "channel_id" INTEGER NOT NULL REFERENCES "channels"("id") ON DELETE CASCADE,
+  "user_id" INTEGER NOT NULL REFERENCES "users"("id") ON DELETE CASCADE,
+  "provider" VARCHAR NOT NULL,
+  "emote_id" VARCHAR NOT NULL,
+  "emote_name" VARCHAR NOT NULL,
+  "day" DATE NOT NULL DEFAULT timezone('utc', now())::date,
+  "usage_count" INTEGER NOT NULL DEFAULT 0,
+
+  CONSTRAINT "unique_emote_daily_usage" UNIQUE ("channel_id", "user_id", "provider", "emote_id", "day")
+);
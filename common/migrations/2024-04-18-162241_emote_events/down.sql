@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE IF EXISTS "emote_events";
+DROP TYPE IF EXISTS "emote_event_action";
@@ -0,0 +1,16 @@
+-- Your SQL goes here
+CREATE TYPE "emote_event_action" AS ENUM ('push', 'pull', 'update');
+
+CREATE TABLE IF NOT EXISTS "emote_events" (
+  "id" SERIAL NOT NULL PRIMARY KEY,
+  "channel_id" INTEGER NOT NULL REFERENCES "channels"("id") ON DELETE CASCADE,
+  "provider" VARCHAR NOT NULL,
+  "emote_id" VARCHAR NOT NULL,
+  "emote_name" VARCHAR NOT NULL,
+  "old_emote_name" VARCHAR,
+  "action" emote_event_action NOT NULL,
+  "actor_name" VARCHAR,
+  "created_at" TIMESTAMP NOT NULL DEFAULT timezone('utc', now())
+);
+
+CREATE INDEX IF NOT EXISTS "emote_events_channel_id_created_at" ON "emote_events" ("channel_id", "created_at");
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE IF EXISTS "banned_phrase_offenses";
+DROP TABLE IF EXISTS "banned_phrases";
+DROP TYPE IF EXISTS "banned_phrase_action";
+DROP TYPE IF EXISTS "banned_phrase_kind";
@@ -0,0 +1,27 @@
+-- Your SQL goes here
+CREATE TYPE "banned_phrase_kind" AS ENUM ('text', 'wildcard', 'regex');
+CREATE TYPE "banned_phrase_action" AS ENUM ('delete', 'timeout', 'ban');
+
+CREATE TABLE IF NOT EXISTS "banned_phrases" (
+  "id" SERIAL NOT NULL PRIMARY KEY,
+  "channel_id" INTEGER NOT NULL REFERENCES "channels"("id") ON DELETE CASCADE,
+  "pattern" VARCHAR NOT NULL,
+  "kind" banned_phrase_kind NOT NULL DEFAULT 'text',
+  "action" banned_phrase_action NOT NULL DEFAULT 'delete',
+  "timeout_sec" INTEGER,
+  "created_at" TIMESTAMP NOT NULL DEFAULT timezone('utc', now()),
+
+  CONSTRAINT "unique_banned_phrase" UNIQUE ("channel_id", "kind", "pattern")
+);
+
+CREATE TABLE IF NOT EXISTS "banned_phrase_offenses" (
+  "id" SERIAL NOT NULL PRIMARY KEY,
+  "channel_id" INTEGER NOT NULL REFERENCES "channels"("id") ON DELETE CASCADE,
+  "user_id" INTEGER NOT NULL REFERENCES "users"("id") ON DELETE CASCADE,
+  "phrase_id" INTEGER REFERENCES "banned_phrases"("id") ON DELETE SET NULL,
+  "action" banned_phrase_action NOT NULL,
+  "timeout_sec" INTEGER,
+  "created_at" TIMESTAMP NOT NULL DEFAULT timezone('utc', now())
+);
+
+CREATE INDEX IF NOT EXISTS "banned_phrase_offenses_channel_id_user_id" ON "banned_phrase_offenses" ("channel_id", "user_id", "created_at");
@@ -5,6 +5,9 @@ use diesel::{Connection, PgConnection};
 pub mod models;
 pub mod schema;
 
+/// Time before the data of the parted channel is deleted if it was asked to be purged.
+pub const CHANNEL_PURGE_GRACE_PERIOD_SEC: i64 = 7 * 24 * 60 * 60;
+
 pub fn establish_connection() -> PgConnection {
     let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
     PgConnection::establish(&database_url)
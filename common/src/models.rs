@@ -1,8 +1,10 @@
-use std::{fmt::Display, str::FromStr};
+use std::{cmp::Ordering, fmt::Display, str::FromStr};
 
 use crate::schema::*;
-use chrono::NaiveDateTime;
+use chrono::{NaiveDate, NaiveDateTime, TimeZone, Timelike};
+use chrono_tz::Tz;
 use diesel::{Associations, Identifiable, Insertable, Queryable};
+use regex::{Regex, RegexBuilder};
 use serde::Serialize;
 use uuid::Uuid;
 
@@ -13,6 +15,7 @@ pub struct Channel {
     pub alias_name: String,
     pub joined_at: NaiveDateTime,
     pub opt_outed_at: Option<NaiveDateTime>,
+    pub purge_at: Option<NaiveDateTime>,
 }
 
 #[derive(Insertable)]
@@ -31,6 +34,7 @@ pub struct ChannelPreference {
     pub prefix: String,
     pub language: String,
     pub features: Vec<Option<String>>,
+    pub massping_exclusions: Vec<String>,
 }
 
 #[derive(Insertable)]
@@ -41,9 +45,34 @@ pub struct NewChannelPreference {
     pub language: String,
 }
 
+#[derive(Serialize, diesel_derive_enum::DbEnum, Debug, PartialEq, Clone)]
+#[ExistingTypePath = "crate::schema::sql_types::ChannelUpdateAction"]
+pub enum ChannelUpdateAction {
+    Join,
+    Part,
+}
+
+#[derive(Serialize, Queryable, Identifiable, Associations)]
+#[diesel(belongs_to(Channel, foreign_key = channel_id))]
+pub struct ChannelUpdate {
+    pub id: i32,
+    pub channel_id: i32,
+    pub action: ChannelUpdateAction,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = channel_updates)]
+pub struct NewChannelUpdate {
+    pub channel_id: i32,
+    pub action: ChannelUpdateAction,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ChannelFeature {
     Notify7TVUpdates,
+    NotifyBTTVUpdates,
+    NotifyFFZUpdates,
 }
 
 impl FromStr for ChannelFeature {
@@ -51,6 +80,8 @@ impl FromStr for ChannelFeature {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "notify_7tv_updates" => Ok(Self::Notify7TVUpdates),
+            "notify_bttv_updates" => Ok(Self::NotifyBTTVUpdates),
+            "notify_ffz_updates" => Ok(Self::NotifyFFZUpdates),
             _ => Err("failed to serialize a str".to_string()),
         }
     }
@@ -59,7 +90,9 @@ impl FromStr for ChannelFeature {
 impl Display for ChannelFeature {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            &Self::Notify7TVUpdates => write!(f, "notify_7tv_updates"),
+            Self::Notify7TVUpdates => write!(f, "notify_7tv_updates"),
+            Self::NotifyBTTVUpdates => write!(f, "notify_bttv_updates"),
+            Self::NotifyFFZUpdates => write!(f, "notify_ffz_updates"),
         }
     }
 }
@@ -71,6 +104,8 @@ pub struct User {
     pub alias_name: String,
     pub joined_at: NaiveDateTime,
     pub opt_outed_at: Option<NaiveDateTime>,
+    pub is_admin: bool,
+    pub is_suspended: bool,
 }
 
 #[derive(Insertable)]
@@ -200,6 +235,11 @@ impl ToString for EventType {
 #[ExistingTypePath = "crate::schema::sql_types::EventFlag"]
 pub enum EventFlag {
     Massping,
+    Silent,
+    Announce,
+    Me,
+    NoMentions,
+    Once,
 }
 
 impl FromStr for EventFlag {
@@ -208,12 +248,30 @@ impl FromStr for EventFlag {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "massping" => Ok(Self::Massping),
+            "silent" => Ok(Self::Silent),
+            "announce" => Ok(Self::Announce),
+            "me" => Ok(Self::Me),
+            "no_mentions" => Ok(Self::NoMentions),
+            "once" => Ok(Self::Once),
             _ => Err("Failed to parse an event flag".to_string()),
         }
     }
 }
 
-#[derive(Serialize, Queryable, Identifiable, Associations)]
+impl Display for EventFlag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Massping => write!(f, "massping"),
+            Self::Silent => write!(f, "silent"),
+            Self::Announce => write!(f, "announce"),
+            Self::Me => write!(f, "me"),
+            Self::NoMentions => write!(f, "no_mentions"),
+            Self::Once => write!(f, "once"),
+        }
+    }
+}
+
+#[derive(Serialize, Queryable, Identifiable, Associations, Clone)]
 #[diesel(belongs_to(Channel, foreign_key = channel_id))]
 pub struct Event {
     pub id: i32,
@@ -223,6 +281,11 @@ pub struct Event {
     pub event_type: EventType,
     pub flags: Vec<EventFlag>,
     pub message: String,
+    pub announcement_color: Option<String>,
+    pub is_enabled: bool,
+    pub scheduled_at: Option<NaiveDateTime>,
+    pub schedule_cron: Option<String>,
+    pub reminded_at: Option<NaiveDateTime>,
 }
 
 #[derive(Insertable)]
@@ -242,6 +305,7 @@ pub struct EventSubscription {
     pub id: i32,
     pub event_id: i32,
     pub user_id: i32,
+    pub channel_id: Option<i32>,
 }
 
 #[derive(Insertable)]
@@ -249,6 +313,57 @@ pub struct EventSubscription {
 pub struct NewEventSubscription {
     pub event_id: i32,
     pub user_id: i32,
+    pub channel_id: Option<i32>,
+}
+
+#[derive(Serialize, diesel_derive_enum::DbEnum, Debug, PartialEq, Clone)]
+#[ExistingTypePath = "crate::schema::sql_types::EventRunSource"]
+pub enum EventRunSource {
+    Stream,
+    Command,
+    Schedule,
+    Api,
+}
+
+#[derive(Serialize, Queryable, Identifiable, Associations)]
+#[diesel(belongs_to(Event, foreign_key = event_id))]
+pub struct EventRun {
+    pub id: i32,
+    pub event_id: i32,
+    pub source: EventRunSource,
+    pub parameters: Vec<String>,
+    pub notified_count: i32,
+    pub message_ids: Vec<String>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = event_runs)]
+pub struct NewEventRun {
+    pub event_id: i32,
+    pub source: EventRunSource,
+    pub parameters: Vec<String>,
+    pub notified_count: i32,
+    pub message_ids: Vec<String>,
+}
+
+#[derive(Serialize, Queryable, Identifiable, Associations)]
+#[diesel(belongs_to(Event, foreign_key = event_id))]
+#[diesel(belongs_to(User, foreign_key = user_id))]
+pub struct EventTrigger {
+    pub id: i32,
+    pub event_id: i32,
+    pub user_id: i32,
+    pub parameters: Vec<String>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = event_triggers)]
+pub struct NewEventTrigger {
+    pub event_id: i32,
+    pub user_id: i32,
+    pub parameters: Vec<String>,
 }
 
 #[derive(Serialize, diesel_derive_enum::DbEnum, Debug, PartialEq, Clone, Eq, PartialOrd, Ord)]
@@ -260,6 +375,38 @@ pub enum LevelOfRights {
     Vip,
     Moderator,
     Broadcaster,
+    Admin,
+}
+
+impl FromStr for LevelOfRights {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "suspended" => Ok(Self::Suspended),
+            "subscriber" => Ok(Self::Subscriber),
+            "user" => Ok(Self::User),
+            "vip" => Ok(Self::Vip),
+            "moderator" => Ok(Self::Moderator),
+            "broadcaster" => Ok(Self::Broadcaster),
+            "admin" => Ok(Self::Admin),
+            _ => Err("Failed to parse a level of rights".to_string()),
+        }
+    }
+}
+
+impl Display for LevelOfRights {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Suspended => write!(f, "suspended"),
+            Self::Subscriber => write!(f, "subscriber"),
+            Self::User => write!(f, "user"),
+            Self::Vip => write!(f, "vip"),
+            Self::Moderator => write!(f, "moderator"),
+            Self::Broadcaster => write!(f, "broadcaster"),
+            Self::Admin => write!(f, "admin"),
+        }
+    }
 }
 
 #[derive(Serialize, Queryable, Identifiable, Associations, Clone)]
@@ -326,3 +473,302 @@ pub struct UserToken {
 pub struct NewUserToken {
     pub user_id: i32,
 }
+
+#[derive(Serialize, diesel_derive_enum::DbEnum, Debug, PartialEq, Clone)]
+#[ExistingTypePath = "crate::schema::sql_types::NotificationDelivery"]
+pub enum NotificationDelivery {
+    Chat,
+    Whisper,
+    Digest,
+}
+
+impl FromStr for NotificationDelivery {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "chat" => Ok(Self::Chat),
+            "whisper" => Ok(Self::Whisper),
+            "digest" => Ok(Self::Digest),
+            _ => Err("Failed to parse a notification delivery".to_string()),
+        }
+    }
+}
+
+impl Display for NotificationDelivery {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Chat => write!(f, "chat"),
+            Self::Whisper => write!(f, "whisper"),
+            Self::Digest => write!(f, "digest"),
+        }
+    }
+}
+
+#[derive(Serialize, Queryable, Identifiable, Associations, Clone)]
+#[diesel(belongs_to(User, foreign_key = user_id))]
+#[diesel(table_name = user_preferences)]
+pub struct UserPreference {
+    pub id: i32,
+    pub user_id: i32,
+    pub notification_delivery: NotificationDelivery,
+    /// IANA name of the user's time zone, e.g. `Europe/Moscow`.
+    pub time_zone: String,
+    pub quiet_hours_start: Option<i32>,
+    pub quiet_hours_end: Option<i32>,
+    pub is_muted: bool,
+    pub is_massping_opted_out: bool,
+}
+
+impl UserPreference {
+    /// Checks whether the UTC timestamp falls into the user's quiet hours.
+    pub fn is_quiet_at(&self, timestamp: NaiveDateTime) -> bool {
+        let (start, end) = match (self.quiet_hours_start, self.quiet_hours_end) {
+            (Some(start), Some(end)) => (start as u32, end as u32),
+            _ => return false,
+        };
+
+        // unknown zones fall back to UTC, so a bad value doesn't break the notifications
+        let time_zone = Tz::from_str(&self.time_zone).unwrap_or(Tz::UTC);
+        let hour = time_zone.from_utc_datetime(&timestamp).hour();
+
+        match start.cmp(&end) {
+            Ordering::Less => hour >= start && hour < end,
+            Ordering::Greater => hour >= start || hour < end,
+            Ordering::Equal => false,
+        }
+    }
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = user_preferences)]
+pub struct NewUserPreference {
+    pub user_id: i32,
+    pub notification_delivery: NotificationDelivery,
+}
+
+#[derive(Queryable, Identifiable, Associations)]
+#[diesel(belongs_to(User, foreign_key = user_id))]
+pub struct DigestNotification {
+    pub id: i32,
+    pub user_id: i32,
+    pub message: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = digest_notifications)]
+pub struct NewDigestNotification {
+    pub user_id: i32,
+    pub message: String,
+}
+
+#[derive(Serialize, Queryable, Identifiable, Associations, Clone)]
+#[diesel(belongs_to(Channel, foreign_key = channel_id))]
+pub struct EmoteUsage {
+    pub id: i32,
+    pub channel_id: i32,
+    pub provider: String,
+    pub emote_id: String,
+    pub emote_name: String,
+    pub usage_count: i32,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = emote_usages)]
+pub struct NewEmoteUsage {
+    pub channel_id: i32,
+    pub provider: String,
+    pub emote_id: String,
+    pub emote_name: String,
+    pub usage_count: i32,
+}
+
+#[derive(Serialize, Queryable, Identifiable, Associations, Clone)]
+#[diesel(belongs_to(Channel, foreign_key = channel_id))]
+#[diesel(belongs_to(User, foreign_key = user_id))]
+pub struct EmoteDailyUsage {
+    pub id: i32,
+    pub channel_id: i32,
+    pub user_id: i32,
+    pub provider: String,
+    pub emote_id: String,
+    pub emote_name: String,
+    pub day: NaiveDate,
+    pub usage_count: i32,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = emote_daily_usages)]
+pub struct NewEmoteDailyUsage {
+    pub channel_id: i32,
+    pub user_id: i32,
+    pub provider: String,
+    pub emote_id: String,
+    pub emote_name: String,
+    pub day: NaiveDate,
+    pub usage_count: i32,
+}
+
+#[derive(Serialize, diesel_derive_enum::DbEnum, Debug, PartialEq, Clone)]
+#[ExistingTypePath = "crate::schema::sql_types::EmoteEventAction"]
+pub enum EmoteEventAction {
+    Push,
+    Pull,
+    Update,
+}
+
+#[derive(Serialize, Queryable, Identifiable, Associations, Clone)]
+#[diesel(belongs_to(Channel, foreign_key = channel_id))]
+pub struct EmoteEvent {
+    pub id: i32,
+    pub channel_id: i32,
+    pub provider: String,
+    pub emote_id: String,
+    pub emote_name: String,
+    pub old_emote_name: Option<String>,
+    pub action: EmoteEventAction,
+    pub actor_name: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = emote_events)]
+pub struct NewEmoteEvent {
+    pub channel_id: i32,
+    pub provider: String,
+    pub emote_id: String,
+    pub emote_name: String,
+    pub old_emote_name: Option<String>,
+    pub action: EmoteEventAction,
+    pub actor_name: Option<String>,
+}
+
+#[derive(Serialize, diesel_derive_enum::DbEnum, Debug, PartialEq, Clone)]
+#[ExistingTypePath = "crate::schema::sql_types::BannedPhraseKind"]
+pub enum BannedPhraseKind {
+    Text,
+    Wildcard,
+    Regex,
+}
+
+impl FromStr for BannedPhraseKind {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Self::Text),
+            "wildcard" => Ok(Self::Wildcard),
+            "regex" => Ok(Self::Regex),
+            _ => Err("failed to serialize a str".to_string()),
+        }
+    }
+}
+
+impl BannedPhraseKind {
+    /// Compiles the pattern into a case-insensitive regex.
+    /// Wildcards match any characters with `*` and a single character with `?`.
+    pub fn compile(&self, pattern: &str) -> Result<Regex, regex::Error> {
+        let pattern = match self {
+            Self::Text => regex::escape(pattern),
+            Self::Wildcard => pattern
+                .split('*')
+                .map(|x| {
+                    x.split('?')
+                        .map(regex::escape)
+                        .collect::<Vec<String>>()
+                        .join(".")
+                })
+                .collect::<Vec<String>>()
+                .join(".*"),
+            Self::Regex => pattern.to_string(),
+        };
+
+        RegexBuilder::new(&pattern).case_insensitive(true).build()
+    }
+}
+
+impl Display for BannedPhraseKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Text => write!(f, "text"),
+            Self::Wildcard => write!(f, "wildcard"),
+            Self::Regex => write!(f, "regex"),
+        }
+    }
+}
+
+#[derive(Serialize, diesel_derive_enum::DbEnum, Debug, PartialEq, Clone)]
+#[ExistingTypePath = "crate::schema::sql_types::BannedPhraseAction"]
+pub enum BannedPhraseAction {
+    Delete,
+    Timeout,
+    Ban,
+}
+
+impl FromStr for BannedPhraseAction {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "delete" => Ok(Self::Delete),
+            "timeout" => Ok(Self::Timeout),
+            "ban" => Ok(Self::Ban),
+            _ => Err("failed to serialize a str".to_string()),
+        }
+    }
+}
+
+impl Display for BannedPhraseAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Delete => write!(f, "delete"),
+            Self::Timeout => write!(f, "timeout"),
+            Self::Ban => write!(f, "ban"),
+        }
+    }
+}
+
+#[derive(Serialize, Queryable, Identifiable, Associations, Clone)]
+#[diesel(belongs_to(Channel, foreign_key = channel_id))]
+pub struct BannedPhrase {
+    pub id: i32,
+    pub channel_id: i32,
+    pub pattern: String,
+    pub kind: BannedPhraseKind,
+    pub action: BannedPhraseAction,
+    pub timeout_sec: Option<i32>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = banned_phrases)]
+pub struct NewBannedPhrase {
+    pub channel_id: i32,
+    pub pattern: String,
+    pub kind: BannedPhraseKind,
+    pub action: BannedPhraseAction,
+    pub timeout_sec: Option<i32>,
+}
+
+#[derive(Serialize, Queryable, Identifiable, Associations, Clone)]
+#[diesel(belongs_to(Channel, foreign_key = channel_id))]
+#[diesel(belongs_to(User, foreign_key = user_id))]
+pub struct BannedPhraseOffense {
+    pub id: i32,
+    pub channel_id: i32,
+    pub user_id: i32,
+    pub phrase_id: Option<i32>,
+    pub action: BannedPhraseAction,
+    pub timeout_sec: Option<i32>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = banned_phrase_offenses)]
+pub struct NewBannedPhraseOffense {
+    pub channel_id: i32,
+    pub user_id: i32,
+    pub phrase_id: Option<i32>,
+    pub action: BannedPhraseAction,
+    pub timeout_sec: Option<i32>,
+}
@@ -5,10 +5,30 @@ pub mod sql_types {
     #[diesel(postgres_type(name = "action_statuses"))]
     pub struct ActionStatuses;
 
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "banned_phrase_action"))]
+    pub struct BannedPhraseAction;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "banned_phrase_kind"))]
+    pub struct BannedPhraseKind;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "channel_update_action"))]
+    pub struct ChannelUpdateAction;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "emote_event_action"))]
+    pub struct EmoteEventAction;
+
     #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
     #[diesel(postgres_type(name = "event_flag"))]
     pub struct EventFlag;
 
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "event_run_source"))]
+    pub struct EventRunSource;
+
     #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
     #[diesel(postgres_type(name = "event_type"))]
     pub struct EventType;
@@ -16,6 +36,10 @@ pub mod sql_types {
     #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
     #[diesel(postgres_type(name = "level_of_rights"))]
     pub struct LevelOfRights;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "notification_delivery"))]
+    pub struct NotificationDelivery;
 }
 
 diesel::table! {
@@ -35,6 +59,37 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::BannedPhraseAction;
+
+    banned_phrase_offenses (id) {
+        id -> Int4,
+        channel_id -> Int4,
+        user_id -> Int4,
+        phrase_id -> Nullable<Int4>,
+        action -> BannedPhraseAction,
+        timeout_sec -> Nullable<Int4>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::BannedPhraseKind;
+    use super::sql_types::BannedPhraseAction;
+
+    banned_phrases (id) {
+        id -> Int4,
+        channel_id -> Int4,
+        pattern -> Varchar,
+        kind -> BannedPhraseKind,
+        action -> BannedPhraseAction,
+        timeout_sec -> Nullable<Int4>,
+        created_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     channel_preferences (id) {
         id -> Int4,
@@ -42,6 +97,19 @@ diesel::table! {
         prefix -> Varchar,
         language -> Varchar,
         features -> Array<Nullable<Text>>,
+        massping_exclusions -> Array<Text>,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::ChannelUpdateAction;
+
+    channel_updates (id) {
+        id -> Int4,
+        channel_id -> Int4,
+        action -> ChannelUpdateAction,
+        created_at -> Timestamp,
     }
 }
 
@@ -52,6 +120,7 @@ diesel::table! {
         alias_name -> Varchar,
         joined_at -> Timestamp,
         opt_outed_at -> Nullable<Timestamp>,
+        purge_at -> Nullable<Timestamp>,
     }
 }
 
@@ -67,11 +136,88 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    digest_notifications (id) {
+        id -> Int4,
+        user_id -> Int4,
+        message -> Varchar,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    emote_daily_usages (id) {
+        id -> Int4,
+        channel_id -> Int4,
+        user_id -> Int4,
+        provider -> Varchar,
+        emote_id -> Varchar,
+        emote_name -> Varchar,
+        day -> Date,
+        usage_count -> Int4,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::EmoteEventAction;
+
+    emote_events (id) {
+        id -> Int4,
+        channel_id -> Int4,
+        provider -> Varchar,
+        emote_id -> Varchar,
+        emote_name -> Varchar,
+        old_emote_name -> Nullable<Varchar>,
+        action -> EmoteEventAction,
+        actor_name -> Nullable<Varchar>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    emote_usages (id) {
+        id -> Int4,
+        channel_id -> Int4,
+        provider -> Varchar,
+        emote_id -> Varchar,
+        emote_name -> Varchar,
+        usage_count -> Int4,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::EventRunSource;
+
+    event_runs (id) {
+        id -> Int4,
+        event_id -> Int4,
+        source -> EventRunSource,
+        parameters -> Array<Text>,
+        notified_count -> Int4,
+        message_ids -> Array<Text>,
+        created_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     event_subscriptions (id) {
         id -> Int4,
         event_id -> Int4,
         user_id -> Int4,
+        channel_id -> Nullable<Int4>,
+    }
+}
+
+diesel::table! {
+    event_triggers (id) {
+        id -> Int4,
+        event_id -> Int4,
+        user_id -> Int4,
+        parameters -> Array<Text>,
+        created_at -> Timestamp,
     }
 }
 
@@ -88,6 +234,11 @@ diesel::table! {
         event_type -> EventType,
         flags -> Array<EventFlag>,
         message -> Varchar,
+        announcement_color -> Nullable<Varchar>,
+        is_enabled -> Bool,
+        scheduled_at -> Nullable<Timestamp>,
+        schedule_cron -> Nullable<Varchar>,
+        reminded_at -> Nullable<Timestamp>,
     }
 }
 
@@ -134,6 +285,22 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::NotificationDelivery;
+
+    user_preferences (id) {
+        id -> Int4,
+        user_id -> Int4,
+        notification_delivery -> NotificationDelivery,
+        time_zone -> Varchar,
+        quiet_hours_start -> Nullable<Int4>,
+        quiet_hours_end -> Nullable<Int4>,
+        is_muted -> Bool,
+        is_massping_opted_out -> Bool,
+    }
+}
+
 diesel::table! {
     user_tokens (user_id) {
         user_id -> Int4,
@@ -149,33 +316,60 @@ diesel::table! {
         alias_name -> Varchar,
         joined_at -> Timestamp,
         opt_outed_at -> Nullable<Timestamp>,
+        is_admin -> Bool,
+        is_suspended -> Bool,
     }
 }
 
 diesel::joinable!(actions -> channels (channel_id));
 diesel::joinable!(actions -> users (user_id));
+diesel::joinable!(banned_phrase_offenses -> banned_phrases (phrase_id));
+diesel::joinable!(banned_phrase_offenses -> channels (channel_id));
+diesel::joinable!(banned_phrase_offenses -> users (user_id));
+diesel::joinable!(banned_phrases -> channels (channel_id));
 diesel::joinable!(channel_preferences -> channels (channel_id));
+diesel::joinable!(channel_updates -> channels (channel_id));
 diesel::joinable!(custom_commands -> channels (channel_id));
+diesel::joinable!(digest_notifications -> users (user_id));
+diesel::joinable!(emote_daily_usages -> channels (channel_id));
+diesel::joinable!(emote_daily_usages -> users (user_id));
+diesel::joinable!(emote_events -> channels (channel_id));
+diesel::joinable!(emote_usages -> channels (channel_id));
+diesel::joinable!(event_runs -> events (event_id));
+diesel::joinable!(event_subscriptions -> channels (channel_id));
 diesel::joinable!(event_subscriptions -> events (event_id));
 diesel::joinable!(event_subscriptions -> users (user_id));
+diesel::joinable!(event_triggers -> events (event_id));
+diesel::joinable!(event_triggers -> users (user_id));
 diesel::joinable!(events -> channels (channel_id));
 diesel::joinable!(rights -> channels (channel_id));
 diesel::joinable!(rights -> users (user_id));
 diesel::joinable!(sessions -> users (user_id));
 diesel::joinable!(timers -> channels (channel_id));
+diesel::joinable!(user_preferences -> users (user_id));
 diesel::joinable!(user_tokens -> users (user_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
     actions,
+    banned_phrase_offenses,
+    banned_phrases,
     channel_preferences,
+    channel_updates,
     channels,
     custom_commands,
+    digest_notifications,
+    emote_daily_usages,
+    emote_events,
+    emote_usages,
+    event_runs,
     event_subscriptions,
+    event_triggers,
     events,
     rights,
     session_states,
     sessions,
     timers,
+    user_preferences,
     user_tokens,
     users,
 );